@@ -1,41 +1,77 @@
 use phase1_coordinator::{
     authentication::{KeyPair, Production, Signature},
-    commands::{Computation, RandomSource, SEED_LENGTH},
+    commands::{
+        CircuitSet,
+        Computation,
+        ContributionProgress,
+        ContributionHeader,
+        ContributionStage,
+        Extraction,
+        Initialization,
+        RandomSource,
+        Verification,
+        SEED_LENGTH,
+    },
     io::{self, KeyPairUser},
-    objects::{ContributionFileSignature, ContributionInfo, ContributionState, TrimmedContributionInfo},
+    objects::{
+        ContributionFileSignature,
+        ContributionInfo,
+        ContributionReceipt,
+        ContributionState,
+        TrimmedContributionInfo,
+    },
     rest::{ContributorStatus, PostChunkRequest, TOKEN_REGEX, UPDATE_TIME},
-    storage::Object,
 };
 
 use reqwest::{Client, Url};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_stream::try_stream;
 use crossterm::{
     execute,
     terminal::{Clear, ClearType, ScrollDown},
 };
 use ed25519_compact::{KeyPair as EdKeyPair, Seed};
-use futures_util::StreamExt;
+use futures_util::{FutureExt, StreamExt};
 use phase1_cli::{
     ascii_logo::{ASCII_CONTRIBUTION_DONE, ASCII_LOGO},
-    keys::{self, EncryptedKeypair, TomlConfig},
-    requests, CeremonyOpt, CoordinatorUrl,
+    config::ContributionConfig,
+    genesis::{self, AllocationRules, Exclusion},
+    keys::{self, EncryptedKeypair, StoredKeypair, TomlConfig},
+    network,
+    output::{Event, OutputFormat, TransferReporter},
+    proof::ContributionProof,
+    requests::{self, RetryPolicy},
+    transcript::{self, SignatureStatus},
+    BeaconOpt,
+    BenchmarkOpt,
+    CeremonyOpt,
+    ContributionFiles,
+    Contributors,
+    CoordinatorUrl,
+    ExportKeypairOpt,
+    ExtractParamsOpt,
+    OutputOpt,
+    ProofPath,
+    ProveContributionOpt,
+    ReceiptPath,
+    TranscriptPath,
 };
 use serde_json;
 use setup_utils::calculate_hash;
 use structopt::StructOpt;
 
 use std::{
-    collections::HashMap,
+    collections::HashSet,
     fs::{self, File, OpenOptions},
-    io::Read,
+    io::BufWriter,
     sync::Arc,
     time::Instant,
 };
 
-use chrono::{Duration, Utc};
-use indicatif::{ProgressBar, ProgressStyle};
+use chrono::{DateTime, Duration, Utc};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use memmap::{Mmap, MmapOptions};
 use owo_colors::OwoColorize;
 
 use regex::Regex;
@@ -48,6 +84,8 @@ use tracing::{debug, trace};
 const OFFLINE_CONTRIBUTION_FILE_NAME: &str = "contribution.params";
 const OFFLINE_CHALLENGE_FILE_NAME: &str = "challenge.params";
 
+const RECEIPT_REQUEST_ATTEMPTS: usize = 10;
+
 const CUSTOM_SEED_MSG_NO: &str = "Enter a variable-length random string to be used as entropy in combination with your OS randomness.\nThis will generate the random seed that initializes the ChaCha random number generator.";
 const CUSTOM_SEED_MSG_YES: &str = "Provide your custom random seed to initialize the ChaCha random number generator.\nYou seed might come you from an external source of randomness like atmospheric noise, radioactive elements, lava lite etc. or an airgapped machine.";
 
@@ -68,10 +106,41 @@ macro_rules! pretty_hash {
     }};
 }
 
-/// Asks the user a few questions to properly setup the contribution
+/// Prints the beginning of a step of the contribution, or emits it as an event with the json output
+fn step(format: OutputFormat, step: u8, total: u8, description: &str) {
+    if format.is_json() {
+        Event::Step {
+            step,
+            total,
+            description,
+        }
+        .emit();
+    } else {
+        println!("{} {}", format!("[{}/{}]", step, total).bold().dimmed(), description);
+    }
+}
+
+/// Prints a message for the user, suppressed with the json output
+fn info<T: std::fmt::Display>(format: OutputFormat, msg: T) {
+    if !format.is_json() {
+        println!("{}", msg);
+    }
+}
+
+/// Asks the user a few questions to properly setup the contribution, unless the answers are provided in the config
 #[inline(always)]
-fn initialize_contribution() -> Result<ContributionInfo> {
+fn initialize_contribution(config: Option<&ContributionConfig>) -> Result<ContributionInfo> {
     let mut contrib_info = ContributionInfo::default();
+    if let Some(config) = config {
+        if config.incentivized {
+            contrib_info.full_name = config.full_name.clone();
+            contrib_info.email = config.email.clone();
+            contrib_info.is_incentivized = true;
+        }
+
+        return Ok(contrib_info);
+    }
+
     println!("{}","If you decide to participate in the incentivized trusted setup,\nyou will need to give your full name (first and last name) and your email address.\n(Your personal data is for internal use and won't be published publicly!)".bright_cyan());
     let incentivization = io::get_user_input(
         "Do you want to participate in the incentivized trusted setup? [y/n]".bright_yellow(),
@@ -95,24 +164,20 @@ fn initialize_contribution() -> Result<ContributionInfo> {
     Ok(contrib_info)
 }
 
+/// Memory maps the given file, so that its content is loaded lazily by the OS instead of being copied in memory
 #[inline(always)]
-fn get_file_as_byte_vec(filename: &str, round_height: u64, contribution_id: u64) -> Result<Vec<u8>> {
-    let mut f = File::open(filename)?;
-    let metadata = fs::metadata(filename)?;
-
-    let anoma_file_size: u64 = Object::anoma_contribution_file_size(round_height, contribution_id);
-    let mut buffer = vec![0; anoma_file_size as usize];
-    debug!(
-        "namada_contribution_file_size: round_height {}, contribution_id {}",
-        round_height, contribution_id
-    );
-    debug!("metadata file length {}", metadata.len());
-    f.read(&mut buffer)?;
+fn map_file(filename: &str) -> Result<Mmap> {
+    let file = File::open(filename)?;
+    let map = unsafe { MmapOptions::new().map(&file)? };
 
-    Ok(buffer)
+    Ok(map)
 }
 
-fn get_progress_bar(len: u64) -> ProgressBar {
+fn get_progress_bar(format: OutputFormat, len: u64) -> ProgressBar {
+    if format.is_json() {
+        return ProgressBar::hidden();
+    }
+
     let progress_bar = ProgressBar::new(len);
     progress_bar.set_style(
         ProgressStyle::default_bar()
@@ -123,9 +188,50 @@ fn get_progress_bar(len: u64) -> ProgressBar {
     progress_bar
 }
 
+/// Formats the time left before the lock deadline
+fn time_left(deadline: DateTime<Utc>) -> String {
+    let left = deadline - Utc::now();
+
+    if left <= Duration::zero() {
+        String::from("The lock deadline has passed!")
+    } else {
+        format!(
+            "{:02}:{:02} left before the lock deadline",
+            left.num_minutes(),
+            left.num_seconds() % 60
+        )
+    }
+}
+
+/// Spawns a thread refreshing the given bar with the time left before the lock deadline, until the bar is finished
+fn start_countdown(bar: ProgressBar, deadline: DateTime<Utc>) -> std::thread::JoinHandle<()> {
+    bar.set_style(ProgressStyle::default_spinner().template("{spinner} {msg}"));
+
+    std::thread::spawn(move || {
+        while !bar.is_finished() {
+            bar.set_message(time_left(deadline));
+            bar.tick();
+            std::thread::sleep(std::time::Duration::from_secs(1));
+        }
+    })
+}
+
 /// Contest and offline execution branches
 #[inline(always)]
-fn compute_contribution_offline() -> Result<()> {
+fn compute_contribution_offline(format: OutputFormat, circuit_set: CircuitSet, deadline: DateTime<Utc>) -> Result<()> {
+    if format.is_json() {
+        Event::OfflineContributionRequired {
+            challenge_file: OFFLINE_CHALLENGE_FILE_NAME,
+            contribution_file: OFFLINE_CONTRIBUTION_FILE_NAME,
+            circuit_set,
+            deadline,
+        }
+        .emit();
+        std::io::stdin().read_line(&mut String::new())?;
+
+        return Ok(());
+    }
+
     // Print instructions to the user
     let mut msg = format!("{}\n\n", "Instructions".bold().underline().bright_cyan(),);
     msg.push_str(format!("{}",format!(
@@ -151,8 +257,8 @@ fn compute_contribution_offline() -> Result<()> {
         format!(
             "{}",
             format!(
-                "{:4}2) Execute the command \"cargo run --release --bin phase1 --features=cli contribute offline\"\n",
-                ""
+                "{:4}2) Execute the command \"cargo run --release --bin namada-ts --features cli contribute offline --circuit-set {}\"\n",
+                "", circuit_set
             )
             .as_str()
             .bright_cyan()
@@ -173,30 +279,75 @@ fn compute_contribution_offline() -> Result<()> {
     );
     println!("{}", msg.bright_cyan());
 
-    // Wait for the contribution file to be updated with randomness
-    // NOTE: we don't actually check for the timeout on the 15 minutes. If the user takes more time than allowed to produce the file we'll keep going on in the contribution, at the following request the Coordinator will reply with an error because ther contributor has been dropped out of the ceremony
-    io::get_user_input(
+    // Wait for the contribution file to be updated with randomness, showing the time left to the contributor
+    let countdown_bar = ProgressBar::new_spinner();
+    let countdown = start_countdown(countdown_bar.clone(), deadline);
+    let input = io::get_user_input(
         "When your contribution file is ready, press enter to upload it".bright_yellow(),
         None,
-    )?;
+    );
+    countdown_bar.finish();
+    countdown
+        .join()
+        .map_err(|_| anyhow::anyhow!("Countdown thread panicked"))?;
+    input?;
 
     Ok(())
 }
 
+/// Updates the progress bar of a circuit
+fn update_circuit_progress_bar(bar: &ProgressBar, stage: ContributionStage) {
+    if let ContributionStage::Done = stage {
+        bar.finish_with_message(stage_name(stage));
+    } else {
+        bar.inc(1);
+        bar.set_message(stage_name(stage));
+    }
+}
+
+/// Parses the custom random seed, given in hex
+fn parse_seed(seed_str: &str) -> Result<[u8; SEED_LENGTH]> {
+    let mut seed = [0u8; SEED_LENGTH];
+    hex::decode_to_slice(seed_str, &mut seed)
+        .map_err(|_| anyhow::anyhow!("The custom seed must be {} bytes in hexadecimal format", SEED_LENGTH))?;
+
+    Ok(seed)
+}
+
+/// Returns the name of a [`ContributionStage`]
+fn stage_name(stage: ContributionStage) -> &'static str {
+    match stage {
+        ContributionStage::Reading => "reading parameters",
+        ContributionStage::Contributing => "contributing",
+        ContributionStage::Writing => "writing parameters",
+        ContributionStage::Done => "done",
+    }
+}
+
 /// Computes randomness
-fn compute_contribution(custom_seed: bool, challenge: &[u8], filename: &str) -> Result<()> {
-    let rand_source = if custom_seed {
+fn compute_contribution(
+    format: OutputFormat,
+    circuit_set: CircuitSet,
+    parallel_circuits: usize,
+    custom_seed: bool,
+    challenge_filename: &str,
+    filename: &str,
+    deadline: Option<DateTime<Utc>>,
+    config: Option<&ContributionConfig>,
+) -> Result<()> {
+    let rand_source = if let Some(config) = config {
+        match (custom_seed, &config.seed) {
+            (true, Some(seed)) => RandomSource::Seed(parse_seed(seed)?),
+            (true, None) => anyhow::bail!("Missing seed in the config file"),
+            (false, _) => RandomSource::Entropy(config.entropy.clone().unwrap_or_default()),
+        }
+    } else if custom_seed {
         let seed_str = io::get_user_input(
             "Enter your custom random seed (64 characters / 32 bytes in hexadecimal format without a '0x' prefix):"
                 .bright_yellow(),
             Some(&Regex::new(r"^[[:xdigit:]]{64}$")?),
         )?;
-        let mut seed = [0u8; SEED_LENGTH];
-
-        for (i, val) in hex::decode(seed_str)?.into_iter().enumerate() {
-            seed[i] = val;
-        }
-        RandomSource::Seed(seed)
+        RandomSource::Seed(parse_seed(&seed_str)?)
     } else {
         let entropy = io::get_user_input(
             "Frenetically type a random string to be used as entropy:".bright_yellow(),
@@ -205,76 +356,298 @@ fn compute_contribution(custom_seed: bool, challenge: &[u8], filename: &str) ->
         RandomSource::Entropy(entropy)
     };
 
-    println!("Computation of your contribution in progress... This might take a couple of seconds...");
+    info(
+        format,
+        "Computation of your contribution in progress... This might take a few minutes...",
+    );
 
-    let writer = OpenOptions::new().append(true).open(filename)?;
+    let challenge = map_file(challenge_filename)?;
+    ContributionHeader::from_file(&challenge)
+        .and_then(|header| header.check_circuits(circuit_set))
+        .context("Invalid challenge file")?;
+    let writer = BufWriter::new(OpenOptions::new().append(true).open(filename)?);
 
-    #[cfg(debug_assertions)]
-    Computation::contribute_test_masp(challenge, writer, &rand_source);
-    #[cfg(not(debug_assertions))]
-    Computation::contribute_masp(challenge, writer, &rand_source);
+    // One progress bar per circuit, each one going through the reading, contributing and writing stages
+    let multi_progress = MultiProgress::new();
+    if format.is_json() {
+        multi_progress.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    let bars: Vec<ProgressBar> = circuit_set
+        .circuit_names()
+        .iter()
+        .map(|&circuit| {
+            let bar = multi_progress.add(ProgressBar::new(3));
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template("{prefix:>8} [{elapsed_precise}] {bar:20} {msg}")
+                    .progress_chars("#>-"),
+            );
+            bar.set_prefix(circuit);
+            bar.set_message("waiting");
+            bar.enable_steady_tick(1000);
+            bar
+        })
+        .collect();
+    let countdown = deadline.map(|deadline| {
+        let bar = multi_progress.add(ProgressBar::new_spinner());
+        (bar.clone(), start_countdown(bar, deadline))
+    });
+    let draw_handle = std::thread::spawn(move || multi_progress.join());
 
-    println!(
-        "{}",
+    let progress = |p: ContributionProgress| {
+        if format.is_json() {
+            Event::CircuitProgress {
+                circuit: p.circuit(),
+                stage: stage_name(p.stage),
+            }
+            .emit();
+        }
+        update_circuit_progress_bar(&bars[p.circuit_index], p.stage)
+    };
+    let result = Computation::contribute_circuits_with_progress(
+        circuit_set,
+        &challenge,
+        writer,
+        &rand_source,
+        parallel_circuits,
+        progress,
+    );
+    if result.is_err() {
+        // Release the progress bars of the circuits left unfinished
+        for bar in bars.iter().filter(|bar| !bar.is_finished()) {
+            bar.abandon();
+        }
+    }
+
+    if let Some((countdown_bar, countdown)) = countdown {
+        countdown_bar.finish();
+        countdown
+            .join()
+            .map_err(|_| anyhow::anyhow!("Countdown thread panicked"))?;
+    }
+    draw_handle
+        .join()
+        .map_err(|_| anyhow::anyhow!("Progress bars thread panicked"))??;
+    for (circuit, time) in result? {
+        info(format, format!("{} circuit contribution time: {:.2?}", circuit, time));
+    }
+
+    info(
+        format,
         "Randomness has been correctly produced in the target file"
             .green()
-            .bold()
+            .bold(),
     );
     Ok(())
 }
 
-/// Performs the contribution sequence. Returns the round height of the contribution.
+/// Contribution computed on top of the previous contribution of the ceremony while it was being verified.
+struct SpeculativeContribution {
+    round_height: u64,
+    challenge_hash: Vec<u8>,
+    challenge_filename: String,
+    contrib_filename: String,
+    start_computation: DateTime<Utc>,
+    end_computation: DateTime<Utc>,
+}
+
+impl SpeculativeContribution {
+    /// Returns `true` if the contribution has been computed on the given challenge of the given round.
+    fn is_built_on(&self, round_height: u64, challenge_hash: &[u8]) -> bool {
+        self.round_height == round_height && self.challenge_hash == challenge_hash
+    }
+
+    /// Removes the files of a contribution computed on an outdated challenge.
+    async fn discard(self) {
+        for filename in [&self.challenge_filename, &self.contrib_filename].iter() {
+            if let Err(e) = async_fs::remove_file(filename).await {
+                debug!("Couldn't remove {}: {}", filename, e);
+            }
+        }
+    }
+}
+
+/// Downloads a challenge from Amazon S3 to the given file.
+async fn download_challenge(
+    format: OutputFormat,
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    challenge_url: &str,
+    challenge_filename: &str,
+) -> Result<()> {
+    let mut challenge_stream = requests::get_challenge(client, retry_policy, challenge_url).await?;
+    let mut challenge_writer = async_fs::File::create(challenge_filename).await?;
+    let progress_bar = get_progress_bar(format, challenge_stream.1);
+    let mut reporter = TransferReporter::new("download", challenge_stream.1, format);
+    while let Some(b) = challenge_stream.0.next().await {
+        let b = b?;
+        challenge_writer.write_all(&b).await?;
+        progress_bar.inc(b.len() as u64);
+        reporter.inc(b.len() as u64);
+    }
+    challenge_writer.flush().await?;
+    progress_bar.finish();
+
+    Ok(())
+}
+
+/// Hashes the challenge file through a memory map, without loading it in memory
+async fn hash_challenge(challenge_filename: Arc<String>) -> Result<Vec<u8>> {
+    let challenge_hash = tokio::task::spawn_blocking(move || -> Result<_> {
+        let challenge = map_file(challenge_filename.as_str())?;
+        debug!("Challenge length {}", challenge.len());
+
+        Ok(calculate_hash(&challenge))
+    })
+    .await??;
+    debug!("Challenge hash is {}", pretty_hash!(&challenge_hash));
+
+    Ok(challenge_hash.to_vec())
+}
+
+/// Computes the contribution on top of the previous contribution while the coordinator verifies it, so that the
+/// contributor doesn't wait for the verification to start computing. The randomness is taken from the config file,
+/// since the computation may be discarded. Returns `None` if the coordinator doesn't provide the challenge, because the
+/// contributor is not the next one or the previous contribution has already been verified.
+async fn speculate(
+    format: OutputFormat,
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator: &Url,
+    keypair: &KeyPair,
+    contrib_info: &ContributionInfo,
+    config: Option<Arc<ContributionConfig>>,
+    parallel_circuits: usize,
+) -> Result<Option<SpeculativeContribution>> {
+    let speculative_challenge =
+        match requests::get_speculative_challenge_url(client, retry_policy, coordinator, keypair).await {
+            Ok(speculative_challenge) => speculative_challenge,
+            Err(e) => {
+                debug!("No speculative challenge available: {}", e);
+                return Ok(None);
+            }
+        };
+    let round_height = speculative_challenge.round_height;
+    let circuit_set = requests::get_circuit_set(client, retry_policy, coordinator).await?;
+
+    if format.is_json() {
+        Event::SpeculativeComputation { round_height }.emit();
+    }
+    info(format, "The previous contribution is being verified: your contribution is computed on top of it in the meantime, and will be uploaded once the verification succeeds".bright_cyan());
+
+    let challenge_filename = Arc::new(format!("namada_speculative_challenge_round_{}.params", round_height));
+    download_challenge(
+        format,
+        client,
+        retry_policy,
+        speculative_challenge.url.as_str(),
+        challenge_filename.as_str(),
+    )
+    .await?;
+    let challenge_hash = hash_challenge(challenge_filename.clone()).await?;
+
+    let contrib_filename = Arc::new(format!(
+        "namada_speculative_contribution_round_{}_public_key_{}.params",
+        round_height,
+        keypair.pubkey()
+    ));
+    let mut response_writer = async_fs::File::create(contrib_filename.as_str()).await?;
+    response_writer.write_all(challenge_hash.as_ref()).await?;
+    response_writer.flush().await?;
+
+    let custom_seed = contrib_info.is_own_seed_of_randomness;
+    let challenge_filename_copy = challenge_filename.clone();
+    let contrib_filename_copy = contrib_filename.clone();
+    let start_computation = Utc::now();
+    tokio::task::spawn_blocking(move || {
+        compute_contribution(
+            format,
+            circuit_set,
+            parallel_circuits,
+            custom_seed,
+            challenge_filename_copy.as_str(),
+            contrib_filename_copy.as_str(),
+            None,
+            config.as_deref(),
+        )
+    })
+    .await??;
+
+    Ok(Some(SpeculativeContribution {
+        round_height,
+        challenge_hash,
+        challenge_filename: challenge_filename.to_string(),
+        contrib_filename: contrib_filename.to_string(),
+        start_computation,
+        end_computation: Utc::now(),
+    }))
+}
+
+/// Performs the contribution sequence, reusing the speculative contribution if it has been computed on the actual
+/// challenge. Returns the round height of the contribution.
 #[inline(always)]
 async fn contribute(
+    format: OutputFormat,
     client: &Client,
+    retry_policy: &RetryPolicy,
     coordinator: &Url,
     keypair: &KeyPair,
     mut contrib_info: ContributionInfo,
+    config: Option<Arc<ContributionConfig>>,
+    parallel_circuits: usize,
+    speculation: Option<SpeculativeContribution>,
     heartbeat_handle: &JoinHandle<()>,
 ) -> Result<u64> {
     // Get the necessary info to compute the contribution
-    println!("{} Locking chunk", "[4/11]".bold().dimmed());
-    let locked_locators = requests::get_lock_chunk(client, coordinator, keypair).await?;
+    let circuit_set = requests::get_circuit_set(client, retry_policy, coordinator).await?;
+    step(format, 4, 11, "Locking chunk");
+    let locked_chunk = requests::get_lock_chunk(client, retry_policy, coordinator, keypair).await?;
+    let locked_locators = locked_chunk.locked_locators;
     contrib_info.timestamps.challenge_locked = Utc::now();
-    let end_lock_time = contrib_info.timestamps.challenge_locked + Duration::minutes(20);
-    println!(
-        "{}",
-        format!("From now on, you will have a maximum of 20 minutes to contribute and upload your contribution after which you will be dropped out of the ceremony!\nYour time starts now on {} and ends in 20 minutes on {}  \nHave fun!",
+    let lock_timeout = Duration::seconds(locked_chunk.lock_timeout);
+    let end_lock_time = contrib_info.timestamps.challenge_locked + lock_timeout;
+    info(
+        format,
+        format!("From now on, you will have a maximum of {} minutes to contribute and upload your contribution after which you will be dropped out of the ceremony!\nYour time starts now on {} and ends in {} minutes on {}  \nHave fun!",
+        lock_timeout.num_minutes(),
         contrib_info.timestamps.challenge_locked.to_rfc2822(),
+        lock_timeout.num_minutes(),
         end_lock_time.to_rfc2822()).bright_cyan()
     );
     let response_locator = locked_locators.next_contribution();
     let round_height = response_locator.round_height();
     contrib_info.ceremony_round = round_height;
-
-    let challenge_url = requests::get_challenge_url(client, coordinator, keypair, &round_height).await?;
-    println!("{} Getting challenge", "[5/11]".bold().dimmed());
-    let mut challenge_stream = requests::get_challenge(client, challenge_url.as_str()).await?;
-    let progress_bar = get_progress_bar(challenge_stream.1);
-    let mut challenge: Vec<u8> = Vec::new();
-    while let Some(b) = challenge_stream.0.next().await {
-        let b = b?;
-        challenge.extend_from_slice(&b);
-        progress_bar.inc(b.len() as u64);
+    if format.is_json() {
+        Event::LockAcquired {
+            round_height,
+            deadline: end_lock_time,
+        }
+        .emit();
     }
-    progress_bar.finish();
-    contrib_info.timestamps.challenge_downloaded = Utc::now();
 
-    // Saves the challenge locally, in case the contributor is paranoid and wants to double check himself. It is also used in the offline contrib path
+    let challenge_url = requests::get_challenge_url(client, retry_policy, coordinator, keypair, &round_height).await?;
+    step(format, 5, 11, "Getting challenge");
+
+    // Streams the challenge to disk, in case the contributor is paranoid and wants to double check himself. The file is then used for the computation and in the offline contrib path
     let challenge_filename = if contrib_info.is_another_machine {
-        OFFLINE_CHALLENGE_FILE_NAME.to_string()
+        Arc::new(OFFLINE_CHALLENGE_FILE_NAME.to_string())
     } else {
-        format!("namada_challenge_round_{}.params", round_height)
+        Arc::new(format!("namada_challenge_round_{}.params", round_height))
     };
-    let mut challenge_writer = async_fs::File::create(challenge_filename.as_str()).await?;
-    challenge_writer.write_all(&challenge.as_slice()).await?;
-
-    let challenge_hash = calculate_hash(challenge.as_ref());
-    debug!("Challenge hash is {}", pretty_hash!(&challenge_hash));
-    debug!("Challenge length {}", challenge.len());
+    download_challenge(
+        format,
+        client,
+        retry_policy,
+        challenge_url.as_str(),
+        challenge_filename.as_str(),
+    )
+    .await?;
+    contrib_info.timestamps.challenge_downloaded = Utc::now();
+    let challenge_hash = hash_challenge(challenge_filename.clone()).await?;
+    let challenge_size = async_fs::metadata(challenge_filename.as_str()).await?.len();
 
     // Prepare contribution file with the challege hash
-    println!("{} Setting up contribution file", "[6/11]".bold().dimmed());
+    step(format, 6, 11, "Setting up contribution file");
     let contrib_filename = if contrib_info.is_another_machine {
         Arc::new(OFFLINE_CONTRIBUTION_FILE_NAME.to_string())
     } else {
@@ -284,63 +657,117 @@ async fn contribute(
             keypair.pubkey()
         ))
     };
-    let mut response_writer = async_fs::File::create(contrib_filename.as_str()).await?;
-    response_writer.write_all(challenge_hash.to_vec().as_ref()).await?;
-
-    // Compute contribution
-    println!("{} Computing contribution", "[7/11]".bold().dimmed());
+    let speculation = match speculation {
+        Some(speculation) if speculation.is_built_on(round_height, &challenge_hash) => Some(speculation),
+        Some(speculation) => {
+            // The contribution the speculative computation is built on didn't pass the verification
+            if format.is_json() {
+                Event::SpeculativeComputationDiscarded {
+                    round_height: speculation.round_height,
+                }
+                .emit();
+            }
+            info(format, "The previous contribution changed since your contribution was computed on top of it: the computation has to be performed again".bright_cyan());
+            speculation.discard().await;
+            None
+        }
+        None => None,
+    };
 
-    let contrib_filename_copy = contrib_filename.clone();
-    contrib_info.timestamps.start_computation = Utc::now();
-    if contrib_info.is_another_machine {
-        tokio::task::spawn_blocking(move || compute_contribution_offline()).await??;
+    if let Some(speculation) = speculation {
+        // The challenge is the one the contribution has been computed on while the previous contribution was verified
+        step(format, 7, 11, "Reusing the contribution computed while waiting");
+        async_fs::rename(&speculation.contrib_filename, contrib_filename.as_str()).await?;
+        async_fs::remove_file(&speculation.challenge_filename).await?;
+        contrib_info.timestamps.start_computation = speculation.start_computation;
+        contrib_info.timestamps.end_computation = speculation.end_computation;
     } else {
-        let custom_seed = contrib_info.is_own_seed_of_randomness;
-        if custom_seed {
-            println!("{}", CUSTOM_SEED_MSG_YES.bright_cyan());
+        let mut response_writer = async_fs::File::create(contrib_filename.as_str()).await?;
+        response_writer.write_all(&challenge_hash).await?;
+
+        // Compute contribution
+        step(format, 7, 11, "Computing contribution");
+
+        let contrib_filename_copy = contrib_filename.clone();
+        contrib_info.timestamps.start_computation = Utc::now();
+        if contrib_info.is_another_machine {
+            tokio::task::spawn_blocking(move || compute_contribution_offline(format, circuit_set, end_lock_time))
+                .await??;
         } else {
-            println!("{}", CUSTOM_SEED_MSG_NO.bright_cyan());
+            let custom_seed = contrib_info.is_own_seed_of_randomness;
+            if config.is_none() {
+                if custom_seed {
+                    println!("{}", CUSTOM_SEED_MSG_YES.bright_cyan());
+                } else {
+                    println!("{}", CUSTOM_SEED_MSG_NO.bright_cyan());
+                }
+            }
+            tokio::task::spawn_blocking(move || {
+                compute_contribution(
+                    format,
+                    circuit_set,
+                    parallel_circuits,
+                    custom_seed,
+                    challenge_filename.as_str(),
+                    contrib_filename_copy.as_str(),
+                    Some(end_lock_time),
+                    config.as_deref(),
+                )
+            })
+            .await??;
         }
-        tokio::task::spawn_blocking(move || {
-            compute_contribution(custom_seed, challenge.as_ref(), contrib_filename_copy.as_str())
-        })
-        .await??;
+        contrib_info.timestamps.end_computation = Utc::now();
+        trace!("Response writer {:?}", response_writer);
     }
+
+    // Hash the contribution file through a memory map, without loading it in memory
     let contrib_filename_copy = contrib_filename.clone();
-    let contribution = tokio::task::spawn_blocking(move || {
-        get_file_as_byte_vec(
-            contrib_filename_copy.as_str(),
-            round_height,
-            response_locator.contribution_id(),
-        )
+    let challenge_hash_len = challenge_hash.len();
+    let (contribution_file_hash, contribution_hash) = tokio::task::spawn_blocking(move || -> Result<_> {
+        let contribution = map_file(contrib_filename_copy.as_str())?;
+        debug!(
+            "Contribution length: {}, expected {}",
+            contribution.len(),
+            circuit_set.response_file_size(challenge_size)
+        );
+        let contribution_params = contribution
+            .get(challenge_hash_len..)
+            .ok_or_else(|| anyhow::anyhow!("The contribution file is too short"))?;
+
+        Ok((calculate_hash(&contribution), calculate_hash(contribution_params)))
     })
     .await??;
 
-    contrib_info.timestamps.end_computation = Utc::now();
-    trace!("Response writer {:?}", response_writer);
-    println!(
-        "{}",
+    info(
+        format,
         format!(
             "Completed contribution in {} seconds",
             (contrib_info.timestamps.end_computation - contrib_info.timestamps.start_computation).num_seconds()
         )
         .green()
-        .bold()
+        .bold(),
     );
 
     // Update contribution info
-    println!("{} Updating contribution info", "[8/11]".bold().dimmed());
-    let contribution_file_hash = calculate_hash(contribution.as_ref());
+    step(format, 8, 11, "Updating contribution info");
     let contribution_file_hash_str = hex::encode(contribution_file_hash);
     debug!("Contribution hash is {}", contribution_file_hash_str);
-    debug!("Contribution length: {}", contribution.len());
     contrib_info.contribution_file_hash = contribution_file_hash_str;
     contrib_info.contribution_file_signature =
         Production.sign(keypair.sigkey(), contrib_info.contribution_file_hash.as_str())?;
-    let challenge_hash_len = challenge_hash.len();
-    contrib_info.contribution_hash = hex::encode(calculate_hash(&contribution[challenge_hash_len..]));
+    contrib_info.contribution_hash = hex::encode(contribution_hash);
     contrib_info.contribution_hash_signature =
         Production.sign(keypair.sigkey(), contrib_info.contribution_hash.as_str())?;
+    if format.is_json() {
+        Event::ContributionComputed {
+            round_height,
+            contribution_hash: &contrib_info.contribution_hash,
+            contribution_file_hash: &contrib_info.contribution_file_hash,
+            seconds: (contrib_info.timestamps.end_computation - contrib_info.timestamps.start_computation)
+                .num_seconds(),
+        }
+        .emit();
+    }
 
     // Send contribution to the coordinator
     let contribution_state = ContributionState::new(challenge_hash.to_vec(), contribution_file_hash.to_vec(), None)?;
@@ -348,19 +775,29 @@ async fn contribute(
     let signature = Production.sign(keypair.sigkey(), &contribution_state.signature_message()?)?;
     let contribution_file_signature = ContributionFileSignature::new(signature, contribution_state)?;
 
+    // Once the lock deadline has passed the coordinator drops the contributor, so the contribution would be rejected anyway
+    if Utc::now() > end_lock_time {
+        anyhow::bail!(
+            "The lock deadline of {} has passed: your contribution won't be uploaded since the coordinator has dropped you from the round",
+            end_lock_time.to_rfc2822()
+        );
+    }
+
     let (contribution_url, contribution_signature_url) =
-        requests::get_contribution_url(client, coordinator, keypair, &round_height).await?;
-    println!("{} Uploading contribution", "[9/11]".bold().dimmed());
+        requests::get_contribution_url(client, retry_policy, coordinator, keypair, &round_height).await?;
+    step(format, 9, 11, "Uploading contribution");
     let contrib_file = async_fs::File::open(contrib_filename.as_str()).await?;
     let contrib_size = async_fs::metadata(contrib_filename.as_str()).await?.len();
     let mut stream = ReaderStream::new(contrib_file);
-    let pb = get_progress_bar(contrib_size);
+    let pb = get_progress_bar(format, contrib_size);
     let pb_clone = pb.clone();
+    let mut reporter = TransferReporter::new("upload", contrib_size, format);
 
     let contrib_stream = try_stream! {
         while let Some(b) = stream.next().await {
             let b = b?;
             pb.inc(b.len() as u64);
+            reporter.inc(b.len() as u64);
             yield b;
         }
     };
@@ -376,32 +813,44 @@ async fn contribute(
     .await?;
     pb_clone.finish();
     contrib_info.timestamps.end_contribution = Utc::now();
+    if format.is_json() {
+        Event::UploadDone { round_height }.emit();
+    }
 
     // Compute signature of contributor info
     contrib_info
         .try_sign(keypair)
-        .expect(&format!("{}", "Error while signing the contribution info".red().bold()));
+        .context("Error while signing the contribution info")?;
 
     // Write contribution info file and send it to the Coordinator
-    println!("{} Uploading contribution info", "[10/11]".bold().dimmed());
+    step(format, 10, 11, "Uploading contribution info");
     async_fs::write(
         format!("namada_contributor_info_round_{}.json", contrib_info.ceremony_round),
         &serde_json::to_vec(&contrib_info)?,
     )
     .await?;
-    requests::post_contribution_info(client, coordinator, keypair, &contrib_info).await?;
+    requests::post_contribution_info(client, retry_policy, coordinator, keypair, &contrib_info).await?;
 
     // Notify contribution to the coordinator for the verification
-    println!(
-        "{} Notifying the coordinator of your uploaded contribution.\nYour contribution is being processed... This might take a minute...",
-        "[11/11]".bold().dimmed()
+    step(
+        format,
+        11,
+        11,
+        "Notifying the coordinator of your uploaded contribution.\nYour contribution is being processed... This might take a minute...",
     );
     let post_chunk_req = PostChunkRequest::new(
         round_height,
         locked_locators.next_contribution(),
         locked_locators.next_contribution_file_signature(),
     );
-    requests::post_contribute_chunk(client, coordinator, keypair, &post_chunk_req).await?;
+    if let Err(e) = requests::post_contribute_chunk(client, retry_policy, coordinator, keypair, &post_chunk_req).await {
+        // The notification can't be safely resubmitted: if the coordinator already received it before the failure,
+        // the contributor has been moved among the finished ones and the contribution is being verified
+        match requests::get_contributor_queue_status(client, retry_policy, coordinator, keypair).await {
+            Ok(ContributorStatus::Finished) => debug!("Contribution already received by the coordinator: {}", e),
+            _ => return Err(e.into()),
+        }
+    }
 
     // Interrupt heartbeat, to prevent heartbeating during verification
     // NOTE: need to manually cancel the heartbeat task because, by default, async runtimes use detach on drop strategy
@@ -414,25 +863,66 @@ async fn contribute(
     Ok(round_height)
 }
 
+/// Retrieves the receipt of the contribution signed by the coordinator and saves it next to the contributor info file.
+/// Returns the name of the receipt file.
+///
+/// The receipt is issued only once the contribution has been verified, so the request is retried a few times.
+async fn save_contribution_receipt(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator: &Url,
+    keypair: &KeyPair,
+    round_height: u64,
+) -> Result<String> {
+    let mut attempts = 0;
+
+    let receipt = loop {
+        match requests::get_contribution_receipt(client, retry_policy, coordinator, keypair, &round_height).await {
+            Ok(receipt) => break receipt,
+            Err(e) => {
+                attempts += 1;
+                if attempts == RECEIPT_REQUEST_ATTEMPTS {
+                    return Err(e.into());
+                }
+                debug!("Contribution receipt not available yet: {}", e);
+                time::sleep(UPDATE_TIME).await;
+            }
+        }
+    };
+
+    let receipt_filename = format!("namada_contribution_receipt_round_{}.json", round_height);
+    async_fs::write(&receipt_filename, &serde_json::to_vec(&receipt)?).await?;
+
+    Ok(receipt_filename)
+}
+
 /// Waits in line until it's time to contribute
 #[inline(always)]
 async fn contribution_loop(
+    format: OutputFormat,
     client: Arc<Client>,
+    retry_policy: RetryPolicy,
     coordinator: Arc<Url>,
     keypair: Arc<KeyPair>,
     mut contrib_info: ContributionInfo,
-) {
-    println!("{} Joining queue", "[3/11]".bold().dimmed());
-    println!("{}","You can only join the ceremony either with the unique token you received by email for your cohort,\nor the FFA (Free For All) token available to everybody towards the end of the ceremony.\nExample token: 'b19271c0e0754cb7d31d'".bright_cyan());
-    let token = io::get_user_input(
-        "Enter your unique token or the FFA token (20 characters in hexadecimal format):".bright_yellow(),
-        Some(&Regex::new(TOKEN_REGEX).unwrap()),
-    )
-    .unwrap();
+    config: Option<Arc<ContributionConfig>>,
+    parallel_circuits: usize,
+) -> Result<()> {
+    step(format, 3, 11, "Joining queue");
+    let token = match config.as_ref().and_then(|c| c.token.clone()) {
+        Some(token) => token,
+        None => {
+            println!("{}","You can only join the ceremony either with the unique token you received by email for your cohort,\nor the FFA (Free For All) token available to everybody towards the end of the ceremony.\nExample token: 'b19271c0e0754cb7d31d'".bright_cyan());
+            io::get_user_input(
+                "Enter your unique token or the FFA token (20 characters in hexadecimal format):".bright_yellow(),
+                Some(&Regex::new(TOKEN_REGEX).unwrap()),
+            )?
+        }
+    };
 
-    requests::post_join_queue(&client, &coordinator, &keypair, &token)
+    requests::post_join_queue(&client, &retry_policy, &coordinator, &keypair, &token)
         .await
-        .expect(&format!("{}", "Couldn't join the queue".red().bold()));
+        .context("Couldn't join the queue")?;
     contrib_info.timestamps.joined_queue = Utc::now();
 
     // Spawn heartbeat task to prevent the Coordinator from
@@ -444,7 +934,7 @@ async fn contribution_loop(
 
     let heartbeat_handle = tokio::task::spawn(async move {
         loop {
-            if let Err(e) = requests::post_heartbeat(&client_cnt, &coordinator_cnt, &keypair_cnt).await {
+            if let Err(e) = requests::post_heartbeat(&client_cnt, &retry_policy, &coordinator_cnt, &keypair_cnt).await {
                 eprintln!(
                     "{}",
                     format!("{}: {}", "Heartbeat error".red().bold(), e.to_string().red().bold())
@@ -457,10 +947,12 @@ async fn contribution_loop(
     let mut round_height = 0;
     let mut status_count = 1;
     let queue_timer = Instant::now();
+    let mut speculation = None;
+    let mut speculation_handle: Option<JoinHandle<Result<Option<SpeculativeContribution>>>> = None;
 
-    let init_queue_status = requests::get_contributor_queue_status(&client, &coordinator, &keypair)
+    let init_queue_status = requests::get_contributor_queue_status(&client, &retry_policy, &coordinator, &keypair)
         .await
-        .expect(&format!("{}", "Couldn't get the status of contributor".red().bold()));
+        .context("Couldn't get the status of contributor")?;
     let mut init_queue_position = 0;
     match init_queue_status {
         ContributorStatus::Queue(position, _) => {
@@ -471,11 +963,14 @@ async fn contribution_loop(
 
     loop {
         // Check the contributor's position in the queue
-        let queue_status = requests::get_contributor_queue_status(&client, &coordinator, &keypair)
+        let queue_status = requests::get_contributor_queue_status(&client, &retry_policy, &coordinator, &keypair)
             .await
-            .expect(&format!("{}", "Couldn't get the status of contributor".red().bold()));
+            .context("Couldn't get the status of contributor")?;
 
         match queue_status {
+            ContributorStatus::Queue(position, size) if format.is_json() => {
+                Event::QueuePosition { position, size }.emit();
+            }
             ContributorStatus::Queue(position, size) => {
                 let msg = format!(
                     "Queue position: {}\nQueue size: {}\nExpected waiting time: {} min\nMax waiting time: {} min\nElapsed time in queue: {} min",
@@ -491,7 +986,7 @@ async fn contribution_loop(
 
                 if status_count > 1 {
                     // Clear previous status from terminal
-                    execute!(std::io::stdout(), ScrollDown(8), Clear(ClearType::FromCursorDown)).unwrap();
+                    execute!(std::io::stdout(), ScrollDown(8), Clear(ClearType::FromCursorDown))?;
                 }
                 println!(
                     "{}{}\n{}\n{}\n{}",
@@ -499,58 +994,184 @@ async fn contribution_loop(
                 );
                 status_count += 1;
             }
+            ContributorStatus::Waiting if format.is_json() => Event::WaitingVerification.emit(),
+            ContributorStatus::Waiting => println!(
+                "{}",
+                "Waiting for the verification of the previous contribution of the round...".bright_cyan()
+            ),
             ContributorStatus::Round => {
-                round_height = contribute(&client, &coordinator, &keypair, contrib_info.clone(), &heartbeat_handle)
-                    .await
-                    .expect(&format!("{}", "Contribution failed".red().bold()));
+                // The speculative computation, if still running, is the one needed to contribute
+                if let Some(handle) = speculation_handle.take() {
+                    speculation = speculation_outcome(handle.await);
+                }
+                round_height = contribute(
+                    format,
+                    &client,
+                    &retry_policy,
+                    &coordinator,
+                    &keypair,
+                    contrib_info.clone(),
+                    config.clone(),
+                    parallel_circuits,
+                    speculation.take(),
+                    &heartbeat_handle,
+                )
+                .await
+                .context("Contribution failed")?;
             }
             ContributorStatus::Finished => {
                 let content = fs::read(&format!("namada_contributor_info_round_{}.json", round_height))
-                    .expect(&format!("{}", "Couldn't read the contributor info file".red().bold()));
-                let contrib_info: ContributionInfo = serde_json::from_slice(&content).unwrap();
-
-                println!("{}\n{}\n\nI've contributed to @namadanetwork Trusted Setup Ceremony at round #{} with the contribution hash {}. Let's enable interchain privacy. #InterchainPrivacy{}\n\n",
-                                                "Done! Thank you for your contribution! If your contribution is valid, it will appear on namada.net. Check it out!".green().bold(),
-                                                "Share your attestation that proves your contribution to the world:".bright_cyan(),
-                                                round_height,
-                                contrib_info.contribution_hash,
-                format!("You also find all the metadata of your contribution (ceremony round, contribution hash, public key, timestamps etc.) in the \"namada_contributior_info_round_{}.json\"",round_height).as_str().bright_cyan()
-                                );
-                println!("{}", ASCII_CONTRIBUTION_DONE.bright_yellow());
+                    .context("Couldn't read the contributor info file")?;
+                let contrib_info: ContributionInfo = serde_json::from_slice(&content)?;
+
+                if !format.is_json() {
+                    println!("{}\n{}\n\nI've contributed to @namadanetwork Trusted Setup Ceremony at round #{} with the contribution hash {}. Let's enable interchain privacy. #InterchainPrivacy{}\n\n",
+                                                    "Done! Thank you for your contribution! If your contribution is valid, it will appear on namada.net. Check it out!".green().bold(),
+                                                    "Share your attestation that proves your contribution to the world:".bright_cyan(),
+                                                    round_height,
+                                    contrib_info.contribution_hash,
+                    format!("You also find all the metadata of your contribution (ceremony round, contribution hash, public key, timestamps etc.) in the \"namada_contributior_info_round_{}.json\"",round_height).as_str().bright_cyan()
+                                    );
+                    println!("{}", ASCII_CONTRIBUTION_DONE.bright_yellow());
+                }
+
+                let receipt =
+                    save_contribution_receipt(&client, &retry_policy, &coordinator, &keypair, round_height).await;
+                match &receipt {
+                    Ok(receipt_filename) => info(
+                        format,
+                        format!(
+                            "The receipt of your contribution, signed by the coordinator, has been saved to \"{}\". You can check it at any time with \"namada-ts check-receipt {}\"",
+                            receipt_filename, receipt_filename
+                        )
+                        .bright_cyan(),
+                    ),
+                    Err(e) => eprintln!(
+                        "{}",
+                        format!("Couldn't retrieve the receipt of your contribution: {}", e).red().bold()
+                    ),
+                }
+
+                if format.is_json() {
+                    Event::Finished {
+                        round_height,
+                        contribution_hash: &contrib_info.contribution_hash,
+                        receipt_file: receipt.as_ref().ok().map(String::as_str),
+                    }
+                    .emit();
+                }
 
                 break;
             }
             ContributorStatus::Banned => {
-                println!(
-                    "{}",
-                    "This contributor has been banned from the ceremony because of an invalid contribution."
-                        .red()
-                        .bold()
-                );
+                if format.is_json() {
+                    Event::Banned.emit();
+                } else {
+                    println!(
+                        "{}",
+                        "This contributor has been banned from the ceremony because of an invalid contribution."
+                            .red()
+                            .bold()
+                    );
+                }
                 break;
             }
             ContributorStatus::Other => {
-                println!("{}", "Did not retrieve a valid contributor state.".red().bold());
+                if format.is_json() {
+                    Event::Dropped.emit();
+                } else {
+                    println!("{}", "Did not retrieve a valid contributor state.".red().bold());
+                }
                 break;
             }
         }
 
+        // The contributor coming right after the latest contribution can compute on top of it during its verification,
+        // in the background while the queue status is polled. Only the randomness of the config file is used, since
+        // the computation may be discarded
+        if let Some(result) = speculation_handle.as_mut().and_then(|handle| handle.now_or_never()) {
+            speculation_handle = None;
+            speculation = speculation_outcome(result);
+        }
+        let is_next_contributor = matches!(
+            queue_status,
+            ContributorStatus::Queue(1, _) | ContributorStatus::Waiting
+        );
+        if is_next_contributor
+            && speculation.is_none()
+            && speculation_handle.is_none()
+            && config.is_some()
+            && !contrib_info.is_another_machine
+        {
+            let client = client.clone();
+            let coordinator = coordinator.clone();
+            let keypair = keypair.clone();
+            let contrib_info = contrib_info.clone();
+            let config = config.clone();
+            speculation_handle = Some(tokio::task::spawn(async move {
+                speculate(
+                    format,
+                    &client,
+                    &retry_policy,
+                    &coordinator,
+                    &keypair,
+                    &contrib_info,
+                    config,
+                    parallel_circuits,
+                )
+                .await
+            }));
+        }
+
         // Get status updates
         time::sleep(UPDATE_TIME).await;
     }
+
+    if let Some(handle) = speculation_handle {
+        handle.abort();
+    }
+
+    Ok(())
+}
+
+/// Returns the contribution computed in advance, reporting the failure of its computation.
+fn speculation_outcome(
+    result: std::result::Result<Result<Option<SpeculativeContribution>>, tokio::task::JoinError>,
+) -> Option<SpeculativeContribution> {
+    match result {
+        Ok(Ok(speculation)) => speculation,
+        Ok(Err(e)) => {
+            eprintln!(
+                "{}",
+                format!("Couldn't compute the contribution in advance: {}", e)
+                    .red()
+                    .bold()
+            );
+            None
+        }
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("The computation of the contribution in advance stopped: {}", e)
+                    .red()
+                    .bold()
+            );
+            None
+        }
+    }
 }
 
 #[inline(always)]
-async fn close_ceremony(client: &Client, coordinator: &Url, keypair: &KeyPair) {
-    match requests::get_stop_coordinator(client, coordinator, keypair).await {
+async fn close_ceremony(client: &Client, retry_policy: &RetryPolicy, coordinator: &Url, keypair: &KeyPair) {
+    match requests::get_stop_coordinator(client, retry_policy, coordinator, keypair).await {
         Ok(()) => println!("{}", "Ceremony completed!".green().bold()),
         Err(e) => eprintln!("{}", e.to_string().red().bold()),
     }
 }
 
 #[inline(always)]
-async fn get_contributions(coordinator: &Url) {
-    match requests::get_contributions_info(coordinator).await {
+async fn get_contributions(client: &Client, coordinator: &Url) {
+    match requests::get_contributions_info(client, coordinator).await {
         Ok(contributions) => {
             let contributions_str = std::str::from_utf8(&contributions).unwrap();
             println!("Contributions:\n{}", contributions_str)
@@ -559,10 +1180,400 @@ async fn get_contributions(coordinator: &Url) {
     }
 }
 
+/// Computes a contribution on a locally generated challenge and compares the time taken with the lock timeout
+fn benchmark(opt: BenchmarkOpt) -> Result<()> {
+    println!("Generating a local challenge... This might take a while...");
+    let start = Instant::now();
+    let challenge = Initialization::initial_challenge(opt.circuits.circuit_set, 0)?;
+    println!("Challenge generated in {:.2?}", start.elapsed());
+
+    println!("Computing a contribution on the local challenge...");
+    let start = Instant::now();
+    let mut response = calculate_hash(&challenge).to_vec();
+    let rand_source = RandomSource::Entropy(String::from("benchmark"));
+    let timings = Computation::contribute_circuits_with_progress(
+        opt.circuits.circuit_set,
+        &challenge,
+        &mut response,
+        &rand_source,
+        opt.parallelism.parallel_circuits,
+        |_| (),
+    )?;
+    let contribution_time = start.elapsed();
+
+    for (circuit, time) in &timings {
+        println!("{} circuit contribution time: {:.2?}", circuit, time);
+    }
+    println!("Total contribution time: {:.2?}", contribution_time);
+
+    // The lock timeout also includes the download of the challenge and the upload of the contribution
+    let lock_timeout = std::time::Duration::from_secs(opt.lock_timeout * 60);
+    if contribution_time > lock_timeout {
+        println!(
+            "{}",
+            format!(
+                "WARNING: the contribution took longer than the {} minutes allowed by the lock timeout. You might be dropped out of the ceremony, consider contributing from a more powerful machine",
+                opt.lock_timeout
+            )
+            .red()
+            .bold()
+        );
+    } else {
+        println!(
+            "{}",
+            format!(
+                "This machine can compute a contribution within the {} minutes lock timeout. Remember that the download of the challenge and the upload of the contribution are also part of it",
+                opt.lock_timeout
+            )
+            .green()
+            .bold()
+        );
+    }
+
+    Ok(())
+}
+
+/// Derives the keypair from a mnemonic and exports it to a Namada wallet file
+fn export_keypair(opt: ExportKeypairOpt) -> Result<()> {
+    let content = fs::read_to_string(&opt.mnemonic.path)?;
+    let seed = io::seed_from_string(content.as_str())?;
+
+    // Generate keypair and address
+    let keypair_struct = EdKeyPair::from_seed(Seed::from_slice(&seed[..32])?);
+    let address = keys::generate_address(&hex::encode(keypair_struct.pk.to_vec()));
+    let bech_address = keys::bech_encode_address_with_hrp(&address, &opt.hrp)
+        .map_err(|e| anyhow::anyhow!("Invalid human readable part {}: {}", opt.hrp, e))?;
+
+    let keypair = if opt.public_only {
+        None
+    } else if opt.unencrypted {
+        println!(
+            "{}",
+            "WARNING: the secret key will be stored in plain text, anyone with access to the wallet file can use it"
+                .bright_red()
+                .bold()
+        );
+        Some(StoredKeypair::raw(&keypair_struct))
+    } else {
+        let password = rpassword::prompt_password(
+            "Enter the password to encrypt the keypair. Make sure to safely store this password: ".bright_yellow(),
+        )?;
+        let confirmation = rpassword::prompt_password("Enter again the password to confirm: ".bright_yellow())?;
+        if confirmation != password {
+            anyhow::bail!("Passwords don't match!");
+        }
+
+        Some(StoredKeypair::Encrypted(EncryptedKeypair::from_keypair(
+            &keypair_struct,
+            password,
+        )))
+    };
+
+    let alias = match opt.alias {
+        Some(alias) => alias.to_lowercase(),
+        None => {
+            if "y" == io::get_user_input("Would you like to use a custom alias for your key? If not, the public key will be used as an alias [y/n]".bright_yellow(), Some(&Regex::new(r"^(?i)[yn]$")?))?.to_lowercase() {
+                io::get_user_input("Enter the alias:".bright_yellow(), None)?.to_lowercase()
+            } else {
+                address.to_lowercase()
+            }
+        }
+    };
+
+    // Write to toml file
+    let toml_config = TomlConfig::new(&alias, keypair, &bech_address, &address);
+    let content = if opt.append && opt.wallet.exists() {
+        let mut wallet: toml::value::Table = toml::from_str(&fs::read_to_string(&opt.wallet)?)?;
+        toml_config.append_to(&mut wallet)?;
+        toml::to_string(&wallet)?
+    } else {
+        toml::to_string(&toml_config)?
+    };
+    fs::write(&opt.wallet, content)?;
+
+    println!(
+        "Public key: {}\nPublic key hash: {}\nAddress: {}",
+        hex::encode(keypair_struct.pk.as_ref()),
+        address,
+        bech_address
+    );
+    if opt.append {
+        println!(
+            "{}",
+            format!("Keypair was correctly added to the \"{}\" file.", opt.wallet.display())
+                .bold()
+                .green()
+        );
+    } else {
+        println!("{}", format!("Keypair was correctly generated in the \"{}\" file. You can copy its content to the \"wallet.toml\" file. Refer to the Namada documentation on how to generate a wallet.", opt.wallet.display()).bold().green());
+    }
+
+    Ok(())
+}
+
+/// Recovers the contributor keypair from the mnemonic and produces a signed proof of the contribution found in the
+/// public contribution summary
+async fn prove_contribution(opt: ProveContributionOpt) -> Result<()> {
+    let content = fs::read_to_string(&opt.mnemonic.path)?;
+    let keypair = KeyPair::try_from_seed(&io::seed_from_string(&content)?)?;
+
+    let client = network::build_client(&opt.url.network, &opt.url.coordinator)?;
+    let summary = requests::get_contributions_info(&client, &opt.url.coordinator).await?;
+    let contributions: Vec<TrimmedContributionInfo> = serde_json::from_slice(&summary)?;
+    let contribution = contributions
+        .iter()
+        .find(|c| c.public_key() == keypair.pubkey())
+        .ok_or_else(|| anyhow::anyhow!("No contribution found for the public key {}", keypair.pubkey()))?;
+
+    let proof = ContributionProof::try_new(&keypair, contribution, &opt.hrp)?;
+    let proof_filename = format!("namada_contribution_proof_round_{}.json", proof.round_height);
+    fs::write(&proof_filename, serde_json::to_vec(&proof)?)?;
+
+    println!(
+        "{}",
+        format!(
+            "The proof of your contribution has been saved to \"{}\". It can be checked with \"namada-ts check-proof {}\"",
+            proof_filename, proof_filename
+        )
+        .green()
+        .bold()
+    );
+    println!(
+        "Round: {}\nPublic key: {}\nAddress: {}\nContribution hash: {}",
+        proof.round_height, proof.public_key, proof.address, proof.contribution_hash
+    );
+
+    Ok(())
+}
+
+/// Checks the signatures of a proof of contribution
+fn check_proof(proof_path: ProofPath) -> Result<()> {
+    let content = fs::read(&proof_path.path)?;
+    let proof: ContributionProof = serde_json::from_slice(&content)?;
+
+    if !proof.verify()? {
+        anyhow::bail!("Invalid proof of contribution");
+    }
+
+    println!("{}", "The proof is valid".green().bold());
+    println!(
+        "Round: {}\nPublic key: {}\nAddress: {}\nContribution hash: {}\nIssued on: {}",
+        proof.round_height,
+        proof.public_key,
+        proof.address,
+        proof.contribution_hash,
+        proof.timestamp.to_rfc2822()
+    );
+    println!(
+        "{}",
+        "Make sure that the contribution appears with the same hash in the public contribution summary".bright_cyan()
+    );
+
+    Ok(())
+}
+
+/// Generates the genesis allocations of the contributors
+fn generate_addresses(contributors: Contributors) -> Result<()> {
+    let mut rules = match (&contributors.rules, contributors.amount) {
+        (Some(path), _) => AllocationRules::load(path)?,
+        (None, Some(amount)) => AllocationRules::flat(genesis::DEFAULT_TOKEN.to_owned(), amount),
+        (None, None) => anyhow::bail!("Either --amount or --rules must be provided"),
+    };
+    if let Some(token) = contributors.token {
+        rules.token = token;
+    }
+    let excluded: HashSet<String> = match &contributors.exclude {
+        Some(path) => fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_owned)
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    let contributions = match (&contributors.contributors_dir, &contributors.path) {
+        (Some(dir), _) => genesis::read_contributions(dir)?,
+        (None, Some(path)) => genesis::read_summary(path)?,
+        (None, None) => anyhow::bail!("Either --path or --contributors-dir must be provided"),
+    };
+    let allocations = genesis::generate_allocations(&contributions, &rules, &excluded, &contributors.hrp)?;
+
+    for (public_key, reason) in &allocations.excluded {
+        let reason = match reason {
+            Exclusion::Excluded => "in the exclusion list",
+            Exclusion::InvalidSignature => "invalid signature",
+        };
+        println!("{}", format!("Skipped {}: {}", public_key, reason).yellow());
+    }
+
+    fs::write(&contributors.output, allocations.to_genesis_toml(&rules.token)?)?;
+    println!(
+        "{}",
+        format!(
+            "{} addresses were correctly generated in the {:?} file.",
+            allocations.balances.len(),
+            contributors.output
+        )
+        .bold()
+        .green()
+    );
+
+    Ok(())
+}
+
+/// Verifies a response file against its challenge and prints the contribution hashes
+fn verify_contribution(files: ContributionFiles) -> Result<()> {
+    let challenge = fs::read(&files.challenge)?;
+    let response = fs::read(&files.response)?;
+
+    let verified = Verification::transform_pok_and_correctness(files.circuits.circuit_set, &challenge, &response)
+        .map_err(|e| anyhow::anyhow!("Invalid contribution: {}", e))?;
+
+    println!("{}", "The contribution is valid".green().bold());
+    println!("Challenge hash: {}", pretty_hash!(&calculate_hash(&challenge)));
+    println!("Ceremony id: {}", verified.header.ceremony_id);
+    println!("Contribution file hash: {}", hex::encode(&verified.response_hash));
+    println!("Contribution hash: {}", hex::encode(calculate_hash(&response[64..])));
+    for (circuit, hash) in &verified.circuit_hashes {
+        println!("{} circuit contribution hash: {}", circuit, pretty_hash!(hash));
+    }
+    println!("MPC contribution hash: {}", pretty_hash!(&verified.contribution_hash()));
+
+    Ok(())
+}
+
+/// Applies the random beacon on top of the last challenge of the transcript
+fn apply_beacon(opt: BeaconOpt) -> Result<()> {
+    println!("Applying the random beacon... This might take a while...");
+    let beacon = transcript::apply_beacon(&opt.path, opt.circuits.circuit_set, &opt.beacon)?;
+
+    println!(
+        "{}",
+        format!("The random beacon has been applied in round {}", beacon.round_height)
+            .green()
+            .bold()
+    );
+    println!("Beacon: {}", beacon.value);
+    println!("SHA-256 iterations: {}", beacon.iterations);
+    println!("Seed: {}", hex::encode(beacon.seed()?));
+
+    Ok(())
+}
+
+/// Writes the Groth16 parameters of the final contribution file to the .params files and prints their hashes
+fn extract_params(opt: ExtractParamsOpt) -> Result<()> {
+    let file = fs::read(&opt.path)?;
+    let parameters = Extraction::run(opt.circuits.circuit_set, &file).context("Invalid contribution file")?;
+
+    fs::create_dir_all(&opt.output)?;
+    for circuit in &parameters {
+        let path = opt.output.join(circuit.file_name());
+        fs::write(&path, &circuit.bytes)?;
+        println!("{}: {}", path.display(), hex::encode(&circuit.hash));
+    }
+    println!(
+        "{}",
+        format!(
+            "The parameters of {} circuits were correctly extracted",
+            parameters.len()
+        )
+        .green()
+        .bold()
+    );
+
+    Ok(())
+}
+
+/// Checks the coordinator signature of a contribution receipt and prints its content
+fn check_receipt(receipt_path: ReceiptPath) -> Result<()> {
+    let content = fs::read(&receipt_path.path)?;
+    let receipt: ContributionReceipt = serde_json::from_slice(&content)?;
+
+    // The receipt carries the key of its signer, so it only authenticates the coordinator if that key is the expected one
+    if receipt_path.coordinator_pubkey != receipt.coordinator_public_key {
+        anyhow::bail!(
+            "The receipt was issued by {} instead of the expected coordinator {}",
+            receipt.coordinator_public_key,
+            receipt_path.coordinator_pubkey
+        );
+    }
+
+    if !receipt.verify_signature()? {
+        anyhow::bail!("Invalid coordinator signature of the receipt");
+    }
+
+    println!("{}", "The receipt is valid".green().bold());
+    println!(
+        "Round: {}\nContributor: {}\nContribution hash: {}\nNext challenge hash: {}\nIssued on: {}\nCoordinator: {}",
+        receipt.round_height,
+        receipt.contributor_public_key,
+        receipt.contribution_hash,
+        receipt.next_challenge_hash,
+        receipt.timestamp.to_rfc2822(),
+        receipt.coordinator_public_key
+    );
+
+    Ok(())
+}
+
+/// Verifies the whole transcript and prints the list of contributions it contains
+fn verify_transcript(transcript_path: TranscriptPath) -> Result<()> {
+    let verified_rounds = transcript::verify_transcript(
+        &transcript_path.path,
+        transcript_path.circuits.circuit_set,
+        transcript_path.ceremony_id,
+        &transcript_path.coordinator_pubkey,
+        transcript_path.allow_missing_signatures,
+        transcript_path.expect_beacon,
+    )?;
+
+    let signature_status = |status: SignatureStatus| match status {
+        SignatureStatus::Valid => "valid",
+        SignatureStatus::Unchecked => "unchecked, the public key of the signer is unknown",
+        SignatureStatus::Missing => "missing",
+    };
+    for round in &verified_rounds {
+        if let Some(beacon) = &round.beacon {
+            println!(
+                "Round {} contribution {}\n\tRandom beacon: {} ({} SHA-256 iterations)\n\tContribution hash: {}\n\tMPC hash: {}",
+                round.round_height,
+                round.contribution_id,
+                beacon.value,
+                beacon.iterations,
+                round.contribution_hash,
+                round.mpc_hash
+            );
+            continue;
+        }
+        println!(
+            "Round {} contribution {}\n\tContributor: {}\n\tContribution hash: {}\n\tMPC hash: {}\n\tContributor signature: {}\n\tCoordinator signature: {}",
+            round.round_height,
+            round.contribution_id,
+            round.contributor.as_deref().unwrap_or("unknown"),
+            round.contribution_hash,
+            round.mpc_hash,
+            signature_status(round.contributor_signature),
+            signature_status(round.coordinator_signature)
+        );
+    }
+    println!(
+        "{}",
+        format!(
+            "The transcript is valid and contains {} contributions",
+            verified_rounds.len()
+        )
+        .green()
+        .bold()
+    );
+
+    Ok(())
+}
+
 #[cfg(debug_assertions)]
 #[inline(always)]
-async fn verify_contributions(client: &Client, coordinator: &Url, keypair: &KeyPair) {
-    match requests::get_verify_chunks(client, coordinator, keypair).await {
+async fn verify_contributions(client: &Client, retry_policy: &RetryPolicy, coordinator: &Url, keypair: &KeyPair) {
+    match requests::get_verify_chunks(client, retry_policy, coordinator, keypair).await {
         Ok(()) => println!("{}", "Verification of pending contributions completed".green().bold()),
         Err(e) => eprintln!("{}", e.to_string().red().bold()),
     }
@@ -570,8 +1581,8 @@ async fn verify_contributions(client: &Client, coordinator: &Url, keypair: &KeyP
 
 #[cfg(debug_assertions)]
 #[inline(always)]
-async fn update_coordinator(client: &Client, coordinator: &Url, keypair: &KeyPair) {
-    match requests::get_update(client, coordinator, keypair).await {
+async fn update_coordinator(client: &Client, retry_policy: &RetryPolicy, coordinator: &Url, keypair: &KeyPair) {
+    match requests::get_update(client, retry_policy, coordinator, keypair).await {
         Ok(()) => println!("{}", "Coordinator updated".green().bold()),
         Err(e) => eprintln!("{}", e.to_string().red().bold()),
     }
@@ -582,38 +1593,83 @@ enum Branch {
     Default(bool),
 }
 
+/// Builds the client used to communicate with the coordinator and the object store
+fn build_client(url: &CoordinatorUrl) -> Client {
+    network::build_client(&url.network, &url.coordinator).expect(&format!(
+        "{}",
+        "Error while configuring the network client".red().bold()
+    ))
+}
+
+/// Reports an error which stops the CLI and exits
+fn exit_with_error(format: OutputFormat, e: anyhow::Error) -> ! {
+    if format.is_json() {
+        Event::Error {
+            message: format!("{:#}", e),
+        }
+        .emit();
+    } else {
+        eprintln!("{}", format!("{:#}", e).red().bold());
+    }
+
+    std::process::exit(1);
+}
+
+/// Loads the config of a non-interactive contribution, if any
+fn load_config(output: &OutputOpt, custom_seed: bool) -> Result<Option<ContributionConfig>> {
+    match &output.config {
+        Some(path) => {
+            let config = ContributionConfig::load(path)?;
+            config.check_non_interactive(custom_seed)?;
+
+            Ok(Some(config))
+        }
+        None => Ok(None),
+    }
+}
 
 /// Performs the entire contribution cycle
 #[inline(always)]
-async fn contribution_prelude(url: CoordinatorUrl, branch: Branch) {
+async fn contribution_prelude(
+    format: OutputFormat,
+    url: CoordinatorUrl,
+    branch: Branch,
+    config: Option<ContributionConfig>,
+    parallel_circuits: usize,
+) -> Result<()> {
     // Check that the passed-in coordinator url is correct
-    let client = Client::new();
-    requests::ping_coordinator(&client, &url.coordinator).await.expect(&format!("{}", "ERROR: could not contact the Coordinator, please check the url you provided".red().bold()));
+    let client =
+        network::build_client(&url.network, &url.coordinator).context("Error while configuring the network client")?;
+    let retry_policy = RetryPolicy::from(&url.retry);
+    requests::ping_coordinator(&client, &retry_policy, &url.coordinator)
+        .await
+        .context("ERROR: could not contact the Coordinator, please check the url you provided")?;
 
-    println!("{}", ASCII_LOGO.bright_yellow());
-    println!("{}", "Welcome to the Namada Trusted Setup Ceremony!".bold());
+    info(format, ASCII_LOGO.bright_yellow());
+    info(format, "Welcome to the Namada Trusted Setup Ceremony!".bold());
 
     match branch {
-        Branch::AnotherMachine => println!(
+        Branch::AnotherMachine => info(format, format!(
             "{}\n{}",
             "DISCLAIMER".bright_red().underline().bold(),
             "The \"--another-machine\" flag is active.\nThis feature is designed for advanced users that want to run the computation of the parameters on another machine.\n".bright_red()
-        ),
-        Branch::Default(custom_seed) if custom_seed => println!(
+        )),
+        Branch::Default(custom_seed) if custom_seed => info(format, format!(
             "{}\n{}",
             "DISCLAIMER".bright_red().underline().bold(),
             "The \"--custom-seed\" flag is active.\nThis feature is designed for advanced users that want to give a custom random seed for the ChaCha RNG.\n".bright_red()
-        ),
+        )),
         _ => ()
     }
 
     // Contribute
-    println!("{} Initializing contribution", "[1/11]".bold().dimmed());
-    let mut contrib_info = tokio::task::spawn_blocking(initialize_contribution)
-        .await
-        .unwrap()
-        .expect(&format!("{}", "Error while initializing the contribution".red().bold()));
-    println!("{} Generating keypair", "[2/11]".bold().dimmed());
+    step(format, 1, 11, "Initializing contribution");
+    let config = config.map(Arc::new);
+    let config_copy = config.clone();
+    let mut contrib_info = tokio::task::spawn_blocking(move || initialize_contribution(config_copy.as_deref()))
+        .await?
+        .context("Error while initializing the contribution")?;
+    step(format, 2, 11, "Generating keypair");
 
     match branch {
         Branch::AnotherMachine => contrib_info.is_another_machine = true,
@@ -621,150 +1677,258 @@ async fn contribution_prelude(url: CoordinatorUrl, branch: Branch) {
         _ => (),
     }
 
-    if contrib_info.is_incentivized {
-        println!("{}\n{}", "IMPORTANT".bright_red().underline().bold(),
-        "You are participating in the incentivized trusted setup.\nThe mnemonic generated in the next step is the ONLY way to recover your keypair that will receive rewards in Namada at genesis.".bright_red());
-    } else {
-        println!(
-            "{}",
-            "The CLI will generate in the background a keypair that is used to interact with the coordinator."
-                .bright_cyan()
-        );
-    }
-    io::get_user_input("Press enter to generate a keypair".bright_yellow(), None).unwrap();
-    let user = if contrib_info.is_incentivized {
-        KeyPairUser::IncentivizedContributor
-    } else {
-        KeyPairUser::Contributor
+    let keypair = match config.as_ref().and_then(|c| c.mnemonic_path.clone()) {
+        Some(mnemonic_path) => {
+            let mnemonic = fs::read_to_string(&mnemonic_path)
+                .with_context(|| format!("Couldn't read the mnemonic file {:?}", mnemonic_path))?;
+            KeyPair::try_from_seed(&io::seed_from_string(&mnemonic)?)?
+        }
+        None if config.is_some() => io::generate_keypair(KeyPairUser::Contributor)?,
+        None => {
+            if contrib_info.is_incentivized {
+                println!("{}\n{}", "IMPORTANT".bright_red().underline().bold(),
+                "You are participating in the incentivized trusted setup.\nThe mnemonic generated in the next step is the ONLY way to recover your keypair that will receive rewards in Namada at genesis.".bright_red());
+            } else {
+                println!(
+                    "{}",
+                    "The CLI will generate in the background a keypair that is used to interact with the coordinator."
+                        .bright_cyan()
+                );
+            }
+            io::get_user_input("Press enter to generate a keypair".bright_yellow(), None)?;
+            let user = if contrib_info.is_incentivized {
+                KeyPairUser::IncentivizedContributor
+            } else {
+                KeyPairUser::Contributor
+            };
+            tokio::task::spawn_blocking(move || io::generate_keypair(user))
+                .await?
+                .context("Error while generating the keypair")?
+        }
     };
-    let keypair = tokio::task::spawn_blocking(move || io::generate_keypair(user))
-        .await
-        .unwrap()
-        .expect(&format!("{}", "Error while generating the keypair".red().bold()));
+    if format.is_json() {
+        Event::KeypairGenerated {
+            public_key: keypair.pubkey(),
+        }
+        .emit();
+    }
 
     contrib_info.timestamps.start_contribution = Utc::now();
     contrib_info.public_key = keypair.pubkey().to_string();
 
     contribution_loop(
+        format,
         Arc::new(client),
+        retry_policy,
         Arc::new(url.coordinator),
         Arc::new(keypair),
         contrib_info,
+        config,
+        parallel_circuits,
     )
-    .await;
+    .await
 }
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr, so that they don't get mixed with the json output
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
     let opt = CeremonyOpt::from_args();
 
     match opt {
         CeremonyOpt::Contribute(branch) => {
+            let format = branch.output_opt().output;
+
             match branch {
-                phase1_cli::Branches::AnotherMachine { url } => contribution_prelude(url, Branch::AnotherMachine).await,
-                phase1_cli::Branches::Default { url, custom_seed } => {
-                    contribution_prelude(url, Branch::Default(custom_seed)).await
+                phase1_cli::Branches::AnotherMachine { url, output } => {
+                    // The contribution is computed on the other machine, no circuit is processed on this one
+                    let result = match load_config(&output, false) {
+                        Ok(config) => contribution_prelude(format, url, Branch::AnotherMachine, config, 1).await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
+                        exit_with_error(format, e);
+                    }
+                }
+                phase1_cli::Branches::Default {
+                    url,
+                    output,
+                    custom_seed,
+                    parallelism,
+                } => {
+                    let result = match load_config(&output, custom_seed) {
+                        Ok(config) => {
+                            contribution_prelude(
+                                format,
+                                url,
+                                Branch::Default(custom_seed),
+                                config,
+                                parallelism.parallel_circuits,
+                            )
+                            .await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
+                        exit_with_error(format, e);
+                    }
                 }
-                phase1_cli::Branches::Offline { custom_seed } => {
+                phase1_cli::Branches::Offline {
+                    output,
+                    custom_seed,
+                    circuits,
+                    parallelism,
+                } => {
                     if custom_seed {
-                        println!(
+                        info(format, format!(
                     "{}\n{}",
                     "DISCLAIMER".bright_red().underline().bold(),
                     "The \"--custom-seed\" flag is active.\nThis feature is designed for advanced users that want to give a custom random seed for the ChaCha RNG.\n".bright_red()
-                );
+                ));
                     }
+                    let config = match output.config.as_deref().map(ContributionConfig::load).transpose() {
+                        Ok(config) => config,
+                        Err(e) => exit_with_error(format, e.into()),
+                    };
+
                     // Only compute randomness. It expects a file called challenge.params to be available in the cwd and already filled with the challenge bytes
-                    println!("{} Reading challenge", "[1/2]".bold().dimmed());
-                    let challenge = async_fs::read(OFFLINE_CHALLENGE_FILE_NAME)
-                        .await
-                        .expect(&format!("{}", "Couldn't read the challenge file".red().bold()));
+                    step(format, 1, 2, "Reading challenge");
+                    if !std::path::Path::new(OFFLINE_CHALLENGE_FILE_NAME).exists() {
+                        exit_with_error(format, anyhow::anyhow!("Couldn't read the challenge file"));
+                    }
 
-                    println!("{} Computing contribution", "[2/2]".bold().dimmed());
+                    step(format, 2, 2, "Computing contribution");
 
-                    if custom_seed {
-                        println!("{}", CUSTOM_SEED_MSG_YES.bright_cyan());
-                    } else {
-                        println!("{}", CUSTOM_SEED_MSG_NO.bright_cyan());
+                    if config.is_none() {
+                        if custom_seed {
+                            println!("{}", CUSTOM_SEED_MSG_YES.bright_cyan());
+                        } else {
+                            println!("{}", CUSTOM_SEED_MSG_NO.bright_cyan());
+                        }
                     }
-                    tokio::task::spawn_blocking(move || {
-                        compute_contribution(custom_seed, &challenge, OFFLINE_CONTRIBUTION_FILE_NAME)
+                    let result = tokio::task::spawn_blocking(move || {
+                        compute_contribution(
+                            format,
+                            circuits.circuit_set,
+                            parallelism.parallel_circuits,
+                            custom_seed,
+                            OFFLINE_CHALLENGE_FILE_NAME,
+                            OFFLINE_CONTRIBUTION_FILE_NAME,
+                            None,
+                            config.as_ref(),
+                        )
                     })
                     .await
-                    .unwrap()
-                    .expect(&format!("{}", "Error in computing randomness".red().bold()));
+                    .unwrap();
+                    if let Err(e) = result {
+                        exit_with_error(format, e.context("Error in computing randomness"));
+                    }
                 }
             }
         }
+        CeremonyOpt::Benchmark(opt) => {
+            let result = tokio::task::spawn_blocking(move || benchmark(opt))
+                .await
+                .expect(&format!("{}", "Error while running the benchmark".red().bold()));
+
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
+        }
         CeremonyOpt::CloseCeremony(url) => {
             let keypair = tokio::task::spawn_blocking(|| io::keypair_from_mnemonic())
                 .await
                 .unwrap()
                 .expect(&format!("{}", "Error while generating the keypair".red().bold()));
 
-            let client = Client::new();
-            close_ceremony(&client, &url.coordinator, &keypair).await;
+            let client = build_client(&url);
+            close_ceremony(&client, &RetryPolicy::from(&url.retry), &url.coordinator, &keypair).await;
         }
-        CeremonyOpt::ExportKeypair(mnemonic_path) => {
-            tokio::task::spawn_blocking(|| {
-                let content = fs::read_to_string(mnemonic_path.path).unwrap();
-                let seed = io::seed_from_string(content.as_str()).unwrap();
-
-                let password = rpassword::prompt_password("Enter the password to encrypt the keypair. Make sure to safely store this password: ".bright_yellow()).unwrap();
-                let confirmation = rpassword::prompt_password("Enter again the password to confirm: ".bright_yellow()).unwrap();
-                if confirmation != password {
-                    eprintln!(
-                        "{}",
-                        format!("{}", "Passwords don't match!".red().bold())
-                    );
-                }
+        CeremonyOpt::ApplyBeacon(opt) => {
+            let result = tokio::task::spawn_blocking(move || apply_beacon(opt))
+                .await
+                .expect(&format!("{}", "Error while applying the random beacon".red().bold()));
 
-                // Generate keypair and address
-                let keypair_struct = EdKeyPair::from_seed(Seed::from_slice(&seed[.. 32]).unwrap());
-                let keypair = EncryptedKeypair::from_keypair(&keypair_struct, password);
-                let address = keys::generate_address(&hex::encode(keypair_struct.pk.to_vec()));
-                let bech_address = keys::bech_encode_address(&address);
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
+        }
+        CeremonyOpt::ExportKeypair(opt) => {
+            let result = tokio::task::spawn_blocking(move || export_keypair(opt))
+                .await
+                .expect(&format!("{}", "Error while generating the keypair".red().bold()));
 
-                let alias = if "y" == io::get_user_input("Would you like to use a custom alias for your key? If not, the public key will be used as an alias [y/n]".bright_yellow(), Some(&Regex::new(r"^(?i)[yn]$").unwrap())).unwrap() {
-                    io::get_user_input("Enter the alias:".bright_yellow(), None).unwrap().to_lowercase()
-                } else {
-                    address.clone().to_lowercase()
-                };
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
+        }
+        CeremonyOpt::ExtractParams(opt) => {
+            let result = tokio::task::spawn_blocking(move || extract_params(opt))
+                .await
+                .expect(&format!("{}", "Error while extracting the parameters".red().bold()));
 
-                // Write to toml file
-                let toml_config  = TomlConfig::new(&alias, keypair, &bech_address, &address);
-                fs::write("keypair.toml", toml::to_string(&toml_config).unwrap()).unwrap();
-                println!("{}", "Keypair was correctly generated in the \"keypair.toml\" file. You can copy its content to the \"wallet.toml\" file. Refer to the Namada documentation on how to generate a wallet.".bold().green());
-            }).await.expect(&format!("{}", "Error while generating the keypair".red().bold()));
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
         }
         CeremonyOpt::GenerateAddresses(contributors) => {
-            tokio::task::spawn_blocking(move || {
-                let content = fs::read(&contributors.path).unwrap();
-                let contrib_info: Vec<TrimmedContributionInfo> = serde_json::from_slice(&content).unwrap();
-                let addresses: HashMap<String, u32> = contrib_info
-                    .iter()
-                    .map(|contrib| {
-                        (
-                            keys::bech_encode_address(&keys::generate_address(contrib.public_key())),
-                            contributors.amount,
-                        )
-                    })
-                    .collect();
+            let result = tokio::task::spawn_blocking(move || generate_addresses(contributors))
+                .await
+                .expect(&format!("{}", "Error while generating the addresses".red().bold()));
 
-                let content = ["[token.xan.balances]", &toml::to_string(&addresses).unwrap()].join("\n");
-                fs::write("genesis.toml", content).unwrap();
-                println!(
-                    "{}",
-                    "The addresses were correctly generated in the \"genesis.toml\" file."
-                        .bold()
-                        .green()
-                );
-            })
-            .await
-            .expect(&format!("{}", "Error while generating the addresses".red().bold()));
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
         }
         CeremonyOpt::GetContributions(url) => {
-            get_contributions(&url.coordinator).await;
+            let client = build_client(&url);
+            get_contributions(&client, &url.coordinator).await;
+        }
+        CeremonyOpt::VerifyContribution(files) => {
+            let result = tokio::task::spawn_blocking(move || verify_contribution(files))
+                .await
+                .expect(&format!("{}", "Error while verifying the contribution".red().bold()));
+
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
+        }
+        CeremonyOpt::CheckReceipt(receipt_path) => {
+            if let Err(e) = check_receipt(receipt_path) {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
+        }
+        CeremonyOpt::ProveContribution(opt) => {
+            if let Err(e) = prove_contribution(opt).await {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
+        }
+        CeremonyOpt::CheckProof(proof_path) => {
+            if let Err(e) = check_proof(proof_path) {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
+        }
+        CeremonyOpt::VerifyTranscript(transcript_path) => {
+            let result = tokio::task::spawn_blocking(move || verify_transcript(transcript_path))
+                .await
+                .expect(&format!("{}", "Error while verifying the transcript".red().bold()));
+
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().red().bold());
+                std::process::exit(1);
+            }
         }
         #[cfg(debug_assertions)]
         CeremonyOpt::VerifyContributions(url) => {
@@ -773,8 +1937,8 @@ async fn main() {
                 .unwrap()
                 .expect(&format!("{}", "Error while generating the keypair".red().bold()));
 
-            let client = Client::new();
-            verify_contributions(&client, &url.coordinator, &keypair).await;
+            let client = build_client(&url);
+            verify_contributions(&client, &RetryPolicy::from(&url.retry), &url.coordinator, &keypair).await;
         }
         #[cfg(debug_assertions)]
         CeremonyOpt::UpdateCoordinator(url) => {
@@ -783,8 +1947,8 @@ async fn main() {
                 .unwrap()
                 .expect(&format!("{}", "Error while generating the keypair".red().bold()));
 
-            let client = Client::new();
-            update_coordinator(&client, &url.coordinator, &keypair).await;
+            let client = build_client(&url);
+            update_coordinator(&client, &RetryPolicy::from(&url.retry), &url.coordinator, &keypair).await;
         }
     }
 }
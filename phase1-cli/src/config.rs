@@ -0,0 +1,83 @@
+//! Inputs of a non-interactive contribution.
+//!
+//! The answers which are normally asked to the contributor on the terminal can be provided in a toml file instead:
+//!
+//! ```toml
+//! token = "b19271c0e0754cb7d31d"
+//! incentivized = true
+//! full_name = "Jane Doe"
+//! email = "jane@doe.com"
+//! mnemonic_path = "mnemonic.txt"
+//! entropy = "a random string"
+//! ```
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Error returned while loading the [`ContributionConfig`].
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Io error on {0:?}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Invalid config file {0:?}: {1}")]
+    Toml(PathBuf, toml::de::Error),
+    #[error("Missing {0} in the config file, required for a non-interactive contribution")]
+    MissingField(&'static str),
+}
+
+/// Answers to the questions asked during the contribution.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContributionConfig {
+    /// The token to join the queue.
+    pub token: Option<String>,
+    /// Participate in the incentivized program.
+    #[serde(default)]
+    pub incentivized: bool,
+    /// Full name, required for the incentivized program.
+    pub full_name: Option<String>,
+    /// Email address, required for the incentivized program.
+    pub email: Option<String>,
+    /// Path to the file containing the mnemonic of the keypair, in the format printed by the CLI. Required for the
+    /// incentivized program, since the generated mnemonic is never printed in a non-interactive contribution.
+    pub mnemonic_path: Option<PathBuf>,
+    /// Entropy used to seed the RNG. If missing, only the OS randomness is used.
+    pub entropy: Option<String>,
+    /// Custom seed of the RNG (32 bytes, hex encoded), used with the `--custom-seed` flag.
+    pub seed: Option<String>,
+}
+
+impl ContributionConfig {
+    /// Reads the config from a toml file.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_owned(), e))?;
+
+        toml::from_str(&content).map_err(|e| ConfigError::Toml(path.to_owned(), e))
+    }
+
+    /// Checks that all the answers needed by a non-interactive contribution are available.
+    pub fn check_non_interactive(&self, custom_seed: bool) -> Result<(), ConfigError> {
+        if self.token.is_none() {
+            return Err(ConfigError::MissingField("token"));
+        }
+
+        if self.incentivized {
+            if self.full_name.is_none() {
+                return Err(ConfigError::MissingField("full_name"));
+            }
+            if self.email.is_none() {
+                return Err(ConfigError::MissingField("email"));
+            }
+            if self.mnemonic_path.is_none() {
+                return Err(ConfigError::MissingField("mnemonic_path"));
+            }
+        }
+
+        if custom_seed && self.seed.is_none() {
+            return Err(ConfigError::MissingField("seed"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+//! Generation of the genesis token allocations of the contributors.
+//!
+//! The amount assigned to each contributor is defined by a list of [`AmountTier`]s, loaded from a toml file:
+//!
+//! ```toml
+//! token = "xan"
+//! default_amount = 1000
+//!
+//! [[tiers]]
+//! incentivized = true
+//! min_round = 1
+//! max_round = 100
+//! amount = 2000
+//! ```
+//!
+//! The first matching tier wins, contributors matching no tier get the default amount.
+//!
+//! The contributors are read either from the contributor info files of the coordinator or from the public
+//! `contributors.json` summary. The latter doesn't tell whether a contributor joined the incentivized program, so its
+//! entries never match a tier with an `incentivized` condition.
+
+use phase1_coordinator::{
+    authentication::{Production, Signature},
+    objects::{ContributionInfo, TrimmedContributionInfo},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::keys;
+
+/// Error returned while generating the genesis allocations.
+#[derive(Debug, Error)]
+pub enum GenesisError {
+    #[error("Duplicate entries for the public key {0} (rounds {1} and {2})")]
+    DuplicateContributor(String, u64, u64),
+    #[error("Duplicate entries for the address {0}")]
+    DuplicateAddress(String),
+    #[error("Invalid human readable part of the address: {0}")]
+    InvalidHrp(String),
+    #[error("Io error on {0:?}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Invalid contributor info file {0:?}: {1}")]
+    Json(PathBuf, serde_json::Error),
+    #[error("Invalid rules file {0:?}: {1}")]
+    Rules(PathBuf, toml::de::Error),
+    #[error("Error while serializing the allocations: {0}")]
+    Serialization(#[from] toml::ser::Error),
+}
+
+type Result<T> = std::result::Result<T, GenesisError>;
+
+/// Token of the genesis file if none is given.
+pub const DEFAULT_TOKEN: &str = "xan";
+const CONTRIBUTOR_INFO_PREFIX: &str = "namada_contributor_info_round_";
+
+/// A contributor entry, read from a contributor info file or from the contributions summary.
+#[derive(Clone, Debug)]
+pub struct Contributor {
+    /// ed25519 public key of the contributor, hex encoded
+    pub public_key: String,
+    /// Round in which the contribution took place
+    pub round: u64,
+    /// Whether the contributor joined the incentivized program, unknown for the entries of the summary
+    pub incentivized: Option<bool>,
+    /// Whether the signatures of the entry are valid
+    pub valid_signature: bool,
+}
+
+impl From<&ContributionInfo> for Contributor {
+    fn from(info: &ContributionInfo) -> Self {
+        Self {
+            public_key: info.public_key.clone(),
+            round: info.ceremony_round,
+            incentivized: Some(info.is_incentivized),
+            valid_signature: info.verify_signatures().is_ok(),
+        }
+    }
+}
+
+impl From<&TrimmedContributionInfo> for Contributor {
+    fn from(info: &TrimmedContributionInfo) -> Self {
+        Self {
+            public_key: info.public_key().to_owned(),
+            round: info.ceremony_round(),
+            incentivized: None,
+            valid_signature: Production.verify(
+                info.public_key(),
+                info.contribution_hash(),
+                info.contribution_hash_signature(),
+            ),
+        }
+    }
+}
+
+/// Amount assigned to the contributors matching all the given conditions.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AmountTier {
+    /// Match only the contributors who joined (or not) the incentivized program.
+    pub incentivized: Option<bool>,
+    /// Match only the contributions from this round on.
+    pub min_round: Option<u64>,
+    /// Match only the contributions up to this round.
+    pub max_round: Option<u64>,
+    /// The amount of tokens to assign.
+    pub amount: u64,
+}
+
+impl AmountTier {
+    fn matches(&self, contributor: &Contributor) -> bool {
+        self.incentivized.map_or(true, |i| contributor.incentivized == Some(i))
+            && self.min_round.map_or(true, |r| contributor.round >= r)
+            && self.max_round.map_or(true, |r| contributor.round <= r)
+    }
+}
+
+/// Rules to compute the allocations.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AllocationRules {
+    /// Name of the token in the genesis file.
+    #[serde(default = "default_token")]
+    pub token: String,
+    /// Amount assigned to the contributors matching no tier.
+    pub default_amount: u64,
+    #[serde(default)]
+    pub tiers: Vec<AmountTier>,
+}
+
+impl AllocationRules {
+    /// Creates rules assigning the same amount to every contributor.
+    pub fn flat(token: String, amount: u64) -> Self {
+        Self {
+            token,
+            default_amount: amount,
+            tiers: Vec::new(),
+        }
+    }
+
+    /// Reads the rules from a toml file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| GenesisError::Io(path.to_owned(), e))?;
+
+        toml::from_str(&content).map_err(|e| GenesisError::Rules(path.to_owned(), e))
+    }
+
+    fn amount(&self, contributor: &Contributor) -> u64 {
+        self.tiers
+            .iter()
+            .find(|tier| tier.matches(contributor))
+            .map_or(self.default_amount, |tier| tier.amount)
+    }
+}
+
+fn default_token() -> String {
+    DEFAULT_TOKEN.to_owned()
+}
+
+/// Reason why a contribution has been left out of the allocations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Exclusion {
+    /// The public key is in the exclusion list, e.g. because the contributor has been banned.
+    Excluded,
+    /// One of the signatures of the entry is not valid.
+    InvalidSignature,
+}
+
+/// Allocations of the genesis file, with the contributions left out.
+#[derive(Debug, Default)]
+pub struct Allocations {
+    /// Amount of tokens assigned to each address.
+    pub balances: BTreeMap<String, u64>,
+    /// Public keys of the contributions left out, with the reason.
+    pub excluded: Vec<(String, Exclusion)>,
+}
+
+#[derive(Serialize)]
+struct TokenBalances<'a> {
+    balances: &'a BTreeMap<String, u64>,
+}
+
+impl Allocations {
+    /// Serializes the balances in the `[token.{token}.balances]` section of a genesis file.
+    pub fn to_genesis_toml(&self, token: &str) -> Result<String> {
+        let tokens = BTreeMap::from([(token, TokenBalances {
+            balances: &self.balances,
+        })]);
+
+        Ok(toml::to_string(&BTreeMap::from([("token", tokens)]))?)
+    }
+}
+
+/// Reads all the contributor info files (`namada_contributor_info_round_{n}*.json`) of the given directory, sorted by
+/// round. The other files of the directory are ignored.
+pub fn read_contributions(dir: &Path) -> Result<Vec<Contributor>> {
+    let entries = std::fs::read_dir(dir).map_err(|e| GenesisError::Io(dir.to_owned(), e))?;
+    let mut contributors = Vec::new();
+
+    for entry in entries {
+        let path = entry.map_err(|e| GenesisError::Io(dir.to_owned(), e))?.path();
+        let is_contributor_info = path.file_name().and_then(|name| name.to_str()).map_or(false, |name| {
+            name.starts_with(CONTRIBUTOR_INFO_PREFIX) && name.ends_with(".json")
+        });
+        if !is_contributor_info || !path.is_file() {
+            continue;
+        }
+
+        let content = std::fs::read(&path).map_err(|e| GenesisError::Io(path.clone(), e))?;
+        let contribution: ContributionInfo =
+            serde_json::from_slice(&content).map_err(|e| GenesisError::Json(path.clone(), e))?;
+        contributors.push(Contributor::from(&contribution));
+    }
+    contributors.sort_by_key(|c| c.round);
+
+    Ok(contributors)
+}
+
+/// Reads the contributions summary (`contributors.json`), sorted by round.
+pub fn read_summary(path: &Path) -> Result<Vec<Contributor>> {
+    let content = std::fs::read(path).map_err(|e| GenesisError::Io(path.to_owned(), e))?;
+    let summary: Vec<TrimmedContributionInfo> =
+        serde_json::from_slice(&content).map_err(|e| GenesisError::Json(path.to_owned(), e))?;
+    let mut contributors: Vec<Contributor> = summary.iter().map(Contributor::from).collect();
+    contributors.sort_by_key(|c| c.round);
+
+    Ok(contributors)
+}
+
+/// Computes the allocations of the given contributors. Entries with an invalid signature or whose public key is in
+/// `excluded` are left out, while duplicate contributors are reported as errors.
+pub fn generate_allocations(
+    contributors: &[Contributor],
+    rules: &AllocationRules,
+    excluded: &HashSet<String>,
+    hrp: &str,
+) -> Result<Allocations> {
+    let mut allocations = Allocations::default();
+    let mut seen: HashMap<&str, u64> = HashMap::new();
+
+    for contributor in contributors {
+        let public_key = contributor.public_key.as_str();
+
+        if let Some(round) = seen.insert(public_key, contributor.round) {
+            return Err(GenesisError::DuplicateContributor(
+                public_key.to_owned(),
+                round,
+                contributor.round,
+            ));
+        }
+
+        if excluded.contains(public_key) {
+            allocations.excluded.push((public_key.to_owned(), Exclusion::Excluded));
+            continue;
+        }
+
+        if !contributor.valid_signature {
+            allocations
+                .excluded
+                .push((public_key.to_owned(), Exclusion::InvalidSignature));
+            continue;
+        }
+
+        let address = keys::bech_encode_address_with_hrp(&keys::generate_address(public_key), hrp)
+            .map_err(|e| GenesisError::InvalidHrp(e.to_string()))?;
+        if allocations
+            .balances
+            .insert(address.clone(), rules.amount(contributor))
+            .is_some()
+        {
+            return Err(GenesisError::DuplicateAddress(address));
+        }
+    }
+
+    Ok(allocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use phase1_coordinator::authentication::KeyPair;
+
+    const HRP: &str = "atest";
+    const RULES: &str = r#"
+default_amount = 1000
+
+[[tiers]]
+incentivized = true
+min_round = 1
+max_round = 2
+amount = 3000
+
+[[tiers]]
+min_round = 1
+max_round = 2
+amount = 2000
+"#;
+
+    fn contribution_info(keypair: &KeyPair, round: u64, incentivized: bool) -> ContributionInfo {
+        let mut info = ContributionInfo::default();
+        info.public_key = keypair.pubkey().to_owned();
+        info.ceremony_round = round;
+        info.is_incentivized = incentivized;
+        info.contribution_hash = "a".repeat(128);
+        info.contribution_hash_signature = Production.sign(keypair.sigkey(), &info.contribution_hash).unwrap();
+        info.contribution_file_hash = "b".repeat(128);
+        info.contribution_file_signature = Production.sign(keypair.sigkey(), &info.contribution_file_hash).unwrap();
+        info.try_sign(keypair).unwrap();
+
+        info
+    }
+
+    fn address(keypair: &KeyPair) -> String {
+        keys::bech_encode_address_with_hrp(&keys::generate_address(keypair.pubkey()), HRP).unwrap()
+    }
+
+    #[test]
+    fn test_tiers() {
+        let rules: AllocationRules = toml::from_str(RULES).unwrap();
+        assert_eq!(rules.token, DEFAULT_TOKEN);
+
+        let keypairs: Vec<KeyPair> = (0..4).map(|_| KeyPair::new()).collect();
+        let contributors = vec![
+            Contributor::from(&contribution_info(&keypairs[0], 1, true)),
+            Contributor::from(&contribution_info(&keypairs[1], 2, false)),
+            Contributor::from(&contribution_info(&keypairs[2], 3, true)),
+            // The summary doesn't tell if the contributor is incentivized
+            Contributor::from(&TrimmedContributionInfo::from(contribution_info(&keypairs[3], 2, true))),
+        ];
+
+        let allocations = generate_allocations(&contributors, &rules, &HashSet::new(), HRP).unwrap();
+        assert!(allocations.excluded.is_empty());
+        assert_eq!(allocations.balances.len(), 4);
+        assert_eq!(allocations.balances[&address(&keypairs[0])], 3000);
+        assert_eq!(allocations.balances[&address(&keypairs[1])], 2000);
+        assert_eq!(allocations.balances[&address(&keypairs[2])], 1000);
+        assert_eq!(allocations.balances[&address(&keypairs[3])], 2000);
+
+        let genesis: toml::Value = toml::from_str(&allocations.to_genesis_toml("nam").unwrap()).unwrap();
+        assert_eq!(
+            genesis["token"]["nam"]["balances"][&address(&keypairs[0])].as_integer(),
+            Some(3000)
+        );
+    }
+
+    #[test]
+    fn test_exclusions() {
+        let rules = AllocationRules::flat(DEFAULT_TOKEN.to_owned(), 1000);
+        let banned = KeyPair::new();
+        let tampered = KeyPair::new();
+        let tampered_summary = KeyPair::new();
+        let honest = KeyPair::new();
+
+        let mut tampered_info = contribution_info(&tampered, 2, false);
+        tampered_info.is_incentivized = true;
+        let mut summary_entry = serde_json::to_value(TrimmedContributionInfo::from(contribution_info(
+            &tampered_summary,
+            3,
+            false,
+        )))
+        .unwrap();
+        summary_entry["contribution_hash"] = serde_json::Value::from("c".repeat(128));
+        let summary_entry: TrimmedContributionInfo = serde_json::from_value(summary_entry).unwrap();
+
+        let contributors = vec![
+            Contributor::from(&contribution_info(&banned, 1, false)),
+            Contributor::from(&tampered_info),
+            Contributor::from(&summary_entry),
+            Contributor::from(&contribution_info(&honest, 4, false)),
+        ];
+        let excluded = HashSet::from([banned.pubkey().to_owned()]);
+
+        let allocations = generate_allocations(&contributors, &rules, &excluded, HRP).unwrap();
+        assert_eq!(allocations.excluded, vec![
+            (banned.pubkey().to_owned(), Exclusion::Excluded),
+            (tampered.pubkey().to_owned(), Exclusion::InvalidSignature),
+            (tampered_summary.pubkey().to_owned(), Exclusion::InvalidSignature),
+        ]);
+        assert_eq!(allocations.balances.len(), 1);
+        assert_eq!(allocations.balances[&address(&honest)], 1000);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let rules = AllocationRules::flat(DEFAULT_TOKEN.to_owned(), 1000);
+        let keypair = KeyPair::new();
+        let contributors = vec![
+            Contributor::from(&contribution_info(&keypair, 1, false)),
+            Contributor::from(&contribution_info(&KeyPair::new(), 2, false)),
+            Contributor::from(&contribution_info(&keypair, 3, true)),
+        ];
+
+        let result = generate_allocations(&contributors, &rules, &HashSet::new(), HRP);
+        assert!(matches!(
+            result,
+            Err(GenesisError::DuplicateContributor(public_key, 1, 3)) if public_key == keypair.pubkey()
+        ));
+
+        // Duplicates are reported even if one of the entries is excluded
+        let excluded = HashSet::from([keypair.pubkey().to_owned()]);
+        let result = generate_allocations(&contributors, &rules, &excluded, HRP);
+        assert!(matches!(result, Err(GenesisError::DuplicateContributor(..))));
+    }
+
+    #[test]
+    fn test_read_contributors() {
+        let dir = tempfile::tempdir().unwrap();
+        let keypairs: Vec<KeyPair> = (0..2).map(|_| KeyPair::new()).collect();
+        let infos = vec![
+            contribution_info(&keypairs[0], 2, true),
+            contribution_info(&keypairs[1], 1, false),
+        ];
+        for info in &infos {
+            std::fs::write(
+                dir.path().join(format!(
+                    "namada_contributor_info_round_{}_contribution_1.json",
+                    info.ceremony_round
+                )),
+                serde_json::to_vec(info).unwrap(),
+            )
+            .unwrap();
+        }
+        // Other files of the directory are ignored
+        let summary: Vec<TrimmedContributionInfo> = infos.iter().cloned().map(TrimmedContributionInfo::from).collect();
+        std::fs::write(
+            dir.path().join("contributors.json"),
+            serde_json::to_vec(&summary).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("namada_contributor_info_round_3.json.bak"), b"invalid").unwrap();
+        std::fs::create_dir(dir.path().join("namada_contributor_info_round_4.json")).unwrap();
+
+        let contributors = read_contributions(dir.path()).unwrap();
+        assert_eq!(contributors.len(), 2);
+        assert_eq!(contributors[0].public_key, keypairs[1].pubkey());
+        assert_eq!(contributors[0].round, 1);
+        assert_eq!(contributors[0].incentivized, Some(false));
+        assert_eq!(contributors[1].public_key, keypairs[0].pubkey());
+        assert_eq!(contributors[1].incentivized, Some(true));
+        assert!(contributors.iter().all(|c| c.valid_signature));
+
+        let contributors = read_summary(&dir.path().join("contributors.json")).unwrap();
+        assert_eq!(contributors.len(), 2);
+        assert_eq!(contributors[0].public_key, keypairs[1].pubkey());
+        assert!(
+            contributors
+                .iter()
+                .all(|c| c.incentivized.is_none() && c.valid_signature)
+        );
+    }
+}
@@ -8,8 +8,10 @@ use sha2::{Digest, Sha256};
 use thiserror::Error;
 
 const ADDRESS_BECH32_VARIANT: bech32::Variant = Variant::Bech32m;
-const ADDRESS_HRP: &str = "atest";
+/// The human readable part of the addresses of the Namada testnets.
+pub const DEFAULT_ADDRESS_HRP: &str = "atest";
 const ENCRYPTED_KEY_PREFIX: &str = "encrypted:";
+const UNENCRYPTED_KEY_PREFIX: &str = "unencrypted:";
 /// An address string before bech32m encoding must be this size.
 const FIXED_LEN_STRING_BYTES: usize = 45;
 const PKH_HASH_LEN: usize = 40;
@@ -24,11 +26,24 @@ pub enum DeserializeStoredKeypairError {
     MissingPrefix,
 }
 
+/// Error returned while updating a wallet.
+#[derive(Error, Debug)]
+pub enum WalletError {
+    #[error("The alias {0} is already used in the wallet")]
+    DuplicateAlias(String),
+    #[error("The public key hash {0} is already in the wallet")]
+    DuplicatePublicKeyHash(String),
+    #[error("The section {0} of the wallet is not a table")]
+    InvalidSection(&'static str),
+    #[error("Error while serializing the wallet: {0}")]
+    Serialization(#[from] toml::ser::Error),
+}
+
 #[derive(Deserialize, Serialize)]
 /// Represents a Namada wallet toml file.
 pub struct TomlConfig<'a> {
-    #[serde(borrow)]
-    keys: HashMap<&'a str, EncryptedKeypair>,
+    #[serde(borrow, default)]
+    keys: HashMap<&'a str, StoredKeypair>,
     #[serde(borrow)]
     addresses: HashMap<&'a str, &'a str>,
     #[serde(borrow)]
@@ -36,13 +51,101 @@ pub struct TomlConfig<'a> {
 }
 
 impl<'a> TomlConfig<'a> {
-    pub fn new(alias: &'a str, key: EncryptedKeypair, address: &'a str, pkh: &'a str) -> Self {
-        let keys = HashMap::from([(alias, key)]);
+    /// Creates a wallet with a single entry. If `key` is [`None`], only the address is stored.
+    pub fn new(alias: &'a str, key: Option<StoredKeypair>, address: &'a str, pkh: &'a str) -> Self {
+        let keys = key.map(|key| HashMap::from([(alias, key)])).unwrap_or_default();
         let addresses = HashMap::from([(alias, address)]);
         let pkhs = HashMap::from([(pkh, alias)]);
 
         Self { keys, addresses, pkhs }
     }
+
+    /// Appends the entries of this wallet to an existing wallet, preserving all the other sections of the latter.
+    /// Fails if one of the aliases or the public key hash is already in the wallet.
+    pub fn append_to(&self, wallet: &mut toml::value::Table) -> Result<(), WalletError> {
+        for (section, entries) in [
+            ("keys", toml::Value::try_from(&self.keys)?),
+            ("addresses", toml::Value::try_from(&self.addresses)?),
+            ("pkhs", toml::Value::try_from(&self.pkhs)?),
+        ] {
+            let table = wallet
+                .entry(section)
+                .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+                .as_table_mut()
+                .ok_or(WalletError::InvalidSection(section))?;
+
+            if let toml::Value::Table(entries) = entries {
+                for (key, value) in entries {
+                    if table.contains_key(&key) {
+                        return Err(match section {
+                            "pkhs" => WalletError::DuplicatePublicKeyHash(key),
+                            _ => WalletError::DuplicateAlias(key),
+                        });
+                    }
+                    table.insert(key, value);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A keypair stored in a wallet, either encrypted with a password or in plain text
+#[derive(Debug)]
+pub enum StoredKeypair {
+    Encrypted(EncryptedKeypair),
+    Raw(Vec<u8>),
+}
+
+impl StoredKeypair {
+    /// Stores the [KeyPair] in plain text, e.g. to import it in a hardware wallet
+    pub fn raw(keypair: &KeyPair) -> Self {
+        Self::Raw(borsh_secret_key(keypair))
+    }
+}
+
+impl Serialize for StoredKeypair {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Encrypted(encrypted) => encrypted.serialize(serializer),
+            Self::Raw(raw) => {
+                let keypair_string = format!("{}{}", UNENCRYPTED_KEY_PREFIX, hex::encode(raw));
+                serde::Serialize::serialize(&keypair_string, serializer)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StoredKeypair {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let keypair_string: String = serde::Deserialize::deserialize(deserializer)
+            .map_err(|err| DeserializeStoredKeypairError::InvalidStoredKeypairString(err.to_string()))
+            .map_err(D::Error::custom)?;
+        if let Some(raw) = keypair_string.strip_prefix(UNENCRYPTED_KEY_PREFIX) {
+            hex::decode(raw)
+                .map(Self::Raw)
+                .map_err(|err| DeserializeStoredKeypairError::InvalidStoredKeypairString(err.to_string()))
+                .map_err(D::Error::custom)
+        } else if let Some(encrypted) = keypair_string.strip_prefix(ENCRYPTED_KEY_PREFIX) {
+            FromStr::from_str(encrypted)
+                .map(Self::Encrypted)
+                .map_err(|err: hex::FromHexError| {
+                    DeserializeStoredKeypairError::InvalidStoredKeypairString(err.to_string())
+                })
+                .map_err(D::Error::custom)
+        } else {
+            Err(DeserializeStoredKeypairError::MissingPrefix).map_err(D::Error::custom)
+        }
+    }
 }
 
 /// An encrypted keypair stored in a wallet
@@ -98,12 +201,7 @@ impl FromStr for EncryptedKeypair {
 impl EncryptedKeypair {
     /// Encrypt a [KeyPair] and store it with its salt
     pub fn from_keypair(keypair: &KeyPair, password: impl AsRef<[u8]>) -> Self {
-        // NOTE: need to append an initial 0 to match the borsh encoding of the enum in the ledger.
-        //  Also need to truncate the length to 33 because the private key also contains the pubkey in the
-        //  trailing 32 bytes
-        let mut sk = vec![0u8];
-        sk.extend_from_slice(keypair.sk.as_ref());
-        sk.truncate(33);
+        let sk = borsh_secret_key(keypair);
 
         let salt = kdf::Salt::default();
         let encryption_key = encryption_key(&salt, password.as_ref());
@@ -115,6 +213,18 @@ impl EncryptedKeypair {
     }
 }
 
+/// Encodes the secret key of a [KeyPair] as expected by the ledger.
+fn borsh_secret_key(keypair: &KeyPair) -> Vec<u8> {
+    // NOTE: need to append an initial 0 to match the borsh encoding of the enum in the ledger.
+    //  Also need to truncate the length to 33 because the private key also contains the pubkey in the
+    //  trailing 32 bytes
+    let mut sk = vec![0u8];
+    sk.extend_from_slice(keypair.sk.as_ref());
+    sk.truncate(33);
+
+    sk
+}
+
 /// Make encryption secret key from a password.
 fn encryption_key(salt: &kdf::Salt, password: &[u8]) -> kdf::SecretKey {
     kdf::Password::from_slice(password)
@@ -138,8 +248,166 @@ pub fn generate_address(pubkey: &str) -> String {
 
 /// Generates a Namada address by [bech32] encoding it.
 pub fn bech_encode_address(address: &str) -> String {
+    bech_encode_address_with_hrp(address, DEFAULT_ADDRESS_HRP).unwrap()
+}
+
+/// Generates a Namada address by [bech32] encoding it with the human readable part of the given network.
+pub fn bech_encode_address_with_hrp(address: &str, hrp: &str) -> Result<String, bech32::Error> {
     let mut bytes = format!("{}::{}", PREFIX_IMPLICIT, address).into_bytes();
     bytes.resize(FIXED_LEN_STRING_BYTES, b' ');
 
-    bech32::encode(ADDRESS_HRP, bytes.to_base32(), ADDRESS_BECH32_VARIANT).unwrap()
+    bech32::encode(hrp, bytes.to_base32(), ADDRESS_BECH32_VARIANT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_compact::Seed;
+
+    const ALIAS: &str = "alice";
+    const PASSWORD: &str = "password";
+
+    fn keypair(seed: u8) -> KeyPair {
+        KeyPair::from_seed(Seed::new([seed; 32]))
+    }
+
+    fn address(keypair: &KeyPair) -> (String, String) {
+        let pkh = generate_address(&hex::encode(keypair.pk.as_ref()));
+        let address = bech_encode_address(&pkh);
+
+        (address, pkh)
+    }
+
+    fn wallet(config: &TomlConfig) -> toml::value::Table {
+        match toml::Value::try_from(config).unwrap() {
+            toml::Value::Table(table) => table,
+            _ => panic!("The wallet should be a table"),
+        }
+    }
+
+    #[test]
+    fn test_encrypted_keypair_round_trip() {
+        let keypair = keypair(1);
+        let (address, pkh) = address(&keypair);
+        let config = TomlConfig::new(
+            ALIAS,
+            Some(StoredKeypair::Encrypted(EncryptedKeypair::from_keypair(
+                &keypair, PASSWORD,
+            ))),
+            &address,
+            &pkh,
+        );
+
+        let content = toml::to_string(&config).unwrap();
+        assert!(content.contains(ENCRYPTED_KEY_PREFIX));
+        let parsed: TomlConfig = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.addresses[ALIAS], address);
+        assert_eq!(parsed.pkhs[pkh.as_str()], ALIAS);
+
+        let encrypted = match &parsed.keys[ALIAS] {
+            StoredKeypair::Encrypted(EncryptedKeypair(encrypted)) => encrypted,
+            key => panic!("Unexpected stored keypair {:?}", key),
+        };
+        // The salt is stored in front of the ciphertext
+        let (salt, ciphertext) = encrypted.split_at(kdf::Salt::default().len());
+        let salt = kdf::Salt::from_slice(salt).unwrap();
+        let sk = aead::open(&encryption_key(&salt, PASSWORD.as_bytes()), ciphertext).unwrap();
+        assert_eq!(sk, borsh_secret_key(&keypair));
+
+        // A wrong password can't decrypt the keypair
+        assert!(aead::open(&encryption_key(&salt, b"wrong"), ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_unencrypted_keypair_round_trip() {
+        let keypair = keypair(1);
+        let (address, pkh) = address(&keypair);
+        let config = TomlConfig::new(ALIAS, Some(StoredKeypair::raw(&keypair)), &address, &pkh);
+
+        let content = toml::to_string(&config).unwrap();
+        assert!(content.contains(UNENCRYPTED_KEY_PREFIX));
+        let parsed: TomlConfig = toml::from_str(&content).unwrap();
+        match &parsed.keys[ALIAS] {
+            StoredKeypair::Raw(sk) => {
+                assert_eq!(sk.len(), 33);
+                assert_eq!(sk, &borsh_secret_key(&keypair));
+            }
+            key => panic!("Unexpected stored keypair {:?}", key),
+        }
+    }
+
+    #[test]
+    fn test_public_only_round_trip() {
+        let keypair = keypair(1);
+        let (address, pkh) = address(&keypair);
+        let config = TomlConfig::new(ALIAS, None, &address, &pkh);
+
+        let content = toml::to_string(&config).unwrap();
+        assert!(!content.contains(ENCRYPTED_KEY_PREFIX));
+        assert!(!content.contains(UNENCRYPTED_KEY_PREFIX));
+        let parsed: TomlConfig = toml::from_str(&content).unwrap();
+        assert!(parsed.keys.is_empty());
+        assert_eq!(parsed.addresses[ALIAS], address);
+        assert_eq!(parsed.pkhs[pkh.as_str()], ALIAS);
+    }
+
+    #[test]
+    fn test_invalid_stored_keypair() {
+        assert!(toml::from_str::<HashMap<String, StoredKeypair>>("key = \"0011\"").is_err());
+        assert!(toml::from_str::<HashMap<String, StoredKeypair>>("key = \"unencrypted:zz\"").is_err());
+    }
+
+    #[test]
+    fn test_append_to() {
+        let alice = keypair(1);
+        let (alice_address, alice_pkh) = address(&alice);
+        let mut existing = wallet(&TomlConfig::new(
+            ALIAS,
+            Some(StoredKeypair::raw(&alice)),
+            &alice_address,
+            &alice_pkh,
+        ));
+        let mut view_keys = toml::value::Table::new();
+        view_keys.insert("key".to_string(), toml::Value::from("preserved"));
+        existing.insert("view_keys".to_string(), toml::Value::Table(view_keys.clone()));
+
+        let bob = keypair(2);
+        let (bob_address, bob_pkh) = address(&bob);
+        TomlConfig::new("bob", None, &bob_address, &bob_pkh)
+            .append_to(&mut existing)
+            .unwrap();
+
+        let content = toml::to_string(&existing).unwrap();
+        let parsed: TomlConfig = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.keys.len(), 1);
+        assert!(matches!(parsed.keys[ALIAS], StoredKeypair::Raw(_)));
+        assert_eq!(parsed.addresses[ALIAS], alice_address);
+        assert_eq!(parsed.addresses["bob"], bob_address);
+        assert_eq!(parsed.pkhs[alice_pkh.as_str()], ALIAS);
+        assert_eq!(parsed.pkhs[bob_pkh.as_str()], "bob");
+        assert_eq!(existing["view_keys"].as_table(), Some(&view_keys));
+    }
+
+    #[test]
+    fn test_append_to_duplicates() {
+        let alice = keypair(1);
+        let (alice_address, alice_pkh) = address(&alice);
+        let existing = wallet(&TomlConfig::new(ALIAS, None, &alice_address, &alice_pkh));
+
+        // Same alias for another key
+        let bob = keypair(2);
+        let (bob_address, bob_pkh) = address(&bob);
+        let result = TomlConfig::new(ALIAS, None, &bob_address, &bob_pkh).append_to(&mut existing.clone());
+        assert!(matches!(result, Err(WalletError::DuplicateAlias(alias)) if alias == ALIAS));
+
+        // Same key under another alias
+        let result = TomlConfig::new("bob", None, &alice_address, &alice_pkh).append_to(&mut existing.clone());
+        assert!(matches!(result, Err(WalletError::DuplicatePublicKeyHash(pkh)) if pkh == alice_pkh));
+
+        // A section of the wallet which is not a table
+        let mut invalid = existing;
+        invalid.insert("pkhs".to_string(), toml::Value::Integer(0));
+        let result = TomlConfig::new("bob", None, &bob_address, &bob_pkh).append_to(&mut invalid);
+        assert!(matches!(result, Err(WalletError::InvalidSection("pkhs"))));
+    }
 }
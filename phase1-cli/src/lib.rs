@@ -4,14 +4,21 @@
 use std::path::PathBuf;
 
 pub mod ascii_logo;
+pub mod config;
+pub mod genesis;
 pub mod keys;
+pub mod network;
+pub mod output;
+pub mod proof;
 pub mod requests;
+pub mod transcript;
 
 use phase1_coordinator::{
-    objects::round::LockedLocators,
-    rest::{ContributorStatus, PostChunkRequest},
+    commands::CircuitSet,
+    rest::{ContributorStatus, LockedChunk, PostChunkRequest, SpeculativeChallenge},
 };
 
+use output::OutputFormat;
 use reqwest::Url;
 use structopt::StructOpt;
 
@@ -25,6 +32,99 @@ pub struct CoordinatorUrl {
         parse(try_from_str)
     )]
     pub coordinator: Url,
+    #[structopt(flatten)]
+    pub retry: RetryOpt,
+    #[structopt(flatten)]
+    pub network: NetworkOpt,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RetryOpt {
+    #[structopt(
+        help = "The maximum number of attempts for a request failing because of a network error",
+        default_value = "5",
+        long
+    )]
+    pub max_attempts: u32,
+    #[structopt(
+        help = "The delay, in milliseconds, before retrying a failed request. The delay doubles at every attempt",
+        default_value = "500",
+        long
+    )]
+    pub retry_delay: u64,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct NetworkOpt {
+    #[structopt(
+        help = "The HTTP(S) or SOCKS5 proxy used for all the requests, e.g. http://proxy.example.com:3128 or socks5h://127.0.0.1:1080",
+        long,
+        env = "NAMADA_PROXY",
+        parse(try_from_str)
+    )]
+    pub proxy: Option<Url>,
+    #[structopt(
+        help = "Route all the requests through Tor, using the SOCKS5 proxy of the local Tor daemon. Onion addresses of the coordinator are supported",
+        long,
+        conflicts_with = "proxy"
+    )]
+    pub tor: bool,
+    #[structopt(
+        help = "The address of the SOCKS5 proxy of the Tor daemon",
+        long,
+        default_value = "127.0.0.1:9050"
+    )]
+    pub tor_address: String,
+    #[structopt(
+        help = "The path to a PEM bundle of root certificates to trust in addition to the default ones",
+        long,
+        parse(try_from_str)
+    )]
+    pub ca_cert: Option<PathBuf>,
+    #[structopt(
+        help = "The hex encoded sha256 hash of the SubjectPublicKeyInfo expected from the coordinator certificate. Can be repeated",
+        long,
+        number_of_values = 1
+    )]
+    pub pin_spki: Vec<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CircuitSetOpt {
+    #[structopt(
+        help = "The set of circuits of the ceremony: masp, test or a custom set registered by the binary",
+        long,
+        default_value = "masp"
+    )]
+    pub circuit_set: CircuitSet,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ParallelCircuitsOpt {
+    #[structopt(
+        help = "The number of circuits contributed to at the same time. Higher values are faster on machines with many cores, but need more memory since each circuit in progress holds its parameters in memory",
+        long,
+        default_value = "1"
+    )]
+    pub parallel_circuits: usize,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct OutputOpt {
+    #[structopt(
+        help = "The format of the output: human readable text or one json event per line",
+        long,
+        default_value = "human",
+        possible_values = &["human", "json"]
+    )]
+    pub output: OutputFormat,
+    #[structopt(
+        help = "The path to a toml file with the answers to the questions asked during the contribution. Required with \"--output json\"",
+        long,
+        required_if("output", "json"),
+        parse(try_from_str)
+    )]
+    pub config: Option<PathBuf>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -34,16 +134,226 @@ pub struct MnemonicPath {
 }
 
 #[derive(Debug, StructOpt)]
-pub struct Contributors {
+pub struct ExportKeypairOpt {
+    #[structopt(flatten)]
+    pub mnemonic: MnemonicPath,
+    #[structopt(
+        help = "The human readable part of the addresses of the target network",
+        long,
+        default_value = "atest"
+    )]
+    pub hrp: String,
+    #[structopt(
+        help = "Store the secret key in plain text instead of encrypting it with a password, e.g. to import it in a hardware wallet",
+        long
+    )]
+    pub unencrypted: bool,
+    #[structopt(
+        help = "Export only the address and the public key hash, without the secret key",
+        long,
+        conflicts_with = "unencrypted"
+    )]
+    pub public_only: bool,
+    #[structopt(
+        help = "The alias of the key in the wallet. If missing, it's asked interactively",
+        long
+    )]
+    pub alias: Option<String>,
+    #[structopt(
+        help = "The path to the wallet file to write",
+        long,
+        default_value = "keypair.toml",
+        parse(try_from_str)
+    )]
+    pub wallet: PathBuf,
+    #[structopt(help = "Append the key to the existing wallet file instead of overwriting it", long)]
+    pub append: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ProveContributionOpt {
+    #[structopt(flatten)]
+    pub mnemonic: MnemonicPath,
+    #[structopt(flatten)]
+    pub url: CoordinatorUrl,
     #[structopt(
-        help = "The path to the contributors.json file",
+        help = "The human readable part of the address of the target network",
+        long,
+        default_value = "atest"
+    )]
+    pub hrp: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ProofPath {
+    #[structopt(
+        help = "The path to the contribution proof file",
+        required = true,
+        parse(try_from_str)
+    )]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ContributionFiles {
+    #[structopt(help = "The path to the challenge file", required = true, parse(try_from_str), long)]
+    pub challenge: PathBuf,
+    #[structopt(
+        help = "The path to the response (contribution) file",
         required = true,
         parse(try_from_str),
         long
     )]
+    pub response: PathBuf,
+    #[structopt(flatten)]
+    pub circuits: CircuitSetOpt,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct BenchmarkOpt {
+    #[structopt(
+        help = "The maximum time, in minutes, allowed to compute and upload a contribution",
+        default_value = "20",
+        long
+    )]
+    pub lock_timeout: u64,
+    #[structopt(flatten)]
+    pub circuits: CircuitSetOpt,
+    #[structopt(flatten)]
+    pub parallelism: ParallelCircuitsOpt,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ReceiptPath {
+    #[structopt(
+        help = "The path to the contribution receipt file",
+        required = true,
+        parse(try_from_str)
+    )]
+    pub path: PathBuf,
+    #[structopt(
+        help = "The hex encoded public key of the coordinator, which must have issued the receipt",
+        long,
+        required = true
+    )]
+    pub coordinator_pubkey: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TranscriptPath {
+    #[structopt(
+        help = "The path to the transcript directory, containing the round_{n} folders",
+        required = true,
+        parse(try_from_str)
+    )]
+    pub path: PathBuf,
+    #[structopt(
+        help = "The hex encoded public key of the coordinator, which must have signed every verified contribution",
+        long,
+        required = true
+    )]
+    pub coordinator_pubkey: String,
+    #[structopt(
+        help = "The id of the ceremony, from which the initial challenge is recomputed",
+        long,
+        required = true
+    )]
+    pub ceremony_id: u64,
+    #[structopt(
+        help = "Report the missing contributor and coordinator signatures instead of rejecting the transcript",
+        long
+    )]
+    pub allow_missing_signatures: bool,
+    #[structopt(
+        help = "Fail if the last contribution of the transcript is not a random beacon",
+        long
+    )]
+    pub expect_beacon: bool,
+    #[structopt(flatten)]
+    pub circuits: CircuitSetOpt,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct BeaconOpt {
+    #[structopt(
+        help = "The path to the transcript directory, containing the round_{n} folders",
+        required = true,
+        parse(try_from_str)
+    )]
+    pub path: PathBuf,
+    #[structopt(
+        help = "The public beacon value (32 bytes in hexadecimal), e.g. the hash of a block mined after the last contribution",
+        long
+    )]
+    pub beacon: String,
+    #[structopt(flatten)]
+    pub circuits: CircuitSetOpt,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ExtractParamsOpt {
+    #[structopt(
+        help = "The path to the final contribution file, i.e. the challenge of the round following the last contribution",
+        required = true,
+        parse(try_from_str)
+    )]
     pub path: PathBuf,
-    #[structopt(help = "The amount of tokens to assign", required = true, long)]
-    pub amount: u32,
+    #[structopt(
+        help = "The directory where the .params files are written",
+        long,
+        default_value = ".",
+        parse(try_from_str)
+    )]
+    pub output: PathBuf,
+    #[structopt(flatten)]
+    pub circuits: CircuitSetOpt,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Contributors {
+    #[structopt(help = "The path to the contributors.json file", parse(try_from_str), long)]
+    pub path: Option<PathBuf>,
+    #[structopt(
+        help = "The path to the directory containing the contributor info files (namada_contributor_info_round_{n}*.json), used instead of --path",
+        long,
+        parse(try_from_str),
+        required_unless = "path",
+        conflicts_with = "path"
+    )]
+    pub contributors_dir: Option<PathBuf>,
+    #[structopt(
+        help = "The amount of tokens to assign to every contributor",
+        long,
+        required_unless = "rules",
+        conflicts_with = "rules"
+    )]
+    pub amount: Option<u64>,
+    #[structopt(
+        help = "The name of the token in the genesis file, overriding the one of the rules file [default: xan]",
+        long
+    )]
+    pub token: Option<String>,
+    #[structopt(
+        help = "The path to a toml file defining the amount tiers, used instead of --amount",
+        long,
+        parse(try_from_str)
+    )]
+    pub rules: Option<PathBuf>,
+    #[structopt(
+        help = "The path to a file listing the public keys to exclude, e.g. of banned contributors, one per line",
+        long,
+        parse(try_from_str)
+    )]
+    pub exclude: Option<PathBuf>,
+    #[structopt(help = "The human readable part of the addresses", long, default_value = "atest")]
+    pub hrp: String,
+    #[structopt(
+        help = "The path of the generated genesis file",
+        long,
+        default_value = "genesis.toml",
+        parse(try_from_str)
+    )]
+    pub output: PathBuf,
 }
 
 #[derive(Debug, StructOpt)]
@@ -52,24 +362,36 @@ pub enum Branches {
     AnotherMachine {
         #[structopt(flatten)]
         url: CoordinatorUrl,
+        #[structopt(flatten)]
+        output: OutputOpt,
     },
     #[structopt(about = "The default contribution path, executes both communication and computation on this machine",)]
     Default {
         #[structopt(flatten)]
         url: CoordinatorUrl,
+        #[structopt(flatten)]
+        output: OutputOpt,
         #[structopt(
             long,
             help = "Give a custom random seed (32 bytes / 64 characters in hexadecimal) for the ChaCha RNG"
         )]
         custom_seed: bool,
+        #[structopt(flatten)]
+        parallelism: ParallelCircuitsOpt,
     },
     #[structopt(about = "Performs only the computation of the contribution, to be used in conjunction with \"namada-ts contribute another-machine\" on a separate machine",)]
     Offline {
+        #[structopt(flatten)]
+        output: OutputOpt,
         #[structopt(
             long,
             help = "Give a custom random seed (32 bytes / 64 characters in hexadecimal) for the ChaCha RNG"
         )]
         custom_seed: bool,
+        #[structopt(flatten)]
+        circuits: CircuitSetOpt,
+        #[structopt(flatten)]
+        parallelism: ParallelCircuitsOpt,
     },
 }
 
@@ -78,14 +400,30 @@ pub enum Branches {
 pub enum CeremonyOpt {
     #[structopt(about = "Contribute to the ceremony")]
     Contribute(Branches),
+    #[structopt(about = "Measure the time needed by this machine to compute a contribution, without contacting the coordinator")]
+    Benchmark(BenchmarkOpt),
     #[structopt(about = "Stop the coordinator and close the ceremony")]
     CloseCeremony(CoordinatorUrl),
+    #[structopt(about = "Apply a random beacon as the final contribution of the ceremony")]
+    ApplyBeacon(BeaconOpt),
     #[structopt(about = "Generate a Namada keypair from a mnemonic")]
-    ExportKeypair(MnemonicPath),
+    ExportKeypair(ExportKeypairOpt),
+    #[structopt(about = "Extract the .params files of the circuits from the final contribution file of the ceremony")]
+    ExtractParams(ExtractParamsOpt),
     #[structopt(about = "Generate the list of addresses of the contributors")]
     GenerateAddresses(Contributors),
     #[structopt(about = "Get a list of all the contributions received")]
     GetContributions(CoordinatorUrl),
+    #[structopt(about = "Verify a contribution file against its challenge", alias = "verify")]
+    VerifyContribution(ContributionFiles),
+    #[structopt(about = "Check the receipt of a contribution signed by the coordinator")]
+    CheckReceipt(ReceiptPath),
+    #[structopt(about = "Produce a signed proof of your contribution from your mnemonic")]
+    ProveContribution(ProveContributionOpt),
+    #[structopt(about = "Check a proof of contribution")]
+    CheckProof(ProofPath),
+    #[structopt(about = "Verify the whole transcript of the ceremony")]
+    VerifyTranscript(TranscriptPath),
     #[cfg(debug_assertions)]
     #[structopt(about = "Verify the pending contributions")]
     VerifyContributions(CoordinatorUrl),
@@ -93,3 +431,12 @@ pub enum CeremonyOpt {
     #[structopt(about = "Update manually the coordinator")]
     UpdateCoordinator(CoordinatorUrl),
 }
+
+impl Branches {
+    /// Returns the output options of the contribution.
+    pub fn output_opt(&self) -> &OutputOpt {
+        match self {
+            Self::AnotherMachine { output, .. } | Self::Default { output, .. } | Self::Offline { output, .. } => output,
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Configuration of the HTTP client used to reach the coordinator and the object store.
+//!
+//! The same client is used for the requests to the coordinator and for the presigned urls of the object store, so
+//! that the proxy and the extra root certificates apply to both. The SPKI pins, instead, are only enforced on the
+//! coordinator host, since the object store certificates are out of the coordinator's control.
+
+use reqwest::{Certificate as ReqwestCertificate, Client, Proxy, Url};
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+    Certificate,
+    ClientConfig,
+    OwnedTrustAnchor,
+    RootCertStore,
+    ServerName,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+use thiserror::Error;
+
+use crate::NetworkOpt;
+
+const SPKI_PIN_LENGTH: usize = 32;
+
+/// Error returned while building the [`Client`].
+#[derive(Debug, Error)]
+pub enum NetworkError {
+    #[error("Invalid certificate in {0:?}: {1}")]
+    InvalidCertificate(PathBuf, String),
+    #[error("Invalid SPKI pin {0}: expected the hex encoded sha256 hash of the public key")]
+    InvalidPin(String),
+    #[error("Io error on {0:?}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("The coordinator url doesn't contain a host to pin")]
+    MissingHost,
+    #[error("Request error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+}
+
+type Result<T> = std::result::Result<T, NetworkError>;
+
+/// Verifies the certificate chain against the trusted roots and, for the pinned host, checks that the public key of
+/// the server certificate matches one of the pins.
+struct SpkiPinVerifier {
+    inner: WebPkiVerifier,
+    host: String,
+    pins: Vec<[u8; SPKI_PIN_LENGTH]>,
+}
+
+impl SpkiPinVerifier {
+    fn is_pinned_host(&self, server_name: &ServerName) -> bool {
+        match server_name {
+            ServerName::DnsName(name) => name.as_ref().eq_ignore_ascii_case(&self.host),
+            ServerName::IpAddress(ip) => ip.to_string() == self.host,
+            _ => false,
+        }
+    }
+
+    /// Checks that the public key of the certificate matches one of the pins, if the server is the pinned host.
+    fn check_pin(&self, end_entity: &Certificate, server_name: &ServerName) -> std::result::Result<(), rustls::Error> {
+        if !self.is_pinned_host(server_name) {
+            return Ok(());
+        }
+
+        let hash = spki_hash(&end_entity.0)?;
+        if !self.pins.iter().any(|pin| pin == &hash) {
+            return Err(rustls::Error::General(format!(
+                "The public key of {} doesn't match any of the pinned ones (found {})",
+                self.host,
+                hex::encode(hash)
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl ServerCertVerifier for SpkiPinVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let verified =
+            self.inner
+                .verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+        self.check_pin(end_entity, server_name)?;
+
+        Ok(verified)
+    }
+}
+
+/// Computes the sha256 hash of the DER encoded SubjectPublicKeyInfo of the certificate.
+fn spki_hash(der: &[u8]) -> std::result::Result<[u8; SPKI_PIN_LENGTH], rustls::Error> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| rustls::Error::General(format!("Couldn't parse the server certificate: {}", e)))?;
+
+    Ok(Sha256::digest(certificate.public_key().raw).into())
+}
+
+fn decode_pin(pin: &str) -> Result<[u8; SPKI_PIN_LENGTH]> {
+    let mut decoded = [0u8; SPKI_PIN_LENGTH];
+    hex::decode_to_slice(pin, &mut decoded).map_err(|_| NetworkError::InvalidPin(pin.to_owned()))?;
+
+    Ok(decoded)
+}
+
+/// Reads the DER encoding of all the certificates of a PEM bundle.
+fn read_certificates(path: &Path) -> Result<Vec<Vec<u8>>> {
+    let file = std::fs::File::open(path).map_err(|e| NetworkError::Io(path.to_owned(), e))?;
+    let certificates =
+        rustls_pemfile::certs(&mut BufReader::new(file)).map_err(|e| NetworkError::Io(path.to_owned(), e))?;
+
+    if certificates.is_empty() {
+        return Err(NetworkError::InvalidCertificate(
+            path.to_owned(),
+            String::from("no certificate found"),
+        ));
+    }
+
+    Ok(certificates)
+}
+
+/// Builds the [`Client`] used for all the requests, applying the proxy, the extra root certificates and the SPKI pins
+/// of the coordinator host.
+pub fn build_client(opt: &NetworkOpt, coordinator: &Url) -> Result<Client> {
+    let mut builder = Client::builder();
+
+    if opt.tor {
+        // socks5h lets the proxy resolve the host names, so that DNS queries don't leak and onion addresses work
+        builder = builder.proxy(Proxy::all(format!("socks5h://{}", opt.tor_address))?);
+    } else if let Some(proxy) = &opt.proxy {
+        builder = builder.proxy(Proxy::all(proxy.clone())?);
+    }
+
+    let extra_roots = match &opt.ca_cert {
+        Some(path) => read_certificates(path)?,
+        None => Vec::new(),
+    };
+
+    if opt.pin_spki.is_empty() {
+        for der in extra_roots {
+            builder = builder.add_root_certificate(ReqwestCertificate::from_der(&der)?);
+        }
+    } else {
+        let host = coordinator
+            .host_str()
+            .ok_or(NetworkError::MissingHost)?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_owned();
+        let pins = opt
+            .pin_spki
+            .iter()
+            .map(|pin| decode_pin(pin))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut roots = RootCertStore::empty();
+        roots.add_server_trust_anchors(
+            webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+            }),
+        );
+        if let Some(path) = &opt.ca_cert {
+            for der in extra_roots {
+                roots
+                    .add(&Certificate(der))
+                    .map_err(|e| NetworkError::InvalidCertificate(path.to_owned(), e.to_string()))?;
+            }
+        }
+
+        let verifier = SpkiPinVerifier {
+            inner: WebPkiVerifier::new(roots, None),
+            host,
+            pins,
+        };
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        builder = builder.use_preconfigured_tls(config);
+    }
+
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBmjCCAUGgAwIBAgIUSeeo2ACVO1iX5kelBNkjAuOuP+EwCgYIKoZIzj0EAwIw
+IjEgMB4GA1UEAwwXY29vcmRpbmF0b3IuZXhhbXBsZS5jb20wIBcNMjYxMDE4MTcz
+MDQwWhgPMjEyNjA5MjQxNzMwNDBaMCIxIDAeBgNVBAMMF2Nvb3JkaW5hdG9yLmV4
+YW1wbGUuY29tMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEt1qMrQ5jemN4cRVE
+Ew0A/xCkThJfF4S50vMjOs1+tsDM9l15Crojdp2ggTBhRzLkZJpuUtcAp9k6XstY
+Fh+2v6NTMFEwHQYDVR0OBBYEFNhxrkxN06teQsO9a6Bnt0htZZ1LMB8GA1UdIwQY
+MBaAFNhxrkxN06teQsO9a6Bnt0htZZ1LMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZI
+zj0EAwIDRwAwRAIgb5vQj11Q60k0A09N1bcKQY8QmAyteHA2fB16DEosj10CICpO
+D9275P4h6WNZvegeR3fa9ZnID/HBw7wV5RY22t91
+-----END CERTIFICATE-----
+";
+    // sha256 of the SubjectPublicKeyInfo of CERTIFICATE, as computed by openssl
+    const PIN: &str = "fb7440e943c0a0f2a9115a22daa9e8d80af2e34c89b34040b8f3fd419345d720";
+    const HOST: &str = "coordinator.example.com";
+
+    fn certificate() -> Certificate {
+        let mut certificates = rustls_pemfile::certs(&mut CERTIFICATE.as_bytes()).unwrap();
+
+        Certificate(certificates.remove(0))
+    }
+
+    fn verifier(pins: &[&str]) -> SpkiPinVerifier {
+        SpkiPinVerifier {
+            inner: WebPkiVerifier::new(RootCertStore::empty(), None),
+            host: HOST.to_owned(),
+            pins: pins.iter().map(|pin| decode_pin(pin).unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_matching_pin() {
+        let server_name = ServerName::try_from(HOST).unwrap();
+        assert_eq!(hex::encode(spki_hash(&certificate().0).unwrap()), PIN);
+
+        assert!(verifier(&[PIN]).check_pin(&certificate(), &server_name).is_ok());
+        // Any of the pins can match
+        let other_pin = "00".repeat(SPKI_PIN_LENGTH);
+        assert!(
+            verifier(&[&other_pin, PIN])
+                .check_pin(&certificate(), &server_name)
+                .is_ok()
+        );
+        // The host name is case insensitive
+        let server_name = ServerName::try_from("Coordinator.Example.com").unwrap();
+        assert!(verifier(&[PIN]).check_pin(&certificate(), &server_name).is_ok());
+    }
+
+    #[test]
+    fn test_mismatching_pin() {
+        let other_pin = "00".repeat(SPKI_PIN_LENGTH);
+        let server_name = ServerName::try_from(HOST).unwrap();
+        assert!(verifier(&[&other_pin]).check_pin(&certificate(), &server_name).is_err());
+
+        // Other hosts, like the object store, are not pinned
+        let server_name = ServerName::try_from("bucket.s3.amazonaws.com").unwrap();
+        assert!(verifier(&[&other_pin]).check_pin(&certificate(), &server_name).is_ok());
+    }
+
+    #[test]
+    fn test_decode_pin() {
+        assert!(decode_pin(PIN).is_ok());
+        assert!(matches!(decode_pin(&PIN[2..]), Err(NetworkError::InvalidPin(_))));
+        assert!(matches!(decode_pin("not a pin"), Err(NetworkError::InvalidPin(_))));
+    }
+}
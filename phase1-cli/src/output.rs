@@ -0,0 +1,139 @@
+//! Machine-readable output of the CLI.
+//!
+//! When the json output is selected, every step of the contribution is reported on stdout as a single line json
+//! [`Event`], so that the CLI can be wrapped by a GUI or by automation scripts. The human readable messages and the
+//! progress bars are suppressed in this mode.
+
+use chrono::{DateTime, Utc};
+use phase1_coordinator::commands::CircuitSet;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Format of the output produced by the CLI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Invalid output format {}, expected one of: human, json", s)),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Returns `true` if the json output has been selected.
+    pub fn is_json(self) -> bool {
+        self == Self::Json
+    }
+}
+
+/// Event reported during the contribution.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A new step of the contribution has started.
+    Step { step: u8, total: u8, description: &'a str },
+    /// The keypair used to interact with the coordinator is ready.
+    KeypairGenerated { public_key: &'a str },
+    /// Position of the contributor in the queue.
+    QueuePosition { position: u64, size: u64 },
+    /// The contributor is in the round and waits for the verification of the previous contribution.
+    WaitingVerification,
+    /// The contribution is computed on top of the previous contribution, before its verification.
+    SpeculativeComputation { round_height: u64 },
+    /// The previous contribution changed after the speculative computation, which has been discarded.
+    SpeculativeComputationDiscarded { round_height: u64 },
+    /// The contributor locked the chunk and must upload the contribution before the deadline.
+    LockAcquired { round_height: u64, deadline: DateTime<Utc> },
+    /// Progress of the download of the challenge or of the upload of the contribution.
+    Transfer {
+        direction: &'a str,
+        transferred: u64,
+        total: u64,
+    },
+    /// The contribution has to be computed on another machine, the CLI waits for a new line on stdin.
+    OfflineContributionRequired {
+        challenge_file: &'a str,
+        contribution_file: &'a str,
+        circuit_set: CircuitSet,
+        deadline: DateTime<Utc>,
+    },
+    /// Progress of the computation of a circuit.
+    CircuitProgress { circuit: &'a str, stage: &'a str },
+    /// The contribution has been computed.
+    ContributionComputed {
+        round_height: u64,
+        contribution_hash: &'a str,
+        contribution_file_hash: &'a str,
+        seconds: i64,
+    },
+    /// The contribution and its signature have been uploaded.
+    UploadDone { round_height: u64 },
+    /// The coordinator has accepted the contribution.
+    Finished {
+        round_height: u64,
+        contribution_hash: &'a str,
+        receipt_file: Option<&'a str>,
+    },
+    /// The contributor has been banned from the ceremony.
+    Banned,
+    /// The coordinator returned an unexpected contributor state.
+    Dropped,
+    /// The contribution failed.
+    Error { message: String },
+}
+
+impl Event<'_> {
+    /// Prints the event on stdout as a single json line.
+    pub fn emit(&self) {
+        match serde_json::to_string(self) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("Couldn't serialize the event {:?}: {}", self, e),
+        }
+    }
+}
+
+/// Emits [`Event::Transfer`] events for a download or an upload, at most once every percent.
+pub struct TransferReporter {
+    format: OutputFormat,
+    direction: &'static str,
+    total: u64,
+    transferred: u64,
+    last_percent: u64,
+}
+
+impl TransferReporter {
+    pub fn new(direction: &'static str, total: u64, format: OutputFormat) -> Self {
+        Self {
+            format,
+            direction,
+            total,
+            transferred: 0,
+            last_percent: 0,
+        }
+    }
+
+    /// Records `len` more transferred bytes.
+    pub fn inc(&mut self, len: u64) {
+        self.transferred += len;
+        let percent = (self.transferred * 100).checked_div(self.total).unwrap_or(100);
+
+        if self.format.is_json() && (percent > self.last_percent || self.transferred == len) {
+            self.last_percent = percent;
+            Event::Transfer {
+                direction: self.direction,
+                transferred: self.transferred,
+                total: self.total,
+            }
+            .emit();
+        }
+    }
+}
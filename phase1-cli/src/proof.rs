@@ -0,0 +1,174 @@
+//! Statement proving a contribution to the ceremony, signed with the contributor key derived from the mnemonic.
+//!
+//! The proof binds the Namada address of the contributor to the entry of the public contribution summary, so that an
+//! external verifier can check it without access to the contributor info file.
+
+use chrono::{DateTime, Utc};
+use phase1_coordinator::{
+    authentication::{self, KeyPair, Production, Signature},
+    objects::TrimmedContributionInfo,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::keys;
+
+/// Error returned while producing or checking a [`ContributionProof`].
+#[derive(Debug, Error)]
+pub enum ProofError {
+    #[error("Invalid human readable part of the address: {0}")]
+    InvalidHrp(String),
+    #[error("Error while serializing ContributionProof: {0}")]
+    SerdeError(#[from] serde_json::Error),
+    #[error("Error while signing ContributionProof: {0}")]
+    SignatureError(String),
+}
+
+/// Signed statement of a contribution
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContributionProof {
+    // ed25519 public key of the contributor, hex encoded
+    pub public_key: String,
+    // Namada address derived from the public key
+    pub address: String,
+    // Round in which the contribution took place
+    pub round_height: u64,
+    // Hash of the contribution, as found in the contribution summary
+    pub contribution_hash: String,
+    // Signature of the contribution hash produced during the contribution, as found in the contribution summary
+    pub contribution_hash_signature: String,
+    // Time at which the proof was produced
+    pub timestamp: DateTime<Utc>,
+    // Signature of the contributor, computed on the json string encoding of all the other fields of this struct
+    pub signature: String,
+}
+
+impl ContributionProof {
+    /// Creates the proof of the given contribution, signed with the contributor keypair.
+    pub fn try_new(keypair: &KeyPair, contribution: &TrimmedContributionInfo, hrp: &str) -> Result<Self, ProofError> {
+        let address = keys::bech_encode_address_with_hrp(&keys::generate_address(keypair.pubkey()), hrp)
+            .map_err(|e| ProofError::InvalidHrp(e.to_string()))?;
+
+        let mut proof = Self {
+            public_key: keypair.pubkey().to_owned(),
+            address,
+            round_height: contribution.ceremony_round(),
+            contribution_hash: contribution.contribution_hash().to_owned(),
+            contribution_hash_signature: contribution.contribution_hash_signature().to_owned(),
+            timestamp: Utc::now(),
+            signature: String::default(),
+        };
+
+        let digest = proof.hash_for_signature()?;
+        proof.signature = Production
+            .sign(keypair.sigkey(), digest.as_str())
+            .map_err(|e| ProofError::SignatureError(format!("{}", e)))?;
+
+        Ok(proof)
+    }
+
+    /// Calculates the hash of the json string encoding all the fields of the struct
+    /// except for the signature itself.
+    fn hash_for_signature(&self) -> Result<String, ProofError> {
+        Ok(authentication::hash_for_signature(self, "signature")?)
+    }
+
+    /// Checks that the address is derived from the public key and that both the proof and the contribution hash are
+    /// signed by the public key.
+    pub fn verify(&self) -> Result<bool, ProofError> {
+        let hrp = match self.address.rsplit_once('1') {
+            Some((hrp, _)) => hrp,
+            None => return Ok(false),
+        };
+        let expected_address = keys::bech_encode_address_with_hrp(&keys::generate_address(&self.public_key), hrp)
+            .map_err(|e| ProofError::InvalidHrp(e.to_string()))?;
+        if expected_address != self.address {
+            return Ok(false);
+        }
+
+        let digest = self.hash_for_signature()?;
+
+        Ok(Production.verify(&self.public_key, &digest, &self.signature)
+            && Production.verify(
+                &self.public_key,
+                &self.contribution_hash,
+                &self.contribution_hash_signature,
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use phase1_coordinator::objects::ContributionInfo;
+
+    const HRP: &str = "tnam";
+
+    fn contribution(keypair: &KeyPair) -> TrimmedContributionInfo {
+        let mut info = ContributionInfo::default();
+        info.public_key = keypair.pubkey().to_owned();
+        info.ceremony_round = 3;
+        info.contribution_hash = "a".repeat(128);
+        info.contribution_hash_signature = Production.sign(keypair.sigkey(), &info.contribution_hash).unwrap();
+
+        info.into()
+    }
+
+    #[test]
+    fn test_prove_and_check() {
+        let keypair = KeyPair::new();
+        let contribution = contribution(&keypair);
+
+        let proof = ContributionProof::try_new(&keypair, &contribution, HRP).unwrap();
+        assert_eq!(proof.public_key, keypair.pubkey());
+        assert!(proof.address.starts_with(HRP));
+        assert_eq!(proof.round_height, 3);
+        assert_eq!(proof.contribution_hash, contribution.contribution_hash());
+        assert!(proof.verify().unwrap());
+
+        // The proof is still valid once written to and read back from a file
+        let proof: ContributionProof = serde_json::from_slice(&serde_json::to_vec(&proof).unwrap()).unwrap();
+        assert!(proof.verify().unwrap());
+
+        assert!(matches!(
+            ContributionProof::try_new(&keypair, &contribution, "Invalid HRP"),
+            Err(ProofError::InvalidHrp(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_proof() {
+        let keypair = KeyPair::new();
+        let proof = ContributionProof::try_new(&keypair, &contribution(&keypair), HRP).unwrap();
+
+        // Signed fields
+        let mut tampered = proof.clone();
+        tampered.round_height += 1;
+        assert!(!tampered.verify().unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.timestamp = tampered.timestamp + chrono::Duration::seconds(1);
+        assert!(!tampered.verify().unwrap());
+
+        // Address of another key or of another network
+        let other_keypair = KeyPair::new();
+        let mut tampered = proof.clone();
+        tampered.address =
+            keys::bech_encode_address_with_hrp(&keys::generate_address(other_keypair.pubkey()), HRP).unwrap();
+        assert!(!tampered.verify().unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.address = keys::bech_encode_address(&keys::generate_address(keypair.pubkey()));
+        assert!(!tampered.verify().unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.address = String::from("not an address");
+        assert!(!tampered.verify().unwrap());
+
+        // Contribution of another contributor, claimed with a valid signature of the proof
+        let mut tampered = ContributionProof::try_new(&other_keypair, &contribution(&keypair), HRP).unwrap();
+        assert!(!tampered.verify().unwrap());
+        tampered.public_key = keypair.pubkey().to_owned();
+        assert!(!tampered.verify().unwrap());
+    }
+}
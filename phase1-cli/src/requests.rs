@@ -4,7 +4,8 @@ use bytes::Bytes;
 use futures_util::Stream;
 use phase1_coordinator::{
     authentication::{KeyPair, Production, Signature},
-    objects::ContributionInfo,
+    commands::CircuitSet,
+    objects::{ContributionInfo, ContributionReceipt},
     rest::{
         RequestContent,
         SignatureHeaders,
@@ -15,19 +16,24 @@ use phase1_coordinator::{
     },
     ContributionFileSignature,
 };
+use rand::Rng;
 use reqwest::{
     header::{HeaderMap, HeaderValue, CONTENT_TYPE},
     Client,
     RequestBuilder,
     Response,
+    StatusCode,
     Url,
 };
 use serde::Serialize;
 use sha2::{Digest, Sha256};
-use std::convert::{TryFrom, TryInto};
+use std::{
+    convert::{TryFrom, TryInto},
+    time::Duration,
+};
 use thiserror::Error;
 
-use crate::{ContributorStatus, LockedLocators, PostChunkRequest};
+use crate::{ContributorStatus, LockedChunk, PostChunkRequest, RetryOpt, SpeculativeChallenge};
 
 /// Error returned from a request.
 #[derive(Debug, Error)]
@@ -40,6 +46,8 @@ pub enum RequestError {
     InvalidHeaderValue(#[from] reqwest::header::InvalidHeaderValue),
     #[error("Json serialization of body failed")]
     JsonError(#[from] serde_json::Error),
+    #[error("CDN timeout expired: {0}")]
+    GatewayTimeout(String),
     #[error("CDN Error: {0}")]
     Proxy(String),
     #[error("Request error: {0}")]
@@ -51,6 +59,125 @@ pub enum RequestError {
 }
 
 type Result<T> = std::result::Result<T, RequestError>;
+
+impl RequestError {
+    /// Returns `true` if the error is likely to be transient and the request worth resubmitting.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::GatewayTimeout(_) | Self::Proxy(_) => true,
+            Self::Reqwest(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the request never reached the server.
+    fn is_connect(&self) -> bool {
+        matches!(self, Self::Reqwest(e) if e.is_connect())
+    }
+}
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_BASE_DELAY_MS: u64 = 500;
+const DEFAULT_MAX_DELAY_MS: u64 = 30_000;
+
+/// Policy applied to resubmit the requests failed because of a transient network error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of times a request is sent, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled at every following attempt.
+    pub base_delay: Duration,
+    /// The upper bound of the delay between two attempts.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_BASE_DELAY_MS),
+            max_delay: Duration::from_millis(DEFAULT_MAX_DELAY_MS),
+        }
+    }
+}
+
+impl From<&RetryOpt> for RetryPolicy {
+    fn from(opt: &RetryOpt) -> Self {
+        Self {
+            max_attempts: opt.max_attempts,
+            base_delay: Duration::from_millis(opt.retry_delay),
+            ..Default::default()
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Computes the delay to wait after the given failed attempt (starting from 1): the exponential backoff is
+    /// capped at `max_delay` and half of it is randomized, to prevent all the contributors from retrying at once.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = backoff / 2;
+        let jitter = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+
+        half + Duration::from_millis(jitter)
+    }
+}
+
+/// Defines which failures a request can be resubmitted on.
+#[derive(Clone, Copy)]
+enum Retry {
+    /// The request is idempotent and can be resubmitted on any transient error.
+    Always,
+    /// The request modifies the state of the coordinator, so it's only resubmitted if it never reached the server or
+    /// if the CDN timed out waiting for the answer of the coordinator.
+    OnConnectionError,
+}
+
+impl Retry {
+    /// Returns `true` if the request can be resubmitted after the given error, at the given attempt. A timeout of the
+    /// CDN only means that the coordinator is slow to answer, so the request is resubmitted without limit.
+    fn should_retry(self, error: &RequestError, attempt: u32, policy: &RetryPolicy) -> bool {
+        if let RequestError::GatewayTimeout(_) = error {
+            return true;
+        }
+
+        attempt < policy.max_attempts
+            && match self {
+                Self::Always => error.is_transient(),
+                Self::OnConnectionError => error.is_connect(),
+            }
+    }
+}
+
+/// Sends the request, resubmitting it with exponential backoff on the failures allowed by `retry` and `policy`.
+async fn send_with_retry(req: RequestBuilder, policy: &RetryPolicy, retry: Retry) -> Result<Response> {
+    let mut attempt = 1;
+
+    loop {
+        let result = match req.try_clone().expect("Expected request not stream").send().await {
+            Ok(response) => decapsulate_response(response).await,
+            Err(e) => Err(e.into()),
+        };
+
+        match result {
+            Err(e) if retry.should_retry(&e, attempt, policy) => {
+                let delay = policy.delay(attempt);
+                eprintln!(
+                    "{}, resubmitting the request in {} ms (attempt {})...",
+                    e,
+                    delay.as_millis(),
+                    attempt + 1
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
 /// Wrapper type to convert [`SignatureHeaders`] into [`HeaderMap`]
 struct HeaderWrap(HeaderMap);
 
@@ -107,10 +234,12 @@ enum Request<'a, T: Serialize> {
 /// Submit a signed json encoded request to the provided enpoint
 async fn submit_request<T: Serialize>(
     client: &Client,
+    retry_policy: &RetryPolicy,
     coordinator_address: &Url,
     endpoint: &str,
     keypair: &KeyPair,
     request: Request<'_, T>,
+    retry: Retry,
 ) -> Result<Response>
 where
     T: Serialize,
@@ -146,17 +275,7 @@ where
     let header_map: HeaderWrap = headers.try_into()?;
     req = req.headers(header_map.into());
 
-    loop {
-        let response = req.try_clone().expect("Expected request not stream").send().await?;
-
-        match decapsulate_response(response).await {
-            Ok(response) => return Ok(response),
-            Err(e) => match e {
-                RequestError::Proxy(_) => eprintln!("CDN timeout expired, resubmitting the request..."),
-                _ => return Err(e),
-            },
-        }
-    }
+    send_with_retry(req, retry_policy, retry).await
 }
 
 /// Decapsulate the response and, if error, maps [`Response`] error to [`RequestError`].
@@ -168,10 +287,12 @@ async fn decapsulate_response(response: Response) -> Result<Response> {
     } else if status.is_client_error() {
         Err(RequestError::Client(response.text().await?))
     } else {
-        if status.as_u16() == reqwest::StatusCode::GATEWAY_TIMEOUT.as_u16() {
-            Err(RequestError::Proxy(response.text().await?))
-        } else {
-            Err(RequestError::Server(response.text().await?))
+        match status {
+            StatusCode::GATEWAY_TIMEOUT => Err(RequestError::GatewayTimeout(response.text().await?)),
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => {
+                Err(RequestError::Proxy(response.text().await?))
+            }
+            _ => Err(RequestError::Server(response.text().await?)),
         }
     }
 }
@@ -179,16 +300,19 @@ async fn decapsulate_response(response: Response) -> Result<Response> {
 /// Send a request to the [Coordinator](`phase1-coordinator::Coordinator`) to join the queue of contributors.
 pub async fn post_join_queue(
     client: &Client,
+    retry_policy: &RetryPolicy,
     coordinator_address: &Url,
     keypair: &KeyPair,
     token: &String,
 ) -> Result<()> {
     submit_request::<String>(
         client,
+        retry_policy,
         coordinator_address,
         "contributor/join_queue",
         keypair,
         Request::Post(Some(token)),
+        Retry::OnConnectionError,
     )
     .await?;
 
@@ -196,63 +320,99 @@ pub async fn post_join_queue(
 }
 
 /// Send a request to the [Coordinator](`phase1-coordinator::Coordinator`) to lock the next [Chunk](`phase1-coordinator::objects::Chunk`).
-pub async fn get_lock_chunk(client: &Client, coordinator_address: &Url, keypair: &KeyPair) -> Result<LockedLocators> {
+pub async fn get_lock_chunk(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+) -> Result<LockedChunk> {
     let response = submit_request::<String>(
         client,
+        retry_policy,
         coordinator_address,
         "contributor/lock_chunk",
         keypair,
         Request::Get,
+        Retry::OnConnectionError,
     )
     .await?;
 
-    Ok(response.json::<LockedLocators>().await?)
+    Ok(response.json::<LockedChunk>().await?)
 }
 
 /// Send a request to the [Coordinator](`phase1-coordinator::Coordinator`) to get the next challenge's key.
 pub async fn get_challenge_url(
     client: &Client,
+    retry_policy: &RetryPolicy,
     coordinator_address: &Url,
     keypair: &KeyPair,
     round_height: &u64,
 ) -> Result<String> {
     let response = submit_request(
         client,
+        retry_policy,
         coordinator_address,
         "contributor/challenge",
         keypair,
         Request::Post(Some(round_height)),
+        Retry::Always,
     )
     .await?;
 
     Ok(response.json().await?)
 }
 
+/// Send a request to the [Coordinator](`phase1-coordinator::Coordinator`) to get the key of the challenge built on top of the contribution preceding ours, before its verification.
+pub async fn get_speculative_challenge_url(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+) -> Result<SpeculativeChallenge> {
+    let response = submit_request::<()>(
+        client,
+        retry_policy,
+        coordinator_address,
+        "contributor/speculative_challenge",
+        keypair,
+        Request::Get,
+        Retry::Always,
+    )
+    .await?;
+
+    Ok(response.json::<SpeculativeChallenge>().await?)
+}
+
 /// Send a request to Amazon S3 to download the next challenge.
 pub async fn get_challenge(
     client: &Client,
+    retry_policy: &RetryPolicy,
     challenge_url: &str,
 ) -> Result<(impl Stream<Item = reqwest::Result<Bytes>>, u64)> {
-    let req = client.get(challenge_url);
-    let response = req.send().await?;
-    let stream_len = response.content_length().unwrap();
+    let response = send_with_retry(client.get(challenge_url), retry_policy, Retry::Always).await?;
+    let stream_len = response
+        .content_length()
+        .ok_or_else(|| RequestError::Server(String::from("Missing content length of the challenge")))?;
 
-    Ok((decapsulate_response(response).await?.bytes_stream(), stream_len))
+    Ok((response.bytes_stream(), stream_len))
 }
 
 /// Send a request to the [Coordinator](`phase1-coordinator::Coordinator`) to get the target Strings where to upload the contribution and its signature.
 pub async fn get_contribution_url(
     client: &Client,
+    retry_policy: &RetryPolicy,
     coordinator_address: &Url,
     keypair: &KeyPair,
     round_height: &u64,
 ) -> Result<(String, String)> {
     let response = submit_request::<u64>(
         client,
+        retry_policy,
         coordinator_address,
         "upload/chunk",
         keypair,
         Request::Post(Some(round_height)),
+        Retry::Always,
     )
     .await?;
 
@@ -298,16 +458,19 @@ where
 /// Send a request to notify the [Coordinator](`phase1-coordinator::Coordinator`) of an uploaded contribution.
 pub async fn post_contribute_chunk(
     client: &Client,
+    retry_policy: &RetryPolicy,
     coordinator_address: &Url,
     keypair: &KeyPair,
     request_body: &PostChunkRequest,
 ) -> Result<()> {
     submit_request(
         client,
+        retry_policy,
         coordinator_address,
         "contributor/contribute_chunk",
         keypair,
         Request::Post(Some(request_body)),
+        Retry::OnConnectionError,
     )
     .await?;
 
@@ -315,13 +478,20 @@ pub async fn post_contribute_chunk(
 }
 
 /// Let the [Coordinator](`phase1-coordinator::Coordinator`) know that the contributor is still alive.
-pub async fn post_heartbeat(client: &Client, coordinator_address: &Url, keypair: &KeyPair) -> Result<()> {
+pub async fn post_heartbeat(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+) -> Result<()> {
     submit_request::<String>(
         client,
+        retry_policy,
         coordinator_address,
         "contributor/heartbeat",
         keypair,
         Request::Post(None),
+        Retry::Always,
     )
     .await?;
 
@@ -330,23 +500,65 @@ pub async fn post_heartbeat(client: &Client, coordinator_address: &Url, keypair:
 
 /// Request an update of the [Coordinator](`phase1-coordinator::Coordinator`) state.
 #[cfg(debug_assertions)]
-pub async fn get_update(client: &Client, coordinator_address: &Url, keypair: &KeyPair) -> Result<()> {
-    submit_request::<()>(client, coordinator_address, "/update", keypair, Request::Get).await?;
+pub async fn get_update(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+) -> Result<()> {
+    submit_request::<()>(
+        client,
+        retry_policy,
+        coordinator_address,
+        "/update",
+        keypair,
+        Request::Get,
+        Retry::OnConnectionError,
+    )
+    .await?;
 
     Ok(())
 }
 
 /// Stop the [Coordinator](`phase1-coordinator::Coordinator`).
-pub async fn get_stop_coordinator(client: &Client, coordinator_address: &Url, keypair: &KeyPair) -> Result<()> {
-    submit_request::<()>(client, coordinator_address, "/stop", keypair, Request::Get).await?;
+pub async fn get_stop_coordinator(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+) -> Result<()> {
+    submit_request::<()>(
+        client,
+        retry_policy,
+        coordinator_address,
+        "/stop",
+        keypair,
+        Request::Get,
+        Retry::OnConnectionError,
+    )
+    .await?;
 
     Ok(())
 }
 
 /// Verify the pending contributions.
 #[cfg(debug_assertions)]
-pub async fn get_verify_chunks(client: &Client, coordinator_address: &Url, keypair: &KeyPair) -> Result<()> {
-    submit_request::<()>(client, coordinator_address, "/verify", keypair, Request::Get).await?;
+pub async fn get_verify_chunks(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+) -> Result<()> {
+    submit_request::<()>(
+        client,
+        retry_policy,
+        coordinator_address,
+        "/verify",
+        keypair,
+        Request::Get,
+        Retry::OnConnectionError,
+    )
+    .await?;
 
     Ok(())
 }
@@ -354,15 +566,18 @@ pub async fn get_verify_chunks(client: &Client, coordinator_address: &Url, keypa
 /// Get Contributor queue status.
 pub async fn get_contributor_queue_status(
     client: &Client,
+    retry_policy: &RetryPolicy,
     coordinator_address: &Url,
     keypair: &KeyPair,
 ) -> Result<ContributorStatus> {
     let response = submit_request::<()>(
         client,
+        retry_policy,
         coordinator_address,
         "contributor/queue_status",
         keypair,
         Request::Get,
+        Retry::Always,
     )
     .await?;
 
@@ -372,25 +587,49 @@ pub async fn get_contributor_queue_status(
 /// Send [`ContributionInfo`] to the Coordinator.
 pub async fn post_contribution_info(
     client: &Client,
+    retry_policy: &RetryPolicy,
     coordinator_address: &Url,
     keypair: &KeyPair,
     request_body: &ContributionInfo,
 ) -> Result<()> {
     submit_request::<ContributionInfo>(
         client,
+        retry_policy,
         coordinator_address,
         "contributor/contribution_info",
         keypair,
         Request::Post(Some(request_body)),
+        Retry::OnConnectionError,
     )
     .await?;
 
     Ok(())
 }
 
+/// Get the [`ContributionReceipt`] of the contribution made in the given round.
+pub async fn get_contribution_receipt(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    round_height: &u64,
+) -> Result<ContributionReceipt> {
+    let response = submit_request(
+        client,
+        retry_policy,
+        coordinator_address,
+        "contributor/receipt",
+        keypair,
+        Request::Post(Some(round_height)),
+        Retry::Always,
+    )
+    .await?;
+
+    Ok(response.json().await?)
+}
+
 /// Retrieve the list of contributions, json encoded
-pub async fn get_contributions_info(coordinator_address: &Url) -> Result<Vec<u8>> {
-    let client = Client::builder().brotli(true).build()?;
+pub async fn get_contributions_info(client: &Client, coordinator_address: &Url) -> Result<Vec<u8>> {
     let address = coordinator_address
         .join("/contribution_info")
         .map_err(|_| RequestError::AddressParseError)?;
@@ -405,23 +644,81 @@ pub async fn get_contributions_info(coordinator_address: &Url) -> Result<Vec<u8>
     }
 }
 
+/// Get the set of circuits of the ceremony from the Coordinator.
+pub async fn get_circuit_set(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    coordinator_address: &Url,
+) -> Result<CircuitSet> {
+    let address = coordinator_address
+        .join("/circuit_set")
+        .map_err(|_| RequestError::AddressParseError)?;
+
+    let response = send_with_retry(client.get(address), retry_policy, Retry::Always).await?;
+
+    Ok(response.json::<CircuitSet>().await?)
+}
+
 /// Query health endpoint of the Coordinator to check the connection
-pub async fn ping_coordinator(client: &Client, coordinator_address: &Url) -> Result<()> {
+pub async fn ping_coordinator(client: &Client, retry_policy: &RetryPolicy, coordinator_address: &Url) -> Result<()> {
     let address = coordinator_address
         .join("/healthcheck")
         .map_err(|_| RequestError::AddressParseError)?;
 
-    let req = client.get(address);
+    send_with_retry(client.get(address), retry_policy, Retry::Always).await?;
 
-    loop {
-        let response = req.try_clone().expect("Expected request not stream").send().await?;
-
-        match decapsulate_response(response).await {
-            Ok(_) => return Ok(()),
-            Err(e) => match e {
-                RequestError::Proxy(_) => eprintln!("CDN timeout expired, resubmitting the request..."),
-                _ => return Err(e),
-            },
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1_000),
+        };
+
+        // Half of the exponential backoff is randomized
+        for (attempt, backoff) in [
+            (0, 100),
+            (1, 100),
+            (2, 200),
+            (3, 400),
+            (4, 800),
+            (5, 1_000),
+            (u32::MAX, 1_000),
+        ] {
+            for _ in 0..10 {
+                let delay = policy.delay(attempt);
+                assert!(delay >= Duration::from_millis(backoff / 2));
+                assert!(delay <= Duration::from_millis(backoff));
+            }
         }
     }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::default();
+        let proxy = RequestError::Proxy(String::new());
+        let timeout = RequestError::GatewayTimeout(String::new());
+        let server = RequestError::Server(String::new());
+        let client = RequestError::Client(String::new());
+
+        assert!(Retry::Always.should_retry(&proxy, 1, &policy));
+        assert!(!Retry::Always.should_retry(&proxy, policy.max_attempts, &policy));
+        assert!(!Retry::Always.should_retry(&server, 1, &policy));
+        assert!(!Retry::Always.should_retry(&client, 1, &policy));
+
+        // The request may have reached the coordinator
+        assert!(!Retry::OnConnectionError.should_retry(&proxy, 1, &policy));
+        assert!(!Retry::OnConnectionError.should_retry(&server, 1, &policy));
+
+        // CDN timeouts are resubmitted without limit
+        assert!(Retry::Always.should_retry(&timeout, policy.max_attempts + 10, &policy));
+        assert!(Retry::OnConnectionError.should_retry(&timeout, policy.max_attempts + 10, &policy));
+    }
 }
@@ -0,0 +1,463 @@
+//! Verification of the whole ceremony transcript, from the initialization parameters to the last verified round.
+//!
+//! The transcript is expected to follow the layout of the coordinator storage:
+//!
+//! ```text
+//! round_{n}/state.json
+//! round_{n}/chunk_0/contribution_0.verified
+//! round_{n}/chunk_0/contribution_0.verified.signature
+//! round_{n}/chunk_0/contribution_{i}.unverified.signature
+//! round_{n}/chunk_0/contribution_{i}.verified
+//! round_{n}/chunk_0/contribution_{i}.verified.signature
+//! ```
+//!
+//! The contributors of a round contribute in sequence: contribution `i` is built on top of the verified contribution
+//! `i - 1`, and the verified response of the last contributor of round `n` is stored as the `contribution_0.verified`
+//! of round `n + 1`. Since the next challenge is the contributor's response with its first 64 bytes replaced by the
+//! hash of the response itself, each response can be rebuilt from two consecutive verified files.
+//!
+//! The final contribution of the ceremony can be a random beacon, recorded in `round_{n}/beacon.json`. Its
+//! contribution is recomputed from the beacon value instead of being signed by a contributor or the coordinator.
+
+use phase1_coordinator::{
+    authentication::{Production, Signature},
+    commands::{Beacon, CircuitSet, Initialization, Verification},
+    objects::{ContributionFileSignature, Round},
+};
+use setup_utils::{calculate_hash, BEACON_HASH_ITERATIONS};
+
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::debug;
+
+const HASH_LENGTH: usize = 64;
+const BEACON_FILE: &str = "beacon.json";
+
+/// Error returned from the verification of the transcript.
+#[derive(Debug, Error)]
+pub enum TranscriptError {
+    #[error("A random beacon has already been applied in round {0}")]
+    BeaconAlreadyApplied(u64),
+    #[error("Contribution of round {0} doesn't match its random beacon")]
+    BeaconMismatch(u64),
+    #[error("Challenge hash of round {0} doesn't match the hash embedded in its response")]
+    ChallengeHashMismatch(u64),
+    #[error("Contribution file signature of round {0} doesn't match the transcript files")]
+    ContributionStateMismatch(u64),
+    #[error("Initialization failed: {0}")]
+    InitializationFailed(String),
+    #[error("Initial challenge differs from the recomputed initialization parameters")]
+    InvalidInitialChallenge,
+    #[error("Invalid {0} signature for round {1}")]
+    InvalidSignature(&'static str, u64),
+    #[error("Invalid random beacon in round {0}: {1}")]
+    InvalidBeacon(u64, String),
+    #[error("Invalid MPC contribution in round {0}: {1}")]
+    InvalidContribution(u64, String),
+    #[error("Io error on {0:?}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Json error on {0:?}: {1}")]
+    Json(PathBuf, serde_json::Error),
+    #[error("The last contribution of the transcript is not a random beacon")]
+    MissingBeacon,
+    #[error("Challenge file of round {0} is missing")]
+    MissingChallenge(u64),
+    #[error("Missing {0} signature for round {1}")]
+    MissingSignature(&'static str, u64),
+    #[error("Round 1 challenge differs from the initialization parameters")]
+    MismatchingInitialization,
+    #[error("Transcript doesn't contain any contribution")]
+    NoContributions,
+    #[error("File of round {0} is too short to contain a contribution")]
+    TruncatedFile(u64),
+}
+
+type Result<T> = std::result::Result<T, TranscriptError>;
+
+/// Status of a signature found in the transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// The signature has been checked against the key of the signer.
+    Valid,
+    /// The signature file matches the transcript, but the key of the signer is unknown, so nothing was authenticated.
+    Unchecked,
+    /// No signature file was found. Only reported when the missing signatures are allowed, or for the contribution
+    /// of the random beacon, which is recomputed instead.
+    Missing,
+}
+
+/// A contribution of the transcript which has been successfully verified.
+#[derive(Debug, Clone)]
+pub struct VerifiedRound {
+    /// The round in which the contribution took place.
+    pub round_height: u64,
+    /// The ID of the contribution in its round, starting from 1.
+    pub contribution_id: u64,
+    /// The public key of the contributor, if the round state is available.
+    pub contributor: Option<String>,
+    /// Hash of the contribution, as found in the contributor info file.
+    pub contribution_hash: String,
+    /// Hash of the MPC contributions of all the circuits.
+    pub mpc_hash: String,
+    /// Status of the signature produced by the contributor.
+    pub contributor_signature: SignatureStatus,
+    /// Status of the signature produced by the coordinator after verification.
+    pub coordinator_signature: SignatureStatus,
+    /// The random beacon, if the contribution has been produced from it instead of by a contributor.
+    pub beacon: Option<Beacon>,
+}
+
+fn round_directory(base: &Path, round_height: u64) -> PathBuf {
+    base.join(format!("round_{}", round_height))
+}
+
+fn chunk_file(base: &Path, round_height: u64, file_name: &str) -> PathBuf {
+    round_directory(base, round_height).join("chunk_0").join(file_name)
+}
+
+fn contribution_file_name(contribution_id: u64, is_verified: bool) -> String {
+    let extension = if is_verified { "verified" } else { "unverified" };
+
+    format!("contribution_{}.{}", contribution_id, extension)
+}
+
+/// Returns the round and the name of the file holding the verified response of the given contribution, which is the
+/// initial challenge of the next round for the last contribution of a round.
+fn next_challenge_file(base: &Path, round_height: u64, contribution_id: u64) -> (u64, String) {
+    let file_name = contribution_file_name(contribution_id, true);
+    match chunk_file(base, round_height, &file_name).exists() {
+        true => (round_height, file_name),
+        false => (round_height + 1, contribution_file_name(0, true)),
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| TranscriptError::Io(path.to_owned(), e))
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| TranscriptError::Io(parent.to_owned(), e))?;
+    }
+
+    std::fs::write(path, content).map_err(|e| TranscriptError::Io(path.to_owned(), e))
+}
+
+/// Reads the [`ContributionFileSignature`] at the given path, if it exists.
+fn read_file_signature(path: &Path) -> Result<Option<ContributionFileSignature>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = read(path)?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| TranscriptError::Json(path.to_owned(), e))
+}
+
+/// Reads the [`Round`] state of the given round, if it exists.
+fn read_round_state(base: &Path, round_height: u64) -> Result<Option<Round>> {
+    let path = round_directory(base, round_height).join("state.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = read(&path)?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| TranscriptError::Json(path.to_owned(), e))
+}
+
+/// Reads the [`Beacon`] applied in the given round, if any.
+fn read_beacon(base: &Path, round_height: u64) -> Result<Option<Beacon>> {
+    let path = round_directory(base, round_height).join(BEACON_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = read(&path)?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| TranscriptError::Json(path.to_owned(), e))
+}
+
+/// Checks that the hashes signed in a [`ContributionFileSignature`] match the provided ones and that the signature
+/// was produced by the given key. Without a key the signature can't be authenticated and is reported as
+/// [`SignatureStatus::Unchecked`].
+fn check_file_signature(
+    file_signature: &ContributionFileSignature,
+    pubkey: Option<&str>,
+    challenge_hash: &[u8],
+    response_hash: &[u8],
+    next_challenge_hash: Option<&[u8]>,
+    signer: &'static str,
+    round_height: u64,
+) -> Result<SignatureStatus> {
+    let matching_state = file_signature.get_challenge_hash() == hex::encode(challenge_hash)
+        && file_signature.get_response_hash() == hex::encode(response_hash)
+        && file_signature.get_next_challenge_hash().as_deref() == next_challenge_hash.map(hex::encode).as_deref();
+    if !matching_state {
+        return Err(TranscriptError::ContributionStateMismatch(round_height));
+    }
+
+    let pubkey = match pubkey {
+        Some(pubkey) => pubkey,
+        None => return Ok(SignatureStatus::Unchecked),
+    };
+    let message = file_signature
+        .get_state()
+        .signature_message()
+        .map_err(|_| TranscriptError::InvalidSignature(signer, round_height))?;
+
+    if !Production.verify(pubkey, &message, file_signature.get_signature()) {
+        return Err(TranscriptError::InvalidSignature(signer, round_height));
+    }
+
+    Ok(SignatureStatus::Valid)
+}
+
+/// Verifies the transcript of the ceremony `ceremony_id` on the `circuit_set`, stored in the `base` directory, and
+/// returns the list of verified contributions.
+///
+/// The initial challenge is recomputed and compared with the one of the transcript. The coordinator signatures are
+/// checked against `coordinator_pubkey`. A missing signature is an error, unless `allow_missing_signatures` is set.
+/// With `expect_beacon`, the last contribution must be a random beacon.
+pub fn verify_transcript(
+    base: &Path,
+    circuit_set: CircuitSet,
+    ceremony_id: u64,
+    coordinator_pubkey: &str,
+    allow_missing_signatures: bool,
+    expect_beacon: bool,
+) -> Result<Vec<VerifiedRound>> {
+    // Check the initialization
+    let initial_challenge_path = chunk_file(base, 0, &contribution_file_name(0, true));
+    if !initial_challenge_path.exists() {
+        return Err(TranscriptError::MissingChallenge(0));
+    }
+    let initial_challenge = read(&initial_challenge_path)?;
+    debug!("Recomputing the initial challenge");
+    let expected_initial_challenge = Initialization::initial_challenge(circuit_set, ceremony_id)
+        .map_err(|e| TranscriptError::InitializationFailed(e.to_string()))?;
+    if initial_challenge != expected_initial_challenge {
+        return Err(TranscriptError::InvalidInitialChallenge);
+    }
+    drop(expected_initial_challenge);
+
+    let mut challenge = read(&chunk_file(base, 1, &contribution_file_name(0, true)))
+        .map_err(|_| TranscriptError::MissingChallenge(1))?;
+    if challenge != initial_challenge {
+        return Err(TranscriptError::MismatchingInitialization);
+    }
+    drop(initial_challenge);
+
+    let mut verified_rounds = Vec::new();
+    let mut round_height = 1;
+    let mut contribution_id = 1;
+
+    loop {
+        let (next_round_height, next_challenge_file) = next_challenge_file(base, round_height, contribution_id);
+        let next_challenge_path = chunk_file(base, next_round_height, &next_challenge_file);
+        if !next_challenge_path.exists() {
+            // The last round directory contains a challenge with no contribution yet
+            break;
+        }
+        // The round advances after the verification of its last contribution
+        let is_last_contribution = next_round_height != round_height;
+        debug!("Verifying contribution {} of round {}", contribution_id, round_height);
+        let next_challenge = read(&next_challenge_path)?;
+        if next_challenge.len() < HASH_LENGTH {
+            return Err(TranscriptError::TruncatedFile(next_round_height));
+        }
+
+        // Rebuild the response of the contributor and check that the next challenge is linked to it
+        let challenge_hash = calculate_hash(&challenge);
+        let mut response = challenge_hash.to_vec();
+        response.extend_from_slice(&next_challenge[HASH_LENGTH..]);
+        let response_hash = calculate_hash(&response);
+        if response_hash.as_slice() != &next_challenge[..HASH_LENGTH] {
+            return Err(TranscriptError::ChallengeHashMismatch(next_round_height));
+        }
+
+        // Verify the MPC contributions
+        let verified = Verification::transform_pok_and_correctness(circuit_set, &challenge, &response)
+            .map_err(|e| TranscriptError::InvalidContribution(round_height, e.to_string()))?;
+
+        // Recompute the contribution of the random beacon, which is the last one of its round
+        let beacon = match is_last_contribution {
+            true => read_beacon(base, round_height)?,
+            false => None,
+        };
+        if let Some(beacon) = &beacon {
+            if beacon.round_height != round_height {
+                return Err(TranscriptError::InvalidBeacon(
+                    round_height,
+                    format!("the beacon was applied in round {}", beacon.round_height),
+                ));
+            }
+            if beacon.iterations != BEACON_HASH_ITERATIONS {
+                return Err(TranscriptError::InvalidBeacon(
+                    round_height,
+                    format!(
+                        "{} SHA-256 iterations instead of {}",
+                        beacon.iterations, BEACON_HASH_ITERATIONS
+                    ),
+                ));
+            }
+            let expected_response = beacon
+                .contribute(circuit_set, &challenge)
+                .map_err(|e| TranscriptError::InvalidBeacon(round_height, e.to_string()))?;
+            if expected_response != response {
+                return Err(TranscriptError::BeaconMismatch(round_height));
+            }
+        }
+
+        // Check the signatures
+        let round = read_round_state(base, round_height)?;
+        let contributor = match beacon {
+            Some(_) => None,
+            None => round
+                .as_ref()
+                .and_then(|r| r.chunk(0).ok())
+                .and_then(|c| c.get_contribution(contribution_id).ok())
+                .and_then(|c| c.get_contributor().as_ref())
+                .map(|c| c.address()),
+        };
+        let next_challenge_hash = calculate_hash(&next_challenge);
+        let coordinator_signature_path =
+            chunk_file(base, next_round_height, &format!("{}.signature", next_challenge_file));
+        let coordinator_signature = match read_file_signature(&coordinator_signature_path)? {
+            Some(file_signature) => check_file_signature(
+                &file_signature,
+                Some(coordinator_pubkey),
+                &challenge_hash,
+                &response_hash,
+                Some(&next_challenge_hash),
+                "coordinator",
+                round_height,
+            )?,
+            None => SignatureStatus::Missing,
+        };
+
+        // The contribution of the beacon is not signed by a contributor
+        let contributor_signature_path = chunk_file(
+            base,
+            round_height,
+            &format!("{}.signature", contribution_file_name(contribution_id, false)),
+        );
+        let contributor_signature = match read_file_signature(&contributor_signature_path)? {
+            Some(file_signature) if beacon.is_none() => check_file_signature(
+                &file_signature,
+                contributor.as_deref(),
+                &challenge_hash,
+                &response_hash,
+                None,
+                "contributor",
+                round_height,
+            )?,
+            _ => SignatureStatus::Missing,
+        };
+
+        // The contribution of the beacon is recomputed above, so it doesn't need to be signed
+        if beacon.is_none() && !allow_missing_signatures {
+            if coordinator_signature == SignatureStatus::Missing {
+                return Err(TranscriptError::MissingSignature("coordinator", round_height));
+            }
+            if contributor_signature == SignatureStatus::Missing {
+                return Err(TranscriptError::MissingSignature("contributor", round_height));
+            }
+        }
+
+        verified_rounds.push(VerifiedRound {
+            round_height,
+            contribution_id,
+            contributor,
+            contribution_hash: hex::encode(calculate_hash(&response[HASH_LENGTH..])),
+            mpc_hash: hex::encode(verified.contribution_hash()),
+            contributor_signature,
+            coordinator_signature,
+            beacon,
+        });
+
+        challenge = next_challenge;
+        match is_last_contribution {
+            true => {
+                round_height += 1;
+                contribution_id = 1;
+            }
+            false => contribution_id += 1,
+        }
+    }
+
+    match verified_rounds.last() {
+        None => return Err(TranscriptError::NoContributions),
+        Some(last) if expect_beacon && last.beacon.is_none() => return Err(TranscriptError::MissingBeacon),
+        Some(_) => (),
+    }
+
+    Ok(verified_rounds)
+}
+
+/// Applies the random beacon `value` as the final contribution of the ceremony on the `circuit_set`, stored in the
+/// `base` directory.
+///
+/// The beacon contributes on top of the last challenge of the transcript: its response is written as the last
+/// contribution of the round of the challenge and the next challenge as the initial challenge of a new round, along
+/// with the `beacon.json` file recording the beacon value and the number of iterations.
+///
+/// This is an offline step of the operator, run once the coordinator has been stopped: the round states are not
+/// updated, so the coordinator must not be restarted on the transcript afterwards.
+pub fn apply_beacon(base: &Path, circuit_set: CircuitSet, value: &str) -> Result<Beacon> {
+    // Find the last challenge, which has no contribution yet
+    let mut round_height = 1;
+    let mut contribution_id = 1;
+    if !chunk_file(base, round_height, &contribution_file_name(0, true)).exists() {
+        return Err(TranscriptError::MissingChallenge(round_height));
+    }
+    loop {
+        let (next_round_height, next_challenge_file) = next_challenge_file(base, round_height, contribution_id);
+        if !chunk_file(base, next_round_height, &next_challenge_file).exists() {
+            break;
+        }
+
+        if next_round_height == round_height {
+            contribution_id += 1;
+            continue;
+        }
+        if read_beacon(base, round_height)?.is_some() {
+            return Err(TranscriptError::BeaconAlreadyApplied(round_height));
+        }
+        round_height += 1;
+        contribution_id = 1;
+    }
+
+    let beacon =
+        Beacon::new(round_height, value).map_err(|e| TranscriptError::InvalidBeacon(round_height, e.to_string()))?;
+    let challenge = read(&chunk_file(
+        base,
+        round_height,
+        &contribution_file_name(contribution_id - 1, true),
+    ))?;
+    let response = beacon
+        .contribute(circuit_set, &challenge)
+        .map_err(|e| TranscriptError::InvalidContribution(round_height, e.to_string()))?;
+
+    // The next challenge is the response with the hash of the response itself in place of the challenge hash
+    let mut next_challenge = calculate_hash(&response).to_vec();
+    next_challenge.extend_from_slice(&response[HASH_LENGTH..]);
+
+    let beacon_path = round_directory(base, round_height).join(BEACON_FILE);
+    let beacon_json = serde_json::to_vec_pretty(&beacon).map_err(|e| TranscriptError::Json(beacon_path.clone(), e))?;
+    // The next challenge is written last, so that an interrupted run can be restarted
+    write(&beacon_path, &beacon_json)?;
+    write(
+        &chunk_file(base, round_height, &contribution_file_name(contribution_id, false)),
+        &response,
+    )?;
+    write(
+        &chunk_file(base, round_height + 1, &contribution_file_name(0, true)),
+        &next_challenge,
+    )?;
+
+    Ok(beacon)
+}
@@ -8,11 +8,11 @@ use std::{io::Write, net::IpAddr, sync::Arc};
 
 use phase1_coordinator::{
     authentication::{KeyPair, Production, Signature},
-    commands::{Computation, RandomSource},
+    commands::{CircuitSet, Computation, RandomSource},
     environment::Testing,
     objects::{ContributionInfo, LockedLocators, TrimmedContributionInfo},
-    rest::{self, PostChunkRequest},
-    storage::{ContributionLocator, ContributionSignatureLocator, Object},
+    rest::{self, PostChunkRequest, Triggers},
+    storage::{ContributionLocator, ContributionSignatureLocator},
     testing::coordinator,
     ContributionFileSignature,
     ContributionState,
@@ -37,7 +37,7 @@ use async_stream::try_stream;
 use futures_util::StreamExt;
 use toml::Value;
 
-use phase1_cli::requests;
+use phase1_cli::requests::{self, RetryPolicy};
 use reqwest::{Client, Url};
 
 const ROUND_HEIGHT: u64 = 1;
@@ -132,12 +132,15 @@ async fn test_prelude() -> (TestCtx, JoinHandle<Result<Rocket<Ignite>, Error>>)
             rest::verify_chunks,
             rest::get_contributor_queue_status,
             rest::post_contribution_info,
+            rest::get_contribution_receipt,
             rest::get_contributions_info,
+            rest::get_circuit_set,
             rest::get_healthcheck,
             rest::get_contribution_url,
             rest::get_challenge_url
         ])
         .manage(coordinator)
+        .manage(Arc::new(Triggers::default()))
         .register("/", catchers![
             rest::invalid_signature,
             rest::unauthorized,
@@ -190,15 +193,18 @@ async fn test_stop_coordinator() {
 
     // Wrong, request from non-coordinator participant
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    let response = requests::get_stop_coordinator(&client, &url, &ctx.contributors[0].keypair).await;
+    let response =
+        requests::get_stop_coordinator(&client, &RetryPolicy::default(), &url, &ctx.contributors[0].keypair).await;
     assert!(response.is_err());
 
     // Shut the server down
-    let response = requests::get_stop_coordinator(&client, &url, &ctx.coordinator.keypair).await;
+    let response =
+        requests::get_stop_coordinator(&client, &RetryPolicy::default(), &url, &ctx.coordinator.keypair).await;
     assert!(response.is_ok());
 
     // Try sending another request (server should be unreachable)
-    let response = requests::get_stop_coordinator(&client, &url, &ctx.coordinator.keypair).await;
+    let response =
+        requests::get_stop_coordinator(&client, &RetryPolicy::default(), &url, &ctx.coordinator.keypair).await;
 
     match response {
         Ok(_) => panic!("Expected error"),
@@ -222,14 +228,22 @@ async fn test_get_contributor_queue_status() {
 
     // Non-existing contributor key
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    let response = requests::get_contributor_queue_status(&client, &url, &ctx.unknown_participant.keypair).await;
+    let response = requests::get_contributor_queue_status(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.unknown_participant.keypair,
+    )
+    .await;
     match response.unwrap() {
         rest::ContributorStatus::Other => (),
         _ => panic!("Wrong ContributorStatus"),
     }
 
     // Ok
-    let response = requests::get_contributor_queue_status(&client, &url, &ctx.contributors[0].keypair).await;
+    let response =
+        requests::get_contributor_queue_status(&client, &RetryPolicy::default(), &url, &ctx.contributors[0].keypair)
+            .await;
     match response.unwrap() {
         rest::ContributorStatus::Round => (),
         _ => panic!("Wrong ContributorStatus"),
@@ -249,11 +263,12 @@ async fn test_heartbeat() {
 
     // Non-existing contributor key
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    let response = requests::post_heartbeat(&client, &url, &ctx.unknown_participant.keypair).await;
+    let response =
+        requests::post_heartbeat(&client, &RetryPolicy::default(), &url, &ctx.unknown_participant.keypair).await;
     assert!(response.is_err());
 
     // Ok
-    requests::post_heartbeat(&client, &url, &ctx.contributors[0].keypair)
+    requests::post_heartbeat(&client, &RetryPolicy::default(), &url, &ctx.contributors[0].keypair)
         .await
         .unwrap();
 
@@ -272,13 +287,13 @@ async fn test_update_coordinator() {
     // Wrong, request from non-coordinator
     let url = Url::parse(&ctx.coordinator_url).unwrap();
     assert!(
-        requests::get_update(&client, &url, &ctx.contributors[0].keypair)
+        requests::get_update(&client, &RetryPolicy::default(), &url, &ctx.contributors[0].keypair)
             .await
             .is_err()
     );
 
     // Ok
-    requests::get_update(&client, &url, &ctx.coordinator.keypair)
+    requests::get_update(&client, &RetryPolicy::default(), &url, &ctx.coordinator.keypair)
         .await
         .unwrap();
 
@@ -298,6 +313,7 @@ async fn test_join_queue() {
     let url = Url::parse(&ctx.coordinator_url).unwrap();
     let mut response = requests::post_join_queue(
         &client,
+        &RetryPolicy::default(),
         &url,
         &ctx.contributors[0].keypair,
         &String::from("7fe7c70eda056784fcf5"),
@@ -306,12 +322,20 @@ async fn test_join_queue() {
     assert!(response.is_err());
 
     // Wrong request, invalid token format
-    response = requests::post_join_queue(&client, &url, &ctx.contributors[0].keypair, &String::from("test")).await;
+    response = requests::post_join_queue(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.contributors[0].keypair,
+        &String::from("test"),
+    )
+    .await;
     assert!(response.is_err());
 
     // Ok request
     requests::post_join_queue(
         &client,
+        &RetryPolicy::default(),
         &url,
         &ctx.contributors[0].keypair,
         &String::from("7fe7c70eda056784fcf4"),
@@ -320,7 +344,14 @@ async fn test_join_queue() {
     .unwrap();
 
     // Wrong request, already existing contributor
-    response = requests::post_join_queue(&client, &url, &ctx.contributors[0].keypair, &String::from("test")).await;
+    response = requests::post_join_queue(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.contributors[0].keypair,
+        &String::from("test"),
+    )
+    .await;
     assert!(response.is_err());
 
     // Drop the server
@@ -338,7 +369,8 @@ async fn test_wrong_lock_chunk() {
 
     // Wrong request, unknown participant
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    let response = requests::get_lock_chunk(&client, &url, &ctx.unknown_participant.keypair).await;
+    let response =
+        requests::get_lock_chunk(&client, &RetryPolicy::default(), &url, &ctx.unknown_participant.keypair).await;
     assert!(response.is_err());
 }
 
@@ -357,11 +389,19 @@ async fn test_wrong_contribute_chunk() {
 
     // Non-existing contributor key
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    let response = requests::post_contribute_chunk(&client, &url, &ctx.unknown_participant.keypair, &r).await;
+    let response = requests::post_contribute_chunk(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.unknown_participant.keypair,
+        &r,
+    )
+    .await;
     assert!(response.is_err());
 
     // Non-current-contributor
-    let response = requests::post_contribute_chunk(&client, &url, &ctx.contributors[1].keypair, &r).await;
+    let response =
+        requests::post_contribute_chunk(&client, &RetryPolicy::default(), &url, &ctx.contributors[1].keypair, &r).await;
     assert!(response.is_err());
 
     // Drop the server
@@ -378,7 +418,8 @@ async fn test_wrong_verify() {
 
     // Wrong, request from non-coordinator participant
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    let response = requests::get_verify_chunks(&client, &url, &ctx.contributors[0].keypair).await;
+    let response =
+        requests::get_verify_chunks(&client, &RetryPolicy::default(), &url, &ctx.contributors[0].keypair).await;
     assert!(response.is_err())
 }
 
@@ -394,12 +435,25 @@ async fn test_wrong_post_contribution_info() {
 
     // Non-existing contributor key
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    let response =
-        requests::post_contribution_info(&client, &url, &ctx.unknown_participant.keypair, &contrib_info).await;
+    let response = requests::post_contribution_info(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.unknown_participant.keypair,
+        &contrib_info,
+    )
+    .await;
     assert!(response.is_err());
 
     // Non-current-contributor participant
-    let response = requests::post_contribution_info(&client, &url, &ctx.contributors[1].keypair, &contrib_info).await;
+    let response = requests::post_contribution_info(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.contributors[1].keypair,
+        &contrib_info,
+    )
+    .await;
     assert!(response.is_err());
 
     // Drop the server
@@ -430,12 +484,20 @@ async fn test_contribution() {
     let url = Url::parse(&ctx.coordinator_url).unwrap();
 
     // Get challenge url
-    let challenge_url = requests::get_challenge_url(&client, &url, &ctx.contributors[0].keypair, &ROUND_HEIGHT)
-        .await
-        .unwrap();
+    let challenge_url = requests::get_challenge_url(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.contributors[0].keypair,
+        &ROUND_HEIGHT,
+    )
+    .await
+    .unwrap();
 
     // Get challenge
-    let mut challenge_stream = requests::get_challenge(&client, challenge_url.as_str()).await.unwrap();
+    let mut challenge_stream = requests::get_challenge(&client, &RetryPolicy::default(), challenge_url.as_str())
+        .await
+        .unwrap();
 
     let mut challenge: Vec<u8> = Vec::new();
     while let Some(b) = challenge_stream.0.next().await {
@@ -444,10 +506,15 @@ async fn test_contribution() {
     }
 
     // Get contribution url
-    let (chunk_url, sig_url) =
-        requests::get_contribution_url(&client, &url, &ctx.contributors[0].keypair, &ROUND_HEIGHT)
-            .await
-            .unwrap();
+    let (chunk_url, sig_url) = requests::get_contribution_url(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.contributors[0].keypair,
+        &ROUND_HEIGHT,
+    )
+    .await
+    .unwrap();
 
     // Upload chunk
     let contribution_locator = ContributionLocator::new(ROUND_HEIGHT, 0, 1, false);
@@ -457,15 +524,12 @@ async fn test_contribution() {
     let mut contribution: Vec<u8> = Vec::new();
     contribution.write_all(challenge_hash.as_slice()).unwrap();
     let seed = RandomSource::Seed(rand::thread_rng().gen::<[u8; 32]>());
-    Computation::contribute_test_masp(&challenge, &mut contribution, &seed);
-
-    // Initial contribution size is 2332 but the Coordinator expect ANOMA_BASE_FILE_SIZE. Extend to this size with trailing 0s
-    let contrib_size = Object::anoma_contribution_file_size(ROUND_HEIGHT, 1);
-    contribution.resize(contrib_size as usize, 0);
+    Computation::contribute_circuits(CircuitSet::Test, &challenge, &mut contribution, &seed).unwrap();
 
     let contribution_file_signature_locator = ContributionSignatureLocator::new(ROUND_HEIGHT, 0, 1, false);
 
     let response_hash = calculate_hash(contribution.as_ref());
+    let contribution_hash = calculate_hash(&contribution[64..]);
 
     let contribution_state = ContributionState::new(challenge_hash.to_vec(), response_hash.to_vec(), None).unwrap();
 
@@ -506,26 +570,42 @@ async fn test_contribution() {
         .unwrap()
         .current_contribution()
         .round_height();
+    contrib_info.contribution_hash = hex::encode(contribution_hash);
+    contrib_info.contribution_hash_signature = Production.sign(sigkey, &contrib_info.contribution_hash).unwrap();
+    contrib_info.contribution_file_hash = hex::encode(response_hash);
+    contrib_info.contribution_file_signature = Production.sign(sigkey, &contrib_info.contribution_file_hash).unwrap();
     contrib_info.try_sign(&ctx.contributors[0].keypair).unwrap();
 
-    requests::post_contribution_info(&client, &url, &ctx.contributors[0].keypair, &contrib_info)
-        .await
-        .unwrap();
+    requests::post_contribution_info(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.contributors[0].keypair,
+        &contrib_info,
+    )
+    .await
+    .unwrap();
 
     // Contribute
     let post_chunk = PostChunkRequest::new(ROUND_HEIGHT, contribution_locator, contribution_file_signature_locator);
 
-    requests::post_contribute_chunk(&client, &url, &ctx.contributors[0].keypair, &post_chunk)
-        .await
-        .unwrap();
+    requests::post_contribute_chunk(
+        &client,
+        &RetryPolicy::default(),
+        &url,
+        &ctx.contributors[0].keypair,
+        &post_chunk,
+    )
+    .await
+    .unwrap();
 
     // Verify chunk
-    requests::get_verify_chunks(&client, &url, &ctx.coordinator.keypair)
+    requests::get_verify_chunks(&client, &RetryPolicy::default(), &url, &ctx.coordinator.keypair)
         .await
         .unwrap();
 
     // Get contributions info
-    let summary_bytes = requests::get_contributions_info(&url).await.unwrap();
+    let summary_bytes = requests::get_contributions_info(&client, &url).await.unwrap();
     let summary: Vec<TrimmedContributionInfo> = serde_json::from_slice(&summary_bytes).unwrap();
     assert_eq!(summary.len(), 1);
     assert_eq!(summary[0].public_key(), ctx.contributors[0].keypair.pubkey());
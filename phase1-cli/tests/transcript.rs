@@ -0,0 +1,251 @@
+//  NOTE: these tests build a small transcript of the test circuit set in a temporary directory, following the layout
+//	of the coordinator storage.
+
+use std::path::Path;
+
+use phase1_cli::transcript::{self, SignatureStatus, TranscriptError};
+use phase1_coordinator::{
+    authentication::{KeyPair, Production, Signature},
+    commands::{CircuitSet, Computation, Initialization, RandomSource},
+    ContributionFileSignature,
+    ContributionState,
+};
+use setup_utils::calculate_hash;
+
+const CIRCUIT_SET: CircuitSet = CircuitSet::Test;
+const CEREMONY_ID: u64 = 1;
+const BEACON: &str = "00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054";
+
+struct TestTranscript {
+    dir: tempfile::TempDir,
+    contributor: KeyPair,
+    coordinator: KeyPair,
+}
+
+fn write(path: &Path, content: &[u8]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+fn sign_state(keypair: &KeyPair, state: ContributionState) -> Vec<u8> {
+    let signature = Production
+        .sign(keypair.sigkey(), &state.signature_message().unwrap())
+        .unwrap();
+
+    serde_json::to_vec(&ContributionFileSignature::new(signature, state).unwrap()).unwrap()
+}
+
+/// Builds a transcript with a single contribution in round 1, without the `state.json` files of the rounds.
+fn build_transcript() -> TestTranscript {
+    let dir = tempfile::tempdir().unwrap();
+    let contributor = KeyPair::new();
+    let coordinator = KeyPair::new();
+
+    let challenge = Initialization::initial_challenge(CIRCUIT_SET, CEREMONY_ID).unwrap();
+    write(&dir.path().join("round_0/chunk_0/contribution_0.verified"), &challenge);
+    write(&dir.path().join("round_1/chunk_0/contribution_0.verified"), &challenge);
+
+    let challenge_hash = calculate_hash(&challenge);
+    let mut response = challenge_hash.to_vec();
+    Computation::contribute_circuits(CIRCUIT_SET, &challenge, &mut response, &RandomSource::Seed([1; 32])).unwrap();
+    let response_hash = calculate_hash(&response);
+    let mut next_challenge = response_hash.to_vec();
+    next_challenge.extend_from_slice(&response[64..]);
+    let next_challenge_hash = calculate_hash(&next_challenge);
+
+    let contributor_state = ContributionState::new(challenge_hash.to_vec(), response_hash.to_vec(), None).unwrap();
+    write(
+        &dir.path().join("round_1/chunk_0/contribution_1.unverified.signature"),
+        &sign_state(&contributor, contributor_state),
+    );
+    let coordinator_state = ContributionState::new(
+        challenge_hash.to_vec(),
+        response_hash.to_vec(),
+        Some(next_challenge_hash.to_vec()),
+    )
+    .unwrap();
+    write(
+        &dir.path().join("round_2/chunk_0/contribution_0.verified"),
+        &next_challenge,
+    );
+    write(
+        &dir.path().join("round_2/chunk_0/contribution_0.verified.signature"),
+        &sign_state(&coordinator, coordinator_state),
+    );
+
+    TestTranscript {
+        dir,
+        contributor,
+        coordinator,
+    }
+}
+
+#[test]
+fn test_round_without_state() {
+    let transcript = build_transcript();
+
+    let rounds = transcript::verify_transcript(
+        transcript.dir.path(),
+        CIRCUIT_SET,
+        CEREMONY_ID,
+        transcript.coordinator.pubkey(),
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(rounds.len(), 1);
+    assert_eq!(rounds[0].round_height, 1);
+    assert_eq!(rounds[0].contribution_id, 1);
+    // Without the round state the contributor is unknown, so its signature can't be authenticated
+    assert!(rounds[0].contributor.is_none());
+    assert_eq!(rounds[0].contributor_signature, SignatureStatus::Unchecked);
+    assert_eq!(rounds[0].coordinator_signature, SignatureStatus::Valid);
+
+    // A deleted signature is rejected, unless the missing signatures are explicitly allowed
+    std::fs::remove_file(
+        transcript
+            .dir
+            .path()
+            .join("round_1/chunk_0/contribution_1.unverified.signature"),
+    )
+    .unwrap();
+    let verify = |allow_missing_signatures| {
+        transcript::verify_transcript(
+            transcript.dir.path(),
+            CIRCUIT_SET,
+            CEREMONY_ID,
+            transcript.coordinator.pubkey(),
+            allow_missing_signatures,
+            false,
+        )
+    };
+    assert!(matches!(
+        verify(false),
+        Err(TranscriptError::MissingSignature("contributor", 1))
+    ));
+    let rounds = verify(true).unwrap();
+    assert_eq!(rounds[0].contributor_signature, SignatureStatus::Missing);
+}
+
+#[test]
+fn test_initialization() {
+    let transcript = build_transcript();
+    let verify = |ceremony_id| {
+        transcript::verify_transcript(
+            transcript.dir.path(),
+            CIRCUIT_SET,
+            ceremony_id,
+            transcript.coordinator.pubkey(),
+            false,
+            false,
+        )
+    };
+    assert!(verify(CEREMONY_ID).is_ok());
+
+    // The initial challenge is recomputed for the given ceremony
+    assert!(matches!(
+        verify(CEREMONY_ID + 1),
+        Err(TranscriptError::InvalidInitialChallenge)
+    ));
+
+    // Tampered initial parameters are rejected, even if they are built on top of the blank hash
+    let initial_challenge_path = transcript.dir.path().join("round_0/chunk_0/contribution_0.verified");
+    let mut initial_challenge = std::fs::read(&initial_challenge_path).unwrap();
+    let last = initial_challenge.len() - 1;
+    initial_challenge[last] ^= 1;
+    write(&initial_challenge_path, &initial_challenge);
+    assert!(matches!(
+        verify(CEREMONY_ID),
+        Err(TranscriptError::InvalidInitialChallenge)
+    ));
+}
+
+#[test]
+fn test_tampered_signature() {
+    let transcript = build_transcript();
+    let signature_path = transcript
+        .dir
+        .path()
+        .join("round_2/chunk_0/contribution_0.verified.signature");
+    let file_signature: ContributionFileSignature =
+        serde_json::from_slice(&std::fs::read(&signature_path).unwrap()).unwrap();
+
+    // Signature produced by another key than the coordinator one
+    write(
+        &signature_path,
+        &sign_state(&transcript.contributor, file_signature.get_state().clone()),
+    );
+    let result = transcript::verify_transcript(
+        transcript.dir.path(),
+        CIRCUIT_SET,
+        CEREMONY_ID,
+        transcript.coordinator.pubkey(),
+        false,
+        false,
+    );
+    assert!(matches!(
+        result,
+        Err(TranscriptError::InvalidSignature("coordinator", 1))
+    ));
+
+    // Signed state not matching the transcript files
+    let mut response_hash = hex::decode(file_signature.get_response_hash()).unwrap();
+    response_hash[0] ^= 1;
+    let tampered_state = ContributionState::new(
+        hex::decode(file_signature.get_challenge_hash()).unwrap(),
+        response_hash,
+        file_signature
+            .get_next_challenge_hash()
+            .as_ref()
+            .map(|h| hex::decode(h).unwrap()),
+    )
+    .unwrap();
+    write(&signature_path, &sign_state(&transcript.coordinator, tampered_state));
+    let result = transcript::verify_transcript(
+        transcript.dir.path(),
+        CIRCUIT_SET,
+        CEREMONY_ID,
+        transcript.coordinator.pubkey(),
+        false,
+        false,
+    );
+    assert!(matches!(result, Err(TranscriptError::ContributionStateMismatch(1))));
+}
+
+#[test]
+fn test_beacon() {
+    let transcript = build_transcript();
+    let verify = |expect_beacon| {
+        transcript::verify_transcript(
+            transcript.dir.path(),
+            CIRCUIT_SET,
+            CEREMONY_ID,
+            transcript.coordinator.pubkey(),
+            false,
+            expect_beacon,
+        )
+    };
+    assert!(matches!(verify(true), Err(TranscriptError::MissingBeacon)));
+
+    // The beacon contributes on top of the last challenge, in round 2
+    let beacon = transcript::apply_beacon(transcript.dir.path(), CIRCUIT_SET, BEACON).unwrap();
+    assert_eq!(beacon.round_height, 2);
+    assert_eq!(beacon.value, BEACON);
+    let rounds = verify(true).unwrap();
+    assert_eq!(rounds.len(), 2);
+    assert_eq!(rounds[1].round_height, 2);
+    assert_eq!(rounds[1].beacon, Some(beacon));
+    assert_eq!(rounds[1].contributor_signature, SignatureStatus::Missing);
+
+    assert!(matches!(
+        transcript::apply_beacon(transcript.dir.path(), CIRCUIT_SET, BEACON),
+        Err(TranscriptError::BeaconAlreadyApplied(2))
+    ));
+
+    // A beacon with another number of iterations is rejected
+    let beacon_path = transcript.dir.path().join("round_2/beacon.json");
+    let mut beacon_json: serde_json::Value = serde_json::from_slice(&std::fs::read(&beacon_path).unwrap()).unwrap();
+    beacon_json["iterations"] = 1.into();
+    write(&beacon_path, &serde_json::to_vec(&beacon_json).unwrap());
+    assert!(matches!(verify(true), Err(TranscriptError::InvalidBeacon(2, _))));
+}
@@ -79,13 +79,17 @@ impl SigTrait for Production {
 
     /// Verifies the given signature for the given message and public key,
     /// and returns `true` if the signature is valid.
-    /// Public key and signature are expected to be [`hex`] encoded.
+    /// Public key and signature are expected to be [`hex`] encoded, returns
+    /// `false` if they are not correctly encoded.
     fn verify(&self, public_key: &str, message: &str, signature: &str) -> bool {
-        let public_key_bytes = hex::decode(public_key).expect("Invalid public key encoding");
-        let public_key = PublicKey::from_slice(public_key_bytes.as_ref()).expect("Invalid public key");
-
-        let signature_bytes = hex::decode(signature).expect("Invalid signature encoding");
-        let signature = Signature::from_slice(signature_bytes.as_ref()).expect("Invalid signature");
+        let public_key = match hex::decode(public_key).map(|bytes| PublicKey::from_slice(bytes.as_ref())) {
+            Ok(Ok(public_key)) => public_key,
+            _ => return false,
+        };
+        let signature = match hex::decode(signature).map(|bytes| Signature::from_slice(bytes.as_ref())) {
+            Ok(Ok(signature)) => signature,
+            _ => return false,
+        };
 
         public_key.verify(message, &signature).is_ok()
     }
@@ -104,4 +108,17 @@ mod tests {
 
         assert!(sig_scheme.verify(keypair.pubkey(), msg, signature.as_ref()));
     }
+
+    #[test]
+    fn verify_badly_encoded() {
+        let sig_scheme = Production;
+        let keypair = KeyPair::new();
+        let msg = "This is the message to sign";
+        let signature = sig_scheme.sign(keypair.sigkey(), msg).unwrap();
+
+        assert!(!sig_scheme.verify("not hex", msg, signature.as_ref()));
+        assert!(!sig_scheme.verify("abcd", msg, signature.as_ref()));
+        assert!(!sig_scheme.verify(keypair.pubkey(), msg, "not hex"));
+        assert!(!sig_scheme.verify(keypair.pubkey(), msg, &signature[..64]));
+    }
 }
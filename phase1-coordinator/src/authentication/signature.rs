@@ -1,3 +1,7 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
 /// A core structure for authentication of contributions.
 pub trait Signature: Send + Sync {
     /// Returns the name of the signature scheme.
@@ -14,3 +18,16 @@ pub trait Signature: Send + Sync {
     /// and returns `true` if the signature is valid.
     fn verify(&self, public_key: &str, message: &str, signature: &str) -> bool;
 }
+
+/// Calculates the hash of the json string encoding all the fields of a struct
+/// except for the signature itself, stored in the `signature_field` field.
+pub fn hash_for_signature<T: Serialize>(value: &T, signature_field: &str) -> Result<String, serde_json::Error> {
+    let mut map: Map<String, Value> = serde_json::from_value(serde_json::to_value(value)?)?;
+    map.remove(signature_field);
+
+    // Compute digest
+    let mut hasher = Sha256::new();
+    hasher.update(Value::Object(map).to_string());
+
+    Ok(format!("{:x?}", hasher.finalize()))
+}
@@ -0,0 +1,92 @@
+use crate::commands::{CircuitSet, Computation, Phase2Error, RandomSource, Seed};
+use setup_utils::{calculate_hash, iterate_beacon_hash, BEACON_HASH_ITERATIONS};
+
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use tracing::{debug, info};
+
+///
+/// Public randomness applied as the final contribution of the ceremony.
+///
+/// The operator supplies a value nobody could predict before the last contribution, e.g. the
+/// hash of a future block. The seed of the contribution is derived from it with iterated SHA-256,
+/// as in [`setup_utils::beacon_randomness`], so that anyone can recompute the contribution.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Beacon {
+    /// The round in which the beacon has been applied
+    pub round_height: u64,
+    /// The public beacon value, hex encoded
+    pub value: String,
+    /// Number of SHA-256 iterations applied to the value to derive the seed
+    pub iterations: u64,
+}
+
+impl Beacon {
+    /// Creates a beacon from its hex encoded value, with the default number of iterations.
+    pub fn new(round_height: u64, value: &str) -> Result<Self, Phase2Error> {
+        let beacon = Self {
+            round_height,
+            value: value.trim_start_matches("0x").to_lowercase(),
+            iterations: BEACON_HASH_ITERATIONS,
+        };
+        beacon.decode_value()?;
+
+        Ok(beacon)
+    }
+
+    fn decode_value(&self) -> Result<[u8; 32], Phase2Error> {
+        hex::decode(&self.value)
+            .ok()
+            .and_then(|value| value.try_into().ok())
+            .ok_or_else(|| Phase2Error::InvalidBeacon(self.value.clone()))
+    }
+
+    /// Derives the seed of the contribution from the beacon value.
+    pub fn seed(&self) -> Result<Seed, Phase2Error> {
+        Ok(iterate_beacon_hash(self.decode_value()?, self.iterations))
+    }
+
+    /// Computes the beacon contribution on top of the given challenge and returns the response file.
+    pub fn contribute(&self, circuit_set: CircuitSet, challenge: &[u8]) -> Result<Vec<u8>, Phase2Error> {
+        let seed = self.seed()?;
+        debug!("Beacon seed is {}", hex::encode(seed));
+
+        let mut response = calculate_hash(challenge).to_vec();
+        Computation::contribute_circuits(circuit_set, challenge, &mut response, &RandomSource::Seed(seed))?;
+        info!("Applied the random beacon {} on round {}", self.value, self.round_height);
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{Beacon, CircuitSet, Initialization, Phase2Error, Verification};
+
+    const BEACON: &str = "00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054";
+
+    #[test]
+    fn test_beacon_contribute() {
+        let challenge = Initialization::initial_challenge(CircuitSet::Test, 0).unwrap();
+        let beacon = Beacon::new(1, BEACON).unwrap();
+
+        // The contribution is valid and can be recomputed from the beacon
+        let response = beacon.contribute(CircuitSet::Test, &challenge).unwrap();
+        assert!(Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &response).is_ok());
+        assert_eq!(beacon.contribute(CircuitSet::Test, &challenge).unwrap(), response);
+
+        // Another beacon produces another contribution
+        let mut other = beacon.clone();
+        other.iterations += 1;
+        assert_ne!(other.seed().unwrap(), beacon.seed().unwrap());
+        assert_ne!(other.contribute(CircuitSet::Test, &challenge).unwrap(), response);
+    }
+
+    #[test]
+    fn test_beacon_value() {
+        assert_eq!(Beacon::new(1, &format!("0x{}", BEACON)).unwrap().value, BEACON);
+        assert!(matches!(Beacon::new(1, "00ff"), Err(Phase2Error::InvalidBeacon(_))));
+        assert!(matches!(Beacon::new(1, "beacon"), Err(Phase2Error::InvalidBeacon(_))));
+    }
+}
@@ -0,0 +1,409 @@
+use crate::commands::{ContributionHeader, Phase2Error};
+
+use bellman::{Circuit, ConstraintSystem, SynthesisError};
+use bls12_381::Scalar;
+use masp_phase2::MPCParameters;
+use once_cell::sync::Lazy;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{Mutex, PoisonError},
+};
+
+const MASP_NAME: &str = "masp";
+const TEST_NAME: &str = "test";
+
+/// The Spend, Output and Convert circuits of the MASP. The size of their initial parameters is hardcoded, since
+/// generating them takes a while.
+static MASP_CIRCUITS: Lazy<CircuitList> = Lazy::new(|| {
+    CircuitList::with_parameters_size(
+        MASP_NAME,
+        vec![
+            Box::new(BlankCircuit::new("Spend", || masp_proofs::circuit::sapling::Spend {
+                value_commitment: None,
+                proof_generation_key: None,
+                payment_address: None,
+                commitment_randomness: None,
+                ar: None,
+                auth_path: vec![None; 32], // Tree depth is 32 for sapling
+                anchor: None,
+            })),
+            Box::new(BlankCircuit::new("Output", || masp_proofs::circuit::sapling::Output {
+                value_commitment: None,
+                payment_address: None,
+                commitment_randomness: None,
+                esk: None,
+                asset_identifier: vec![None; 256],
+            })),
+            Box::new(BlankCircuit::new("Convert", || {
+                masp_proofs::circuit::convert::Convert {
+                    value_commitment: None,
+                    auth_path: vec![None; 32], // Tree depth is 32 for sapling
+                    anchor: None,
+                }
+            })),
+        ],
+        // Parameters generated from `masp-mpc` crate
+        84_720_180,
+    )
+});
+
+static TEST_CIRCUITS: Lazy<CircuitList> = Lazy::new(|| {
+    CircuitList::new(TEST_NAME, vec![Box::new(BlankCircuit::new("Test", || TestCircuit {
+        x: Some(Scalar::one()),
+    }))])
+    .expect("The test circuit can be synthesized")
+});
+
+/// Custom circuit sets, added with [`CircuitSet::register`].
+static CUSTOM_CIRCUITS: Lazy<Mutex<Vec<&'static CircuitList>>> = Lazy::new(Default::default);
+
+/// Number of bytes added to the parameters of a circuit by each contribution, i.e. the size of the public key of the
+/// contributor appended to the parameters.
+pub const CIRCUIT_CONTRIBUTION_SIZE: u64 = 544;
+
+/// A circuit whose parameters are generated by the ceremony.
+pub trait CeremonyCircuit: Send + Sync {
+    /// Name of the circuit, recorded in the contribution file.
+    fn name(&self) -> &'static str;
+
+    /// Generates the initial MPC parameters of the circuit.
+    fn initial_parameters(&self) -> Result<MPCParameters, SynthesisError>;
+}
+
+/// A [`CeremonyCircuit`] built from a function returning a blank instance of a bellman circuit.
+pub struct BlankCircuit<F> {
+    name: &'static str,
+    blank: F,
+}
+
+impl<F> BlankCircuit<F> {
+    pub const fn new(name: &'static str, blank: F) -> Self {
+        Self { name, blank }
+    }
+}
+
+impl<C, F> CeremonyCircuit for BlankCircuit<F>
+where
+    C: Circuit<Scalar>,
+    F: Fn() -> C + Send + Sync,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn initial_parameters(&self) -> Result<MPCParameters, SynthesisError> {
+        MPCParameters::new((self.blank)())
+    }
+}
+
+/// Toy circuit enforcing `x = x^2`, used by the [`CircuitSet::Test`] ceremonies.
+struct TestCircuit {
+    x: Option<Scalar>,
+}
+
+impl Circuit<Scalar> for TestCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let x_value = self.x;
+        let x = cs.alloc(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?;
+
+        cs.enforce(|| "x = x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x);
+
+        Ok(())
+    }
+}
+
+/// Ordered list of named circuits, along with the size of their initial parameters.
+pub struct CircuitList {
+    name: &'static str,
+    circuits: Vec<Box<dyn CeremonyCircuit>>,
+    circuit_names: Vec<&'static str>,
+    parameters_size: u64,
+}
+
+impl CircuitList {
+    /// Creates the list `name` of the `circuits`, given in the order in which they appear in the contribution file.
+    /// The initial parameters of the circuits are generated to compute their size.
+    pub fn new(name: &'static str, circuits: Vec<Box<dyn CeremonyCircuit>>) -> Result<Self, Phase2Error> {
+        if circuits.is_empty() {
+            return Err(Phase2Error::EmptyCircuitSet(name));
+        }
+
+        let mut parameters_size = 0;
+        for circuit in &circuits {
+            let params = circuit
+                .initial_parameters()
+                .map_err(|e| Phase2Error::Synthesis(circuit.name(), e))?;
+            let mut bytes = Vec::new();
+            params.write(&mut bytes)?;
+            parameters_size += bytes.len() as u64;
+        }
+
+        Ok(Self::with_parameters_size(name, circuits, parameters_size))
+    }
+
+    fn with_parameters_size(name: &'static str, circuits: Vec<Box<dyn CeremonyCircuit>>, parameters_size: u64) -> Self {
+        let circuit_names = circuits.iter().map(|circuit| circuit.name()).collect();
+        Self {
+            name,
+            circuits,
+            circuit_names,
+            parameters_size,
+        }
+    }
+
+    /// Name of the list, used to select the circuit set.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl fmt::Debug for CircuitList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitList")
+            .field("name", &self.name)
+            .field("circuits", &self.circuit_names)
+            .field("parameters_size", &self.parameters_size)
+            .finish()
+    }
+}
+
+impl PartialEq for CircuitList {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for CircuitList {}
+
+/// Set of circuits the parameters of the ceremony are generated for.
+///
+/// The circuit set is part of the [`Environment`](crate::environment::Environment) of the coordinator, which announces
+/// it to the contributors so that they compute and verify the contributions with the matching code path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitSet {
+    /// The Spend, Output and Convert circuits of the MASP
+    Masp,
+    /// A single toy circuit, to run quick test ceremonies
+    Test,
+    /// Circuits supplied by the binary running the ceremony, see [`CircuitSet::register`]
+    Custom(&'static CircuitList),
+}
+
+impl CircuitSet {
+    /// Registers the `circuits` as a custom set, which can then be selected by name like the built-in sets, e.g. with
+    /// `--circuit-set` or `NAMADA_MPC_CIRCUIT_SET`. The coordinator and the contributors must register the same
+    /// circuits before selecting the set.
+    pub fn register(circuits: CircuitList) -> Result<Self, Phase2Error> {
+        let mut custom = CUSTOM_CIRCUITS.lock().unwrap_or_else(PoisonError::into_inner);
+        if [MASP_NAME, TEST_NAME].contains(&circuits.name) || custom.iter().any(|set| set.name == circuits.name) {
+            return Err(Phase2Error::DuplicateCircuitSet(circuits.name));
+        }
+
+        // Like the built-in sets, the circuits live until the end of the process
+        let circuits: &'static CircuitList = Box::leak(Box::new(circuits));
+        custom.push(circuits);
+
+        Ok(Self::Custom(circuits))
+    }
+
+    fn list(&self) -> &'static CircuitList {
+        match *self {
+            Self::Masp => Lazy::force(&MASP_CIRCUITS),
+            Self::Test => Lazy::force(&TEST_CIRCUITS),
+            Self::Custom(circuits) => circuits,
+        }
+    }
+
+    /// Circuits of the set, in the order in which they appear in the contribution file
+    pub fn circuits(&self) -> &'static [Box<dyn CeremonyCircuit>] {
+        &self.list().circuits
+    }
+
+    /// Names of the circuits, in the order in which they appear in the contribution file
+    pub fn circuit_names(&self) -> &'static [&'static str] {
+        &self.list().circuit_names
+    }
+
+    /// Size of the initial parameters of all the circuits.
+    fn parameters_size(&self) -> u64 {
+        self.list().parameters_size
+    }
+
+    /// Size of the initial challenge, including the 64 bytes of the hash placed at the head of the contribution file
+    /// and the [`ContributionHeader`].
+    pub fn base_file_size(&self) -> u64 {
+        64 + ContributionHeader::size_for(self.circuit_names()) + self.parameters_size()
+    }
+
+    /// Number of bytes added to the contribution file by each contribution.
+    pub fn per_contribution_file_size_increase(&self) -> u64 {
+        CIRCUIT_CONTRIBUTION_SIZE * self.circuit_names().len() as u64
+    }
+
+    /// Returns the expected size of a contribution file containing `number_of_contributions` contributions, counted
+    /// over all the rounds since the initialization.
+    pub fn contribution_file_size(&self, number_of_contributions: u64) -> u64 {
+        self.base_file_size() + self.per_contribution_file_size_increase() * number_of_contributions
+    }
+
+    /// Returns the expected size of the response computed on top of a challenge of `challenge_size` bytes.
+    pub fn response_file_size(&self, challenge_size: u64) -> u64 {
+        challenge_size + self.per_contribution_file_size_increase()
+    }
+}
+
+impl Default for CircuitSet {
+    fn default() -> Self {
+        Self::Masp
+    }
+}
+
+impl fmt::Display for CircuitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Masp => write!(f, "{}", MASP_NAME),
+            Self::Test => write!(f, "{}", TEST_NAME),
+            Self::Custom(circuits) => write!(f, "{}", circuits.name),
+        }
+    }
+}
+
+impl FromStr for CircuitSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            MASP_NAME => Ok(Self::Masp),
+            TEST_NAME => Ok(Self::Test),
+            _ => {
+                let custom = CUSTOM_CIRCUITS.lock().unwrap_or_else(PoisonError::into_inner);
+                match custom.iter().find(|set| set.name == s) {
+                    Some(&circuits) => Ok(Self::Custom(circuits)),
+                    None => {
+                        let names: Vec<_> = [MASP_NAME, TEST_NAME]
+                            .iter()
+                            .copied()
+                            .chain(custom.iter().map(|set| set.name))
+                            .collect();
+                        Err(format!(
+                            "Invalid circuit set {}, expected one of: {}",
+                            s,
+                            names.join(", ")
+                        ))
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The circuit set is announced by its name, which is resolved against the sets known to the receiver
+impl Serialize for CircuitSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CircuitSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlankCircuit, CircuitList, CircuitSet, TestCircuit};
+    use crate::commands::{Computation, Extraction, Initialization, Phase2Error, RandomSource, Verification};
+
+    use bls12_381::Scalar;
+    use setup_utils::calculate_hash;
+
+    #[test]
+    fn contribution_file_size() {
+        assert_eq!(CircuitSet::Test.contribution_file_size(0), 2_363);
+        assert_eq!(CircuitSet::Test.contribution_file_size(1), 2_363 + 544);
+        assert_eq!(CircuitSet::Masp.contribution_file_size(3), 84_720_307 + 3 * 1_632);
+        assert_eq!(
+            CircuitSet::Masp.response_file_size(CircuitSet::Masp.contribution_file_size(3)),
+            CircuitSet::Masp.contribution_file_size(4)
+        );
+    }
+
+    #[test]
+    fn circuit_names() {
+        assert_eq!(CircuitSet::Masp.circuit_names(), ["Spend", "Output", "Convert"]);
+        assert_eq!(CircuitSet::Test.circuit_names(), ["Test"]);
+    }
+
+    #[test]
+    #[ignore]
+    // The size of the MASP parameters is hardcoded, check that it matches the parameters generated by the circuits
+    fn masp_parameters_size() {
+        let size: u64 = CircuitSet::Masp
+            .circuits()
+            .iter()
+            .map(|circuit| {
+                let mut bytes = Vec::new();
+                circuit.initial_parameters().unwrap().write(&mut bytes).unwrap();
+                bytes.len() as u64
+            })
+            .sum();
+        assert_eq!(size, CircuitSet::Masp.parameters_size());
+    }
+
+    #[test]
+    fn custom() {
+        let circuits = CircuitList::new("toy", vec![
+            Box::new(BlankCircuit::new("First", || TestCircuit { x: Some(Scalar::one()) })),
+            Box::new(BlankCircuit::new("Second", || TestCircuit {
+                x: Some(Scalar::zero()),
+            })),
+        ])
+        .unwrap();
+        let set = CircuitSet::register(circuits).unwrap();
+        assert_eq!(set.circuit_names(), ["First", "Second"]);
+        assert_eq!(set.per_contribution_file_size_increase(), 2 * 544);
+
+        // The set is selected by its name, which can't be registered twice
+        assert_eq!("toy".parse::<CircuitSet>().unwrap(), set);
+        assert_eq!(serde_json::to_string(&set).unwrap(), "\"toy\"");
+        assert_eq!(serde_json::from_str::<CircuitSet>("\"toy\"").unwrap(), set);
+        let duplicate = CircuitList::new("toy", vec![Box::new(BlankCircuit::new("Test", || TestCircuit {
+            x: Some(Scalar::one()),
+        }))])
+        .unwrap();
+        assert!(matches!(
+            CircuitSet::register(duplicate),
+            Err(Phase2Error::DuplicateCircuitSet("toy"))
+        ));
+        assert!(matches!(
+            CircuitList::new("empty", Vec::new()),
+            Err(Phase2Error::EmptyCircuitSet("empty"))
+        ));
+
+        let challenge = Initialization::initial_challenge(set, 0).unwrap();
+        assert_eq!(challenge.len() as u64, set.contribution_file_size(0));
+
+        let mut response = calculate_hash(&challenge).to_vec();
+        Computation::contribute_circuits(set, &challenge, &mut response, &RandomSource::Seed([1; 32])).unwrap();
+        assert_eq!(response.len() as u64, set.contribution_file_size(1));
+        Verification::transform_pok_and_correctness(set, &challenge, &response).unwrap();
+
+        let file_names: Vec<_> = Extraction::run(set, &response)
+            .unwrap()
+            .iter()
+            .map(|parameters| parameters.file_name())
+            .collect();
+        assert_eq!(file_names, ["toy-first.params", "toy-second.params"]);
+    }
+
+    #[test]
+    fn parse() {
+        for set in [CircuitSet::Masp, CircuitSet::Test] {
+            assert_eq!(set.to_string().parse::<CircuitSet>().unwrap(), set);
+        }
+        assert!("groth".parse::<CircuitSet>().is_err());
+    }
+}
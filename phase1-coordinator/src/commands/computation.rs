@@ -1,6 +1,14 @@
 use crate::{
     authentication::Signature,
-    commands::SigningKey,
+    commands::{
+        CircuitEntry,
+        CircuitSet,
+        ContributionHeader,
+        ContributionHeaderError,
+        Phase2Error,
+        SigningKey,
+        CIRCUIT_CONTRIBUTION_SIZE,
+    },
     environment::Environment,
     storage::{Disk, Locator, StorageLocator, StorageObject},
     CoordinatorError,
@@ -9,7 +17,11 @@ use phase1::helpers::CurveKind;
 
 use setup_utils::calculate_hash;
 
-use std::{io::Write, sync::Arc, time::Instant};
+use std::{
+    io::{self, Write},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tracing::{debug, error, info, trace};
 
 pub const SEED_LENGTH: usize = 32;
@@ -18,6 +30,9 @@ pub type Seed = [u8; SEED_LENGTH];
 use blake2::{Blake2b512, Digest};
 use itertools::Itertools;
 use masp_phase2::MPCParameters;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use rayon::prelude::*;
 
 /// Sources of randomness
 pub enum RandomSource {
@@ -27,6 +42,56 @@ pub enum RandomSource {
     Seed(Seed),
 }
 
+/// Steps of the contribution to a single circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContributionStage {
+    /// Reading the parameters of the circuit from the challenge
+    Reading,
+    /// Contributing randomness to the parameters
+    Contributing,
+    /// Writing the updated parameters to the response
+    Writing,
+    /// The circuit has been fully processed
+    Done,
+}
+
+/// Progress of a contribution, reported at the beginning of each [`ContributionStage`]
+#[derive(Debug, Clone, Copy)]
+pub struct ContributionProgress {
+    /// The set of circuits being contributed to
+    pub circuit_set: CircuitSet,
+    /// Index of the circuit in [`CircuitSet::circuits`]
+    pub circuit_index: usize,
+    /// The stage that has just been reached
+    pub stage: ContributionStage,
+}
+
+impl ContributionProgress {
+    /// Returns the name of the circuit
+    pub fn circuit(&self) -> &'static str {
+        self.circuit_set.circuit_names()[self.circuit_index]
+    }
+}
+
+/// Writer counting the bytes written to the inner writer
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 pub struct Computation;
 
 impl Computation {
@@ -68,16 +133,20 @@ impl Computation {
         // Run computation on chunk.
         let settings = environment.parameters();
         let curve = settings.curve();
+        let circuit_set = environment.circuit_set();
         if let Err(error) = match curve {
             CurveKind::Bls12_381 => Self::contribute(
+                circuit_set,
                 storage.reader(challenge_locator)?.as_ref(),
                 storage.writer(response_locator)?.as_mut(),
             ),
             CurveKind::Bls12_377 => Self::contribute(
+                circuit_set,
                 storage.reader(challenge_locator)?.as_ref(),
                 storage.writer(response_locator)?.as_mut(),
             ),
             CurveKind::BW6 => Self::contribute(
+                circuit_set,
                 storage.reader(challenge_locator)?.as_ref(),
                 storage.writer(response_locator)?.as_mut(),
             ),
@@ -123,7 +192,11 @@ impl Computation {
         Ok(())
     }
 
-    fn contribute(challenge_reader: &[u8], mut response_writer: &mut [u8]) -> Result<(), CoordinatorError> {
+    fn contribute(
+        circuit_set: CircuitSet,
+        challenge_reader: &[u8],
+        mut response_writer: &mut [u8],
+    ) -> Result<(), CoordinatorError> {
         trace!("Calculating previous contribution hash and writing it to the response");
 
         let challenge_hash = calculate_hash(&challenge_reader);
@@ -144,13 +217,9 @@ impl Computation {
         // Perform the transformation
         trace!("Computing and writing your contribution, this could take a while");
 
-        // Contribute to the MASP circuit
+        // Contribute to the circuits
         let rand_source = RandomSource::Entropy(String::from("entropy"));
-        #[cfg(debug_assertions)]
-        Self::contribute_test_masp(&challenge_reader, &mut response_writer, &rand_source);
-
-        #[cfg(not(debug_assertions))]
-        Self::contribute_masp(&challenge_reader, &mut response_writer, &rand_source);
+        Self::contribute_circuits(circuit_set, &challenge_reader, &mut response_writer, &rand_source)?;
 
         trace!("Finishing writing your contribution to response file");
 
@@ -159,160 +228,160 @@ impl Computation {
 
     // The [`ContributionFile`] has the following format
     // | previous_contribution_file_hash (64 bytes) |
+    // | header |
     // | masp_mpc_new_parameters_contribution |
-    // The masp-mpc commands are executed at offset 64 of the [`ContributionFile`]
-    #[cfg(not(debug_assertions))]
-    pub fn contribute_masp<W: Write>(challenge_reader: &[u8], mut response_writer: W, rand_source: &RandomSource) {
-        // Create an RNG as following:
-        //  - if the user provides a seed, create the rng from that seed
-        //  - if the user provides entropy, create the rng from the combination of OS randomness and user entropy
-        let mut rng = {
-            use rand::{Rng, SeedableRng};
-            use rand_chacha::ChaChaRng;
-            use std::convert::TryInto;
-
-            match rand_source {
-                RandomSource::Entropy(e) => {
-                    let mut system_rng = rand::rngs::OsRng;
-                    let mut h = Blake2b512::new();
-
-                    // Gather 1024 bytes of entropy from the system
-                    for _ in 0..1024 {
-                        let r: u8 = system_rng.gen();
-                        h.update(&[r]);
-                    }
-
-                    // Hash it all up to make a seed
-                    h.update(e.as_bytes());
-                    let digest = h.finalize();
-
-                    ChaChaRng::from_seed(digest[0..32].try_into().unwrap())
-                }
-                RandomSource::Seed(s) => ChaChaRng::from_seed(*s),
-            }
-        };
-
-        let mut masp_challenge_reader = &challenge_reader[64..];
-        //
-        // MASP Spend circuit
-        //
-        trace!("Reading MASP Spend...");
-        let mut spend_params =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("unable to read MASP Spend params");
-
-        trace!("Contributing to MASP Spend...");
-        let progress_update_interval: u32 = 0;
-
-        let spend_hash = spend_params.contribute(&mut rng, &progress_update_interval);
-        debug!("MASP Spend hash is {}", pretty_hash!(&spend_hash));
-        trace!("Contributed to MASP Spend!");
-
-        //
-        // MASP Output circuit
-        //
-        trace!("Reading MASP Output...");
-        let mut output_params =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("unable to read MASP Output params");
-
-        trace!("Contributing to MASP Output...");
-        let progress_update_interval: u32 = 0;
-
-        let output_hash = output_params.contribute(&mut rng, &progress_update_interval);
-        debug!("MASP Output hash is {}", pretty_hash!(&output_hash));
-        trace!("Contributed to MASP Output!");
-
-        //
-        // MASP Convert circuit
-        //
-        trace!("Reading MASP Convert...");
-        let mut convert_params =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("unable to read MASP Convert params");
-
-        trace!("Contributing to MASP Convert...");
-        let progress_update_interval: u32 = 0;
-        let convert_hash = convert_params.contribute(&mut rng, &progress_update_interval);
-        debug!("MASP Convert hash is {}", pretty_hash!(&convert_hash));
-        trace!("Contributed to MASP Convert!");
-
-        let mut h = Blake2b512::new();
-        h.update(&spend_hash);
-        h.update(&output_hash);
-        h.update(&convert_hash);
-        let h = h.finalize();
-        debug!("MASP Contribution hash is {}", pretty_hash!(&h));
-
-        info!("Contribution hash: 0x{:02x}", h.iter().format(""));
-
-        trace!("Writing MASP Spend parameters to file...");
-        spend_params
-            .write(&mut response_writer)
-            .expect("failed to write updated MASP Spend parameters");
+    // The masp-mpc commands are executed on the parameters of each circuit, located through the [`ContributionHeader`]
+    //
+    /// Contributes to all the circuits of the given [`CircuitSet`] in parallel. Returns the time spent contributing to
+    /// each circuit
+    pub fn contribute_circuits<W: Write>(
+        circuit_set: CircuitSet,
+        challenge_reader: &[u8],
+        response_writer: W,
+        rand_source: &RandomSource,
+    ) -> Result<Vec<(&'static str, Duration)>, Phase2Error> {
+        Self::contribute_circuits_with_progress(
+            circuit_set,
+            challenge_reader,
+            response_writer,
+            rand_source,
+            circuit_set.circuit_names().len(),
+            |_| (),
+        )
+    }
 
-        trace!("Writing MASP Output parameters to file...");
-        output_params
-            .write(&mut response_writer)
-            .expect("failed to write updated MASP Output parameters");
+    /// Same as [`Self::contribute_circuits`], contributing to at most `max_parallel_circuits` circuits at once and
+    /// calling `progress` at each step of the computation. With more than one circuit processed at once, `progress` may
+    /// be called concurrently.
+    ///
+    /// Each circuit being processed holds its deserialized parameters in memory, so the peak memory usage grows with
+    /// `max_parallel_circuits`: with 1, the circuits are contributed to one after the other. The parameters of each
+    /// batch of circuits are written to the response as soon as the batch is done.
+    pub fn contribute_circuits_with_progress<W: Write, F: Fn(ContributionProgress) + Sync>(
+        circuit_set: CircuitSet,
+        challenge_reader: &[u8],
+        mut response_writer: W,
+        rand_source: &RandomSource,
+        max_parallel_circuits: usize,
+        progress: F,
+    ) -> Result<Vec<(&'static str, Duration)>, Phase2Error> {
+        let report = |circuit_index, stage| {
+            progress(ContributionProgress {
+                circuit_set,
+                circuit_index,
+                stage,
+            })
+        };
 
-        trace!("Writing MASP Convert parameters to file...");
-        convert_params
-            .write(&mut response_writer)
-            .expect("failed to write updated MASP Convert parameters");
+        // Create the seed of the RNGs as following:
+        //  - if the user provides a seed, use that seed
+        //  - if the user provides entropy, hash the combination of OS randomness and user entropy
+        let seed: Seed = match rand_source {
+            RandomSource::Entropy(e) => {
+                use rand::Rng;
+                use std::convert::TryInto;
+
+                let mut system_rng = rand::rngs::OsRng;
+                let mut h = Blake2b512::new();
+
+                // Gather 1024 bytes of entropy from the system
+                for _ in 0..1024 {
+                    let r: u8 = system_rng.gen();
+                    h.update(&[r]);
+                }
 
-        response_writer.flush().unwrap();
-    }
+                // Hash it all up to make a seed
+                h.update(e.as_bytes());
+                let digest = h.finalize();
 
-    #[cfg(debug_assertions)]
-    pub fn contribute_test_masp<W: Write>(challenge_reader: &[u8], mut response_writer: W, rand_source: &RandomSource) {
-        // Create an RNG as following:
-        //  - if the user provides a seed, create the rng from that seed
-        //  - if the user provides entropy, create the rng from the combination of OS randomness and user entropy
-        let mut rng = {
-            use rand::{Rng, SeedableRng};
-            use rand_chacha::ChaChaRng;
-            use std::convert::TryInto;
-
-            match rand_source {
-                RandomSource::Entropy(e) => {
-                    let mut system_rng = rand::rngs::OsRng;
-                    let mut h = Blake2b512::new();
-
-                    // Gather 1024 bytes of entropy from the system
-                    for _ in 0..1024 {
-                        let r: u8 = system_rng.gen();
-                        h.update(&[r]);
-                    }
-
-                    // Hash it all up to make a seed
-                    h.update(e.as_bytes());
-                    let digest = h.finalize();
-
-                    ChaChaRng::from_seed(digest[0..32].try_into().unwrap())
-                }
-                RandomSource::Seed(s) => ChaChaRng::from_seed(*s),
+                digest[0..32].try_into().unwrap()
             }
+            RandomSource::Seed(s) => *s,
         };
 
-        let mut test_params =
-            MPCParameters::read(&challenge_reader[64..], false).expect("unable to read MASP Test params");
+        let names = circuit_set.circuit_names();
+        let header = ContributionHeader::from_file(challenge_reader)?;
+        header.check_circuits(circuit_set)?;
+
+        // Each contribution appends the same number of bytes to the parameters of every circuit, so the header of the
+        // response is written before contributing
+        let response_header = ContributionHeader::new(
+            header.ceremony_id,
+            header
+                .circuits
+                .iter()
+                .map(|circuit| CircuitEntry {
+                    name: circuit.name.clone(),
+                    length: circuit.length + CIRCUIT_CONTRIBUTION_SIZE,
+                })
+                .collect(),
+        );
+        response_header.write(&mut response_writer)?;
+
+        // The circuits are independent, so they are contributed to in parallel, by batches of `max_parallel_circuits`.
+        // Each circuit gets its own stream of the ChaCha RNG, so that the randomness doesn't depend on the scheduling of
+        // the threads
+        let contribute_circuit = |circuit_index: usize| -> Result<_, Phase2Error> {
+            let name = names[circuit_index];
+            trace!("Reading {}...", name);
+            report(circuit_index, ContributionStage::Reading);
+            let start = Instant::now();
+            let mut masp_challenge_reader = header.circuit_parameters(challenge_reader, circuit_index)?;
+            let mut circuit_params = MPCParameters::read(&mut masp_challenge_reader, false)
+                .map_err(|e| Phase2Error::MalformedParameters(name, e))?;
+            if !masp_challenge_reader.is_empty() {
+                return Err(ContributionHeaderError::LengthMismatch(name.to_owned()).into());
+            }
 
-        trace!("Contributing to Masp Test...");
-        let progress_update_interval: u32 = 0;
+            trace!("Contributing to {}...", name);
+            report(circuit_index, ContributionStage::Contributing);
+            let progress_update_interval: u32 = 0;
 
-        let test_hash = test_params.contribute(&mut rng, &progress_update_interval);
+            let mut rng = ChaChaRng::from_seed(seed);
+            rng.set_stream(circuit_index as u64);
+            let hash = circuit_params.contribute(&mut rng, &progress_update_interval);
+            let elapsed = start.elapsed();
+            debug!("{} hash is {}", name, pretty_hash!(&hash));
+            info!("Contributed to {} in {:?}", name, elapsed);
 
+            Ok((circuit_params, hash, elapsed))
+        };
+        let indices: Vec<usize> = (0..names.len()).collect();
         let mut h = Blake2b512::new();
-        h.update(&test_hash);
-        let h = h.finalize();
+        let mut timings = Vec::with_capacity(names.len());
+        for batch in indices.chunks(max_parallel_circuits.max(1)) {
+            let results = batch
+                .par_iter()
+                .map(|&circuit_index| contribute_circuit(circuit_index))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // The parameters are written in the order of the circuits, which is the order of the batches
+            for (&circuit_index, (circuit_params, hash, elapsed)) in batch.iter().zip(results) {
+                let name = names[circuit_index];
+                trace!("Writing {} parameters to file...", name);
+                report(circuit_index, ContributionStage::Writing);
+                let mut writer = CountingWriter {
+                    inner: &mut response_writer,
+                    count: 0,
+                };
+                circuit_params.write(&mut writer)?;
+                if writer.count != response_header.circuits[circuit_index].length {
+                    return Err(ContributionHeaderError::LengthMismatch(name.to_owned()).into());
+                }
+                report(circuit_index, ContributionStage::Done);
 
-        debug!("Contribution hash: 0x{:02x}", h.iter().format(""));
+                h.update(hash);
+                timings.push((name, elapsed));
+            }
+        }
+        let h = h.finalize();
+        debug!("Contribution hash is {}", pretty_hash!(&h));
 
-        trace!("Writing MASP Test parameters to file...");
+        info!("Contribution hash: 0x{:02x}", h.iter().format(""));
 
-        test_params
-            .write(&mut response_writer)
-            .expect("failed to write updated MASP Test parameters");
+        response_writer.flush()?;
 
-        response_writer.flush().unwrap();
+        Ok(timings)
     }
 }
 
@@ -320,7 +389,16 @@ impl Computation {
 mod tests {
     use crate::{
         authentication::{Dummy, Signature},
-        commands::{Computation, Initialization, Seed, SEED_LENGTH},
+        commands::{
+            CircuitSet,
+            Computation,
+            ContributionHeader,
+            ContributionStage,
+            Initialization,
+            RandomSource,
+            Seed,
+            SEED_LENGTH,
+        },
         storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object, StorageObject},
         testing::prelude::*,
     };
@@ -372,7 +450,7 @@ mod tests {
             );
 
             if !storage.exists(response_locator) {
-                let expected_filesize = Object::anoma_contribution_file_size(round_height, 1);
+                let expected_filesize = Object::anoma_contribution_file_size(TEST_ENVIRONMENT_ANOMA.circuit_set(), 1);
                 storage.initialize(response_locator.clone(), expected_filesize).unwrap();
             }
             if !storage.exists(contribution_file_signature_locator) {
@@ -417,4 +495,41 @@ mod tests {
             }
         }
     }
+    #[test]
+    fn test_contribute_circuits_with_seed() {
+        let challenge = Initialization::initial_challenge(CircuitSet::Test, 0).unwrap();
+        let seed = RandomSource::Seed([7; SEED_LENGTH]);
+
+        let contribute = || {
+            let mut response = calculate_hash(&challenge).to_vec();
+            let timings = Computation::contribute_circuits(CircuitSet::Test, &challenge, &mut response, &seed).unwrap();
+            assert_eq!(
+                timings.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+                CircuitSet::Test.circuit_names()
+            );
+            // The header written before contributing matches the parameters
+            assert_eq!(response.len() as u64, CircuitSet::Test.contribution_file_size(1));
+            ContributionHeader::from_file(&response).unwrap();
+
+            response
+        };
+
+        // The randomness of each circuit only depends on the seed, not on the scheduling of the threads
+        assert_eq!(contribute(), contribute());
+
+        // Nor on the number of circuits processed at once
+        let mut response = calculate_hash(&challenge).to_vec();
+        let stages = std::sync::Mutex::new(Vec::new());
+        Computation::contribute_circuits_with_progress(CircuitSet::Test, &challenge, &mut response, &seed, 1, |p| {
+            stages.lock().unwrap().push((p.circuit(), p.stage))
+        })
+        .unwrap();
+        assert_eq!(response, contribute());
+        assert_eq!(stages.into_inner().unwrap(), vec![
+            ("Test", ContributionStage::Reading),
+            ("Test", ContributionStage::Contributing),
+            ("Test", ContributionStage::Writing),
+            ("Test", ContributionStage::Done),
+        ]);
+    }
 }
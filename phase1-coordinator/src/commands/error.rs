@@ -0,0 +1,72 @@
+use crate::commands::ContributionHeaderError;
+
+use bellman::SynthesisError;
+use std::io;
+use thiserror::Error;
+
+/// Error returned while initializing, contributing to or verifying the parameters of the circuits.
+#[derive(Debug, Error)]
+pub enum Phase2Error {
+    #[error("The response is not built on top of the challenge")]
+    ChallengeHashMismatch,
+    #[error("A circuit set named {0} is already registered")]
+    DuplicateCircuitSet(&'static str),
+    #[error("The circuit set {0} has no circuits")]
+    EmptyCircuitSet(&'static str),
+    #[error("Invalid beacon value {0}, expected 32 hex encoded bytes")]
+    InvalidBeacon(String),
+    #[error("Invalid contribution to the {0} parameters")]
+    InvalidContribution(&'static str),
+    #[error("Malformed challenge file: {0}")]
+    MalformedChallenge(Box<Phase2Error>),
+    #[error("Malformed contribution file: {0}")]
+    MalformedHeader(#[from] ContributionHeaderError),
+    #[error("Malformed {0} parameters: {1}")]
+    MalformedParameters(&'static str, io::Error),
+    #[error("Unable to synthesize the {0} circuit: {1}")]
+    Synthesis(&'static str, SynthesisError),
+    #[error("Io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl Phase2Error {
+    /// Returns `true` if the error is caused by the content of a contribution file rather than by an internal fault,
+    /// in which case the contributor who produced the file can be held responsible.
+    pub fn is_invalid_contribution(&self) -> bool {
+        matches!(
+            self,
+            Self::ChallengeHashMismatch
+                | Self::InvalidContribution(_)
+                | Self::MalformedHeader(_)
+                | Self::MalformedParameters(..)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Phase2Error;
+    use crate::commands::ContributionHeaderError;
+
+    use bellman::SynthesisError;
+    use std::io;
+
+    #[test]
+    fn is_invalid_contribution() {
+        assert!(Phase2Error::ChallengeHashMismatch.is_invalid_contribution());
+        assert!(Phase2Error::InvalidContribution("Spend").is_invalid_contribution());
+        assert!(Phase2Error::from(ContributionHeaderError::Truncated).is_invalid_contribution());
+        assert!(
+            Phase2Error::MalformedParameters("Output", io::Error::from(io::ErrorKind::InvalidData))
+                .is_invalid_contribution()
+        );
+
+        // The challenge is produced by the coordinator itself
+        assert!(
+            !Phase2Error::MalformedChallenge(Box::new(Phase2Error::InvalidContribution("Spend")))
+                .is_invalid_contribution()
+        );
+        assert!(!Phase2Error::Synthesis("Convert", SynthesisError::Unsatisfiable).is_invalid_contribution());
+        assert!(!Phase2Error::from(io::Error::from(io::ErrorKind::WriteZero)).is_invalid_contribution());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::commands::{CircuitSet, ContributionHeader, Phase2Error, Verification};
+use setup_utils::{calculate_hash, GenericArray, U64};
+
+use rayon::prelude::*;
+use std::time::Instant;
+use tracing::{debug, info, trace};
+
+/// Groth16 parameters of a circuit, extracted from the final contribution file of the ceremony.
+#[derive(Debug, Clone)]
+pub struct CircuitParameters {
+    /// The set of circuits of the ceremony.
+    pub circuit_set: CircuitSet,
+    /// Name of the circuit.
+    pub name: &'static str,
+    /// The parameters, serialized in the format of the bellman Groth16 `Parameters`.
+    pub bytes: Vec<u8>,
+    /// The Blake2b hash of `bytes`.
+    pub hash: GenericArray<u8, U64>,
+}
+
+impl CircuitParameters {
+    /// Returns the name of the params file of the circuit, prefixed by the name of the circuit set, e.g.
+    /// `masp-spend.params`.
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.params", self.circuit_set, self.name.to_lowercase())
+    }
+}
+
+pub struct Extraction;
+
+impl Extraction {
+    ///
+    /// Extracts the Groth16 parameters of each circuit from the final contribution file of the ceremony.
+    ///
+    /// The parameters of the circuits are checked for correctness while being read, but the
+    /// contributions they contain are not verified: the file is expected to be the next challenge
+    /// of the last round, produced by the coordinator after the verification of the last contribution.
+    ///
+    pub fn run(circuit_set: CircuitSet, file: &[u8]) -> Result<Vec<CircuitParameters>, Phase2Error> {
+        let start = Instant::now();
+        let header = ContributionHeader::from_file(file)?;
+        header.check_circuits(circuit_set)?;
+        debug!("Extracting the parameters of ceremony {}", header.ceremony_id);
+
+        let parameters = circuit_set
+            .circuit_names()
+            .par_iter()
+            .enumerate()
+            .map(|(index, &name)| -> Result<_, Phase2Error> {
+                trace!("Reading {} parameters...", name);
+                let params = Verification::read_parameters(&header, file, index, name, true)?;
+
+                trace!("Writing {} Groth16 parameters...", name);
+                let mut bytes = Vec::new();
+                params.get_params().write(&mut bytes)?;
+                let hash = calculate_hash(&bytes);
+                debug!("{} parameters hash is {}", name, pretty_hash!(&hash));
+
+                Ok(CircuitParameters {
+                    circuit_set,
+                    name,
+                    bytes,
+                    hash,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        info!("Completed extraction of the parameters in {:?}", start.elapsed());
+        Ok(parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{CircuitSet, Computation, Extraction, Initialization, Phase2Error, RandomSource};
+    use setup_utils::calculate_hash;
+
+    #[test]
+    fn test_extraction_run() {
+        let challenge = Initialization::initial_challenge(CircuitSet::Test, 0).unwrap();
+        let mut response = calculate_hash(&challenge).to_vec();
+        Computation::contribute_circuits(
+            CircuitSet::Test,
+            &challenge,
+            &mut response,
+            &RandomSource::Seed([1; 32]),
+        )
+        .unwrap();
+
+        let parameters = Extraction::run(CircuitSet::Test, &response).unwrap();
+        assert_eq!(parameters.len(), 1);
+        assert_eq!(parameters[0].file_name(), "test-test.params");
+        assert_eq!(parameters[0].hash, calculate_hash(&parameters[0].bytes));
+
+        // The Groth16 parameters carry the contribution
+        let initial = Extraction::run(CircuitSet::Test, &challenge).unwrap();
+        assert_ne!(initial[0].bytes, parameters[0].bytes);
+
+        assert!(matches!(
+            Extraction::run(CircuitSet::Masp, &response),
+            Err(Phase2Error::MalformedHeader(_))
+        ));
+    }
+}
@@ -0,0 +1,330 @@
+//! Header of the contribution files.
+//!
+//! Every contribution file has the following format:
+//!
+//! | previous_contribution_file_hash (64 bytes) |
+//! | header |
+//! | parameters of each circuit |
+//!
+//! The header describes the parameters that follow it, so that a file can be validated and the parameters of a single
+//! circuit located without parsing the others:
+//!
+//! | magic (4 bytes) | version (u16) | ceremony id (u64) | number of circuits (u32) |
+//! | for each circuit: name length (u8) | name | length of the parameters (u64) |
+//!
+//! All the integers are big endian.
+
+use crate::commands::CircuitSet;
+
+use std::{
+    convert::TryFrom,
+    io::{self, Read, Write},
+    ops::Range,
+};
+use thiserror::Error;
+
+/// Magic bytes at the beginning of the header.
+pub const CONTRIBUTION_FILE_MAGIC: [u8; 4] = *b"NTSC";
+/// Version of the format of the contribution files.
+pub const CONTRIBUTION_FILE_VERSION: u16 = 1;
+
+/// Length of the hash placed at the head of the contribution files.
+const HASH_LENGTH: usize = 64;
+
+/// Error returned while reading or checking a [`ContributionHeader`].
+#[derive(Debug, Error)]
+pub enum ContributionHeaderError {
+    #[error("Invalid magic bytes {0:02x?}, this is not a contribution file")]
+    InvalidMagic([u8; 4]),
+    #[error("Unsupported contribution file version {0}, expected {}", CONTRIBUTION_FILE_VERSION)]
+    UnsupportedVersion(u16),
+    #[error("The file belongs to the ceremony {found}, expected {expected}")]
+    CeremonyMismatch { expected: u64, found: u64 },
+    #[error("The file contains the circuits {found:?}, expected {expected:?}")]
+    CircuitMismatch { expected: Vec<String>, found: Vec<String> },
+    #[error("Invalid circuit name: {0}")]
+    InvalidName(String),
+    #[error("The parameters of the circuit {0} don't match the length declared in the header")]
+    LengthMismatch(String),
+    #[error("The file is shorter than the length declared in its header")]
+    Truncated,
+    #[error("The file is longer than the length declared in its header")]
+    TrailingData,
+    #[error("The file has no circuit at index {0}")]
+    CircuitMissing(usize),
+    #[error("Io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+type Result<T> = std::result::Result<T, ContributionHeaderError>;
+
+/// Entry of a circuit in the [`ContributionHeader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitEntry {
+    /// Name of the circuit
+    pub name: String,
+    /// Length in bytes of the parameters of the circuit
+    pub length: u64,
+}
+
+/// Header placed after the hash at the head of the contribution files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContributionHeader {
+    /// Version of the format of the file
+    pub version: u16,
+    /// Identifier of the ceremony the file belongs to
+    pub ceremony_id: u64,
+    /// The circuits, in the order in which their parameters appear in the file
+    pub circuits: Vec<CircuitEntry>,
+}
+
+impl ContributionHeader {
+    /// Creates a header of the current version.
+    pub fn new(ceremony_id: u64, circuits: Vec<CircuitEntry>) -> Self {
+        Self {
+            version: CONTRIBUTION_FILE_VERSION,
+            ceremony_id,
+            circuits,
+        }
+    }
+
+    /// Returns the size of the header of a file containing the given circuits.
+    pub fn size_for(names: &[&str]) -> u64 {
+        4 + 2 + 8 + 4 + names.iter().map(|name| 1 + name.len() as u64 + 8).sum::<u64>()
+    }
+
+    /// Returns the size of the header.
+    pub fn size(&self) -> u64 {
+        Self::size_for(&self.names())
+    }
+
+    /// Returns the names of the circuits.
+    pub fn names(&self) -> Vec<&str> {
+        self.circuits.iter().map(|circuit| circuit.name.as_str()).collect()
+    }
+
+    /// Writes the header.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&CONTRIBUTION_FILE_MAGIC)?;
+        writer.write_all(&self.version.to_be_bytes())?;
+        writer.write_all(&self.ceremony_id.to_be_bytes())?;
+        writer.write_all(&(self.circuits.len() as u32).to_be_bytes())?;
+        for circuit in &self.circuits {
+            let len = u8::try_from(circuit.name.len()).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Circuit name too long: {}", circuit.name),
+                )
+            })?;
+            writer.write_all(&[len])?;
+            writer.write_all(circuit.name.as_bytes())?;
+            writer.write_all(&circuit.length.to_be_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a header, rejecting the unknown formats.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != CONTRIBUTION_FILE_MAGIC {
+            return Err(ContributionHeaderError::InvalidMagic(magic));
+        }
+
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_be_bytes(version);
+        if version != CONTRIBUTION_FILE_VERSION {
+            return Err(ContributionHeaderError::UnsupportedVersion(version));
+        }
+
+        let mut ceremony_id = [0; 8];
+        reader.read_exact(&mut ceremony_id)?;
+
+        let mut count = [0; 4];
+        reader.read_exact(&mut count)?;
+
+        let mut circuits = Vec::new();
+        for _ in 0..u32::from_be_bytes(count) {
+            let mut len = [0; 1];
+            reader.read_exact(&mut len)?;
+            let mut name = vec![0; len[0] as usize];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name)
+                .map_err(|e| ContributionHeaderError::InvalidName(String::from_utf8_lossy(e.as_bytes()).into()))?;
+
+            let mut length = [0; 8];
+            reader.read_exact(&mut length)?;
+            circuits.push(CircuitEntry {
+                name,
+                length: u64::from_be_bytes(length),
+            });
+        }
+
+        Ok(Self {
+            version,
+            ceremony_id: u64::from_be_bytes(ceremony_id),
+            circuits,
+        })
+    }
+
+    /// Reads the header of a contribution file, checking that the file has exactly the length declared in the header.
+    pub fn from_file(file: &[u8]) -> Result<Self> {
+        let header = Self::read(file.get(HASH_LENGTH..).ok_or(ContributionHeaderError::Truncated)?)?;
+
+        let end = header.circuit_range(header.circuits.len())?.start;
+        if file.len() < end {
+            return Err(ContributionHeaderError::Truncated);
+        }
+        if file.len() > end {
+            return Err(ContributionHeaderError::TrailingData);
+        }
+
+        Ok(header)
+    }
+
+    /// Returns the range of bytes of the parameters of the circuit at `index` in the contribution file. With an index
+    /// equal to the number of circuits, the range starts at the end of the parameters.
+    fn circuit_range(&self, index: usize) -> Result<Range<usize>> {
+        let start = self
+            .circuits
+            .get(..index)
+            .ok_or(ContributionHeaderError::CircuitMissing(index))?
+            .iter()
+            .try_fold(HASH_LENGTH as u64 + self.size(), |offset, circuit| {
+                offset.checked_add(circuit.length)
+            });
+        let end = self.circuits.get(index).map_or(start, |circuit| {
+            start.and_then(|start| start.checked_add(circuit.length))
+        });
+
+        match (start.map(usize::try_from), end.map(usize::try_from)) {
+            (Some(Ok(start)), Some(Ok(end))) => Ok(start..end),
+            _ => Err(ContributionHeaderError::Truncated),
+        }
+    }
+
+    /// Returns the parameters of the circuit at `index` in the contribution file.
+    pub fn circuit_parameters<'a>(&self, file: &'a [u8], index: usize) -> Result<&'a [u8]> {
+        if index >= self.circuits.len() {
+            return Err(ContributionHeaderError::CircuitMissing(index));
+        }
+
+        file.get(self.circuit_range(index)?)
+            .ok_or(ContributionHeaderError::Truncated)
+    }
+
+    /// Checks that the file contains the circuits of the given set.
+    pub fn check_circuits(&self, circuit_set: CircuitSet) -> Result<()> {
+        let expected = circuit_set.circuit_names();
+        if self.names() != expected {
+            return Err(ContributionHeaderError::CircuitMismatch {
+                expected: expected.iter().map(|name| (*name).to_owned()).collect(),
+                found: self.names().into_iter().map(str::to_owned).collect(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the file belongs to the given ceremony.
+    pub fn check_ceremony(&self, ceremony_id: u64) -> Result<()> {
+        if self.ceremony_id != ceremony_id {
+            return Err(ContributionHeaderError::CeremonyMismatch {
+                expected: ceremony_id,
+                found: self.ceremony_id,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CircuitEntry, ContributionHeader, ContributionHeaderError};
+    use crate::commands::CircuitSet;
+
+    fn test_file() -> (ContributionHeader, Vec<u8>) {
+        let header = ContributionHeader::new(7, vec![
+            CircuitEntry {
+                name: "Spend".to_string(),
+                length: 3,
+            },
+            CircuitEntry {
+                name: "Output".to_string(),
+                length: 2,
+            },
+            CircuitEntry {
+                name: "Convert".to_string(),
+                length: 1,
+            },
+        ]);
+
+        let mut file = vec![0; 64];
+        header.write(&mut file).unwrap();
+        file.extend_from_slice(&[1, 1, 1, 2, 2, 3]);
+
+        (header, file)
+    }
+
+    #[test]
+    fn read_write() {
+        let (header, file) = test_file();
+        assert_eq!(file.len() as u64, 64 + header.size() + 6);
+        assert_eq!(
+            header.size(),
+            ContributionHeader::size_for(CircuitSet::Masp.circuit_names())
+        );
+
+        let read = ContributionHeader::from_file(&file).unwrap();
+        assert_eq!(read, header);
+        assert_eq!(read.circuit_parameters(&file, 0).unwrap(), &[1, 1, 1]);
+        assert_eq!(read.circuit_parameters(&file, 1).unwrap(), &[2, 2]);
+        assert_eq!(read.circuit_parameters(&file, 2).unwrap(), &[3]);
+    }
+
+    #[test]
+    fn checks() {
+        let (header, file) = test_file();
+        assert!(header.check_circuits(CircuitSet::Masp).is_ok());
+        assert!(matches!(
+            header.check_circuits(CircuitSet::Test),
+            Err(ContributionHeaderError::CircuitMismatch { .. })
+        ));
+        assert!(header.check_ceremony(7).is_ok());
+        assert!(matches!(
+            header.check_ceremony(8),
+            Err(ContributionHeaderError::CeremonyMismatch { expected: 8, found: 7 })
+        ));
+
+        // Truncated parameters
+        assert!(matches!(
+            ContributionHeader::from_file(&file[..file.len() - 1]),
+            Err(ContributionHeaderError::Truncated)
+        ));
+
+        // Data after the parameters
+        let mut extended = file.clone();
+        extended.push(0);
+        assert!(matches!(
+            ContributionHeader::from_file(&extended),
+            Err(ContributionHeaderError::TrailingData)
+        ));
+
+        // Unknown formats
+        let mut wrong_magic = file.clone();
+        wrong_magic[64] ^= 1;
+        assert!(matches!(
+            ContributionHeader::from_file(&wrong_magic),
+            Err(ContributionHeaderError::InvalidMagic(_))
+        ));
+        let mut wrong_version = file;
+        wrong_version[69] += 1;
+        assert!(matches!(
+            ContributionHeader::from_file(&wrong_version),
+            Err(ContributionHeaderError::UnsupportedVersion(2))
+        ));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    commands::{CircuitEntry, CircuitSet, ContributionHeader, Phase2Error},
     environment::Environment,
     storage::{ContributionLocator, Disk, Locator, Object, StorageObject},
     CoordinatorError,
@@ -10,26 +11,7 @@ use setup_utils::{blank_hash, calculate_hash};
 use std::{io::Write, time::Instant};
 use tracing::{debug, error, info, trace};
 
-use masp_phase2::MPCParameters;
-
-use bellman::{Circuit, ConstraintSystem, SynthesisError};
-use bls12_381::Scalar;
-
-struct TestCircuit {
-    x: Option<Scalar>,
-}
-impl Circuit<Scalar> for TestCircuit {
-    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
-        let x_value = self.x;
-        let x = cs.alloc(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?;
-
-        cs.enforce(|| "x = x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x);
-
-        Ok(())
-    }
-}
-
-pub(crate) struct Initialization;
+pub struct Initialization;
 
 impl Initialization {
     ///
@@ -48,7 +30,9 @@ impl Initialization {
         let start = Instant::now();
 
         // Determine the expected challenge size.
-        let expected_challenge_size = Object::anoma_contribution_file_size(0, 0);
+        let circuit_set = environment.circuit_set();
+        let ceremony_id = environment.ceremony_id();
+        let expected_challenge_size = Object::anoma_contribution_file_size(circuit_set, 0);
         trace!("Expected challenge file size is {}", expected_challenge_size);
 
         // Initialize and fetch a writer for the contribution locator so the output is saved.
@@ -59,9 +43,21 @@ impl Initialization {
         let settings = environment.parameters();
 
         if let Err(error) = match settings.curve() {
-            CurveKind::Bls12_381 => Self::initialization(storage.writer(&contribution_locator)?.as_mut()),
-            CurveKind::Bls12_377 => Self::initialization(storage.writer(&contribution_locator)?.as_mut()),
-            CurveKind::BW6 => Self::initialization(storage.writer(&contribution_locator)?.as_mut()),
+            CurveKind::Bls12_381 => Self::initialization(
+                circuit_set,
+                ceremony_id,
+                storage.writer(&contribution_locator)?.as_mut(),
+            ),
+            CurveKind::Bls12_377 => Self::initialization(
+                circuit_set,
+                ceremony_id,
+                storage.writer(&contribution_locator)?.as_mut(),
+            ),
+            CurveKind::BW6 => Self::initialization(
+                circuit_set,
+                ceremony_id,
+                storage.writer(&contribution_locator)?.as_mut(),
+            ),
         } {
             error!("Initialization failed with {}", error);
             return Err(CoordinatorError::InitializationFailed.into());
@@ -84,12 +80,24 @@ impl Initialization {
         Ok(hash)
     }
 
+    /// Generates the initial challenge of the ceremony in memory, without accessing the storage.
+    pub fn initial_challenge(circuit_set: CircuitSet, ceremony_id: u64) -> Result<Vec<u8>, CoordinatorError> {
+        let mut challenge = vec![0; Object::anoma_contribution_file_size(circuit_set, 0) as usize];
+        Self::initialization(circuit_set, ceremony_id, &mut challenge)?;
+
+        Ok(challenge)
+    }
+
     /// Runs Phase 2 initialization on the given parameters.
     #[inline]
-    fn initialization(mut writer: &mut [u8]) -> Result<(), CoordinatorError> {
+    fn initialization(
+        circuit_set: CircuitSet,
+        ceremony_id: u64,
+        mut writer: &mut [u8],
+    ) -> Result<(), CoordinatorError> {
         info!("Initializing Phase 2 Initialization");
-        // The initialization contribution file contains [blank_hash, init.params]
-        // The circuit parameters are appended to the blank_hash
+        // The initialization contribution file contains [blank_hash, header, init.params]
+        // The header and the circuit parameters are appended to the blank_hash
         let hash = blank_hash();
 
         writer.write_all(&hash.as_slice())?;
@@ -99,14 +107,29 @@ impl Initialization {
 
         info!("Starting Phase 2 initialization operation");
 
-        //
-        // NOTE: Add your MPC Parameters initialization function below
-        //
-        #[cfg(debug_assertions)]
-        Self::initialize_test_masp(&mut writer);
+        let mut entries = Vec::new();
+        let mut parameters = Vec::new();
+        for circuit in circuit_set.circuits() {
+            trace!("Creating initial parameters for {}...", circuit.name());
+            let params = circuit
+                .initial_parameters()
+                .map_err(|e| Phase2Error::Synthesis(circuit.name(), e))?;
+
+            let mut bytes = Vec::new();
+            params.write(&mut bytes)?;
+            entries.push(CircuitEntry {
+                name: circuit.name().to_owned(),
+                length: bytes.len() as u64,
+            });
+            parameters.push(bytes);
+        }
 
-        #[cfg(not(debug_assertions))]
-        Self::initialize_masp(&mut writer);
+        trace!("Writing initial parameters to file...");
+        ContributionHeader::new(ceremony_id, entries).write(&mut writer)?;
+        for bytes in &parameters {
+            writer.write_all(bytes)?;
+        }
+        writer.flush()?;
 
         trace!("Completed Phase 2 initialization operation");
 
@@ -132,95 +155,6 @@ impl Initialization {
 
         Ok(contribution_hash_1.to_vec())
     }
-
-    #[inline]
-    #[cfg(not(debug_assertions))]
-    fn initialize_masp(mut writer: &mut [u8]) {
-        //
-        // MASP spend circuit
-        //
-        trace!("Creating initial parameters for MASP Spend...");
-        let spend_params = MPCParameters::new(
-            masp_proofs::circuit::sapling::Spend {
-                value_commitment: None,
-                proof_generation_key: None,
-                payment_address: None,
-                commitment_randomness: None,
-                ar: None,
-                auth_path: vec![None; 32], // Tree depth is 32 for sapling
-                anchor: None,
-            },
-            //should_filter_points_at_infinity,
-            //radix_directory,
-        )
-        .unwrap();
-        trace!("Writing initial MASP Spend parameters to file...",);
-
-        spend_params
-            .write(&mut writer)
-            .expect("unable to write MASP Spend params");
-        //
-        // MASP output circuit
-        //
-        trace!("Creating initial parameters for MASP Output...");
-        let output_params = MPCParameters::new(
-            masp_proofs::circuit::sapling::Output {
-                value_commitment: None,
-                payment_address: None,
-                commitment_randomness: None,
-                esk: None,
-                asset_identifier: vec![None; 256],
-            },
-            //should_filter_points_at_infinity,
-            //radix_directory,
-        )
-        .unwrap();
-
-        trace!("Writing initial MASP Output parameters to file...",);
-
-        output_params
-            .write(&mut writer)
-            .expect("unable to write MASP Output params");
-
-        //
-        // MASP Convert circuit
-        //
-        trace!("Creating initial parameters for MASP Convert...");
-        let convert_params = MPCParameters::new(
-            masp_proofs::circuit::convert::Convert {
-                value_commitment: None,
-                auth_path: vec![None; 32], // Tree depth is 32 for sapling
-                anchor: None,
-            },
-            //should_filter_points_at_infinity,
-            //radix_directory,
-        )
-        .unwrap();
-
-        trace!("Writing initial MASP Convert parameters to file...",);
-
-        convert_params
-            .write(&mut writer)
-            .expect("unable to write MASP Convert params");
-
-        writer.flush().unwrap();
-    }
-
-    #[inline]
-    #[cfg(debug_assertions)]
-    fn initialize_test_masp(mut writer: &mut [u8]) {
-        // MASP Test circuit
-        trace!("Creating initial parameters for MASP Test Circuit...");
-        let instance = TestCircuit { x: Some(Scalar::one()) };
-        let test_params = MPCParameters::new(instance).unwrap();
-        trace!("Writing initial MASP Test Circuit parameters to file...",);
-
-        test_params
-            .write(&mut writer)
-            .expect("unable to write MASP Test Circuit params");
-
-        writer.flush().unwrap();
-    }
 }
 
 #[cfg(test)]
@@ -1,18 +1,37 @@
 pub(crate) mod aggregation;
 pub(crate) use aggregation::*;
 
+#[cfg(any(test, feature = "operator"))]
+pub mod beacon;
+#[cfg(any(test, feature = "operator"))]
+pub use beacon::*;
+
+pub mod circuits;
+pub use circuits::*;
+
+pub mod error;
+pub use error::*;
+
+#[cfg(any(test, feature = "operator"))]
+pub mod extraction;
+#[cfg(any(test, feature = "operator"))]
+pub use extraction::*;
+
+pub mod header;
+pub use header::*;
+
 #[cfg(any(test, feature = "operator"))]
 pub mod computation;
 #[cfg(any(test, feature = "operator"))]
 pub use computation::*;
 
-pub(crate) mod initialization;
-pub(crate) use initialization::*;
+pub mod initialization;
+pub use initialization::*;
 
 #[cfg(any(test, feature = "operator"))]
-pub(crate) mod verification;
+pub mod verification;
 #[cfg(any(test, feature = "operator"))]
-pub(crate) use verification::*;
+pub use verification::*;
 
 #[cfg(any(test, feature = "operator"))]
 use crate::{
@@ -1,6 +1,6 @@
 use crate::{
     authentication::Signature,
-    commands::SigningKey,
+    commands::{CircuitSet, ContributionHeader, ContributionHeaderError, Phase2Error, SigningKey},
     environment::Environment,
     storage::{
         ContributionLocator,
@@ -22,8 +22,32 @@ use tracing::{debug, error, info, trace};
 use blake2::{Blake2b512, Digest};
 use itertools::Itertools;
 use masp_phase2::{verify_contribution, MPCParameters};
+use rayon::prelude::*;
+
+/// Outcome of the verification of a contribution file against its challenge.
+#[derive(Debug, Clone)]
+pub struct VerifiedContribution {
+    /// The hash of the whole response file.
+    pub response_hash: GenericArray<u8, U64>,
+    /// The header of the response file.
+    pub header: ContributionHeader,
+    /// The name and MPC contribution hash of each circuit, in the order they appear in the file.
+    pub circuit_hashes: Vec<(&'static str, [u8; 64])>,
+}
+
+impl VerifiedContribution {
+    /// Returns the Blake2b hash of all the circuit hashes, as logged by the masp-mpc commands.
+    pub fn contribution_hash(&self) -> Vec<u8> {
+        let mut h = Blake2b512::new();
+        for (_, hash) in &self.circuit_hashes {
+            h.update(hash);
+        }
 
-pub(crate) struct Verification;
+        h.finalize().to_vec()
+    }
+}
+
+pub struct Verification;
 
 impl Verification {
     ///
@@ -115,8 +139,6 @@ impl Verification {
             challenge_locator.clone(),
             response_locator.clone(),
             next_challenge_locator.clone(),
-            round_height,
-            current_contribution_id,
         ) {
             error!("Verification failed with {}", error);
             return Err(error);
@@ -166,8 +188,6 @@ impl Verification {
         challenge_locator: Locator,
         response_locator: Locator,
         next_challenge_locator: Locator,
-        round_height: u64,
-        contribution_id: u64,
     ) -> Result<(), CoordinatorError> {
         // Check that the previous and current locators exist in storage.
         if !storage.exists(&challenge_locator) || !storage.exists(&response_locator) {
@@ -176,25 +196,41 @@ impl Verification {
 
         // Execute ceremony verification on chunk.
         let settings = environment.parameters();
+        let circuit_set = environment.circuit_set();
         let result = match settings.curve() {
             CurveKind::Bls12_381 => Self::transform_pok_and_correctness(
+                circuit_set,
                 storage.reader(&challenge_locator)?.as_ref(),
                 storage.reader(&response_locator)?.as_ref(),
             ),
             CurveKind::Bls12_377 => Self::transform_pok_and_correctness(
+                circuit_set,
                 storage.reader(&challenge_locator)?.as_ref(),
                 storage.reader(&response_locator)?.as_ref(),
             ),
             CurveKind::BW6 => Self::transform_pok_and_correctness(
+                circuit_set,
                 storage.reader(&challenge_locator)?.as_ref(),
                 storage.reader(&response_locator)?.as_ref(),
             ),
         };
+        // Only the errors caused by the content of the response are reported as a failed verification, the internal
+        // faults are propagated so that the contributor is not held responsible for them.
         let response_hash = match result {
-            Ok(response_hash) => response_hash,
-            Err(error) => {
+            Ok(verified) => match verified.header.check_ceremony(environment.ceremony_id()) {
+                Ok(()) => verified.response_hash,
+                Err(error) => {
+                    error!("Verification failed with {}", error);
+                    return Err(CoordinatorError::VerificationFailed);
+                }
+            },
+            Err(error) if error.is_invalid_contribution() => {
                 error!("Verification failed with {}", error);
-                return Err(CoordinatorError::VerificationFailed.into());
+                return Err(CoordinatorError::VerificationFailed);
+            }
+            Err(error) => {
+                error!("Verification aborted with {}", error);
+                return Err(error.into());
             }
         };
 
@@ -217,10 +253,8 @@ impl Verification {
 
             // Initialize the next contribution locator, if it does not exist.
             if !storage.exists(&next_challenge_locator) {
-                storage.initialize(
-                    next_challenge_locator.clone(),
-                    Object::anoma_contribution_file_size(round_height, contribution_id),
-                )?;
+                let response_size = storage.size(&response_locator)?;
+                storage.initialize(next_challenge_locator.clone(), response_size)?;
             }
 
             match settings.curve() {
@@ -268,22 +302,38 @@ impl Verification {
         Ok(())
     }
 
+    ///
+    /// Verifies a response file against its challenge file.
+    ///
+    /// Checks that the response embeds the hash of the challenge in its first 64 bytes
+    /// and that every MPC contribution contained in the response is a valid transformation
+    /// of the corresponding parameters of the challenge, for the circuits of the given set.
+    ///
     #[inline]
-    fn transform_pok_and_correctness(
+    pub fn transform_pok_and_correctness(
+        circuit_set: CircuitSet,
         challenge_reader: &[u8],
         response_reader: &[u8],
-    ) -> Result<GenericArray<u8, U64>, CoordinatorError> {
+    ) -> Result<VerifiedContribution, Phase2Error> {
         debug!("Verifying challenges");
 
+        // Both files must at least contain the hash of their previous file.
+        if challenge_reader.len() < 64 {
+            return Err(Phase2Error::MalformedChallenge(Box::new(
+                ContributionHeaderError::Truncated.into(),
+            )));
+        }
+        if response_reader.len() < 64 {
+            return Err(ContributionHeaderError::Truncated.into());
+        }
+
         // Check that the challenge hashes match.
         let _challenge_hash = {
             // Compute the challenge hash using the challenge file.
             let challenge_hash = calculate_hash(challenge_reader.as_ref());
 
             // Fetch the challenge hash from the response file.
-            let saved_challenge_hash = &response_reader
-                .get(0..64)
-                .ok_or(CoordinatorError::StorageReaderFailed)?[..];
+            let saved_challenge_hash = &response_reader[0..64];
 
             // Check that the challenge hashes match.
             debug!("The challenge hash is {}", pretty_hash!(&challenge_hash));
@@ -292,7 +342,7 @@ impl Verification {
                 true => challenge_hash,
                 false => {
                     error!("Challenge hash does not match saved challenge hash.");
-                    return Err(CoordinatorError::ContributionHashMismatch);
+                    return Err(Phase2Error::ChallengeHashMismatch);
                 }
             }
         };
@@ -300,8 +350,14 @@ impl Verification {
         // Compute the response hash using the response file.
         let response_hash = calculate_hash(response_reader);
         debug!("Response Reader hash is {}", pretty_hash!(&response_hash));
-        debug!("Challenge Reader is {}", pretty_hash!(&challenge_reader[0..256]));
-        debug!("Response Reader is {}", pretty_hash!(&response_reader[0..256]));
+        debug!(
+            "Challenge Reader is {}",
+            pretty_hash!(&challenge_reader.get(0..256).unwrap_or(challenge_reader))
+        );
+        debug!(
+            "Response Reader is {}",
+            pretty_hash!(&response_reader.get(0..256).unwrap_or(response_reader))
+        );
 
         // Fetch the public key of the contributor.
         // let public_key = PublicKey::read(response_reader, compressed_response, &parameters)?;
@@ -309,97 +365,94 @@ impl Verification {
 
         trace!("Starting verification");
 
-        #[cfg(debug_assertions)]
-        Self::verify_test_masp(&challenge_reader, &response_reader);
-
-        #[cfg(not(debug_assertions))]
-        Self::verify_masp(&challenge_reader, &response_reader);
+        let (header, circuit_hashes) = Self::verify_circuits(circuit_set, &challenge_reader, &response_reader)?;
 
         trace!("Completed verification");
 
-        Ok(response_hash)
+        Ok(VerifiedContribution {
+            response_hash,
+            header,
+            circuit_hashes,
+        })
     }
 
+    /// Verifies the parameters of each circuit, returning the header of the response along with the hashes.
     #[inline]
-    #[cfg(not(debug_assertions))]
-    fn verify_masp(challenge_reader: &[u8], response_reader: &[u8]) {
-        trace!("Reading MASP Spend old parameters...");
-        let mut masp_challenge_reader = &challenge_reader[64..];
-        let mut masp_response_reader = &response_reader[64..];
-
-        let masp_spend =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("couldn't deserialize MASP Spend params");
-
-        trace!("Reading MASP Output old parameters...");
-        let masp_output =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("couldn't deserialize MASP Output params");
-
-        trace!("Reading MASP Convert old parameters...");
-        let masp_convert =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("couldn't deserialize MASP Convert params");
-
-        trace!("Reading MASP Spend new parameters...");
-        let new_masp_spend =
-            MPCParameters::read(&mut masp_response_reader, true).expect("couldn't deserialize MASP Spend new_params");
-
-        trace!("Reading MASP Output new parameters...");
-        let new_masp_output =
-            MPCParameters::read(&mut masp_response_reader, true).expect("couldn't deserialize MASP Output new_params");
-
-        trace!("Reading MASP Convert new parameters...");
-        let new_masp_convert =
-            MPCParameters::read(&mut masp_response_reader, true).expect("couldn't deserialize MASP Convert new_params");
-
-        trace!("Verifying MASP Spend...");
-        let spend_hash = match verify_contribution(&masp_spend, &new_masp_spend) {
-            Ok(hash) => hash,
-            Err(_) => panic!("invalid MASP Spend transformation!"),
-        };
-        debug!("MASP Spend hash is {}", pretty_hash!(&spend_hash));
-
-        trace!("Verifying MASP Output...");
-        let output_hash = match verify_contribution(&masp_output, &new_masp_output) {
-            Ok(hash) => hash,
-            Err(_) => panic!("invalid MASP Output transformation!"),
-        };
-        debug!("MASP Output hash is {}", pretty_hash!(&output_hash));
-
-        trace!("Verifying MASP Convert...");
-        let convert_hash = match verify_contribution(&masp_convert, &new_masp_convert) {
-            Ok(hash) => hash,
-            Err(_) => panic!("invalid MASP Convert transformation!"),
-        };
-        debug!("MASP Convert hash is {}", pretty_hash!(&convert_hash));
+    fn verify_circuits(
+        circuit_set: CircuitSet,
+        challenge_reader: &[u8],
+        response_reader: &[u8],
+    ) -> Result<(ContributionHeader, Vec<(&'static str, [u8; 64])>), Phase2Error> {
+        let names = circuit_set.circuit_names();
+
+        trace!("Reading the headers...");
+        let challenge_header = ContributionHeader::from_file(challenge_reader)
+            .and_then(|header| header.check_circuits(circuit_set).map(|()| header))
+            .map_err(|e| Phase2Error::MalformedChallenge(Box::new(e.into())))?;
+        let response_header = ContributionHeader::from_file(response_reader)?;
+        response_header.check_circuits(circuit_set)?;
+        response_header.check_ceremony(challenge_header.ceremony_id)?;
+
+        // The parameters of the circuits are independent, so they are verified in parallel. The errors are then
+        // reported in the order of the circuits, so that the outcome doesn't depend on the scheduling of the threads.
+        let results: Vec<_> = names
+            .par_iter()
+            .enumerate()
+            .map(|(index, name)| -> Result<_, Phase2Error> {
+                let start = Instant::now();
+                trace!("Reading {} old parameters...", name);
+                let old = Self::read_parameters(&challenge_header, challenge_reader, index, *name, false)
+                    .map_err(|e| Phase2Error::MalformedChallenge(Box::new(e)))?;
+
+                trace!("Reading {} new parameters...", name);
+                let new = Self::read_parameters(&response_header, response_reader, index, *name, true)?;
+
+                trace!("Verifying {}...", name);
+                let hash = verify_contribution(&old, &new).map_err(|_| {
+                    error!("invalid {} transformation!", name);
+                    Phase2Error::InvalidContribution(*name)
+                })?;
+                debug!("{} hash is {}", name, pretty_hash!(&hash));
+                info!("Verified {} in {:?}", name, start.elapsed());
+
+                Ok(hash)
+            })
+            .collect();
+
+        let mut circuit_hashes = Vec::with_capacity(names.len());
+        for (name, result) in names.iter().copied().zip(results) {
+            circuit_hashes.push((name, result?));
+        }
 
         let mut h = Blake2b512::new();
-        h.update(&spend_hash);
-        h.update(&output_hash);
-        h.update(&convert_hash);
+        for (_, hash) in &circuit_hashes {
+            h.update(hash);
+        }
         let h = h.finalize();
 
         info!("Verification hash: 0x{:02x}", h.iter().format(""));
-        debug!("MASP Contribution hash is {}", pretty_hash!(&h));
+        debug!("Contribution hash is {}", pretty_hash!(&h));
+
+        Ok((response_header, circuit_hashes))
     }
 
+    /// Reads the parameters of the circuit at `index`, checking that they match the length declared in the header.
     #[inline]
-    #[cfg(debug_assertions)]
-    fn verify_test_masp(challenge_reader: &[u8], response_reader: &[u8]) {
-        let masp_test =
-            MPCParameters::read(&challenge_reader[64..], false).expect("couldn't deserialize MASP Test params");
-
-        let new_masp_test =
-            MPCParameters::read(&response_reader[64..], true).expect("couldn't deserialize MASP Spend new_params");
-
-        let test_hash = match verify_contribution(&masp_test, &new_masp_test) {
-            Ok(hash) => hash,
-            Err(_) => panic!("invalid MASP Spend transformation!"),
-        };
-
-        let mut h = Blake2b512::new();
-        h.update(&test_hash);
-        let h = h.finalize();
+    pub(crate) fn read_parameters(
+        header: &ContributionHeader,
+        file: &[u8],
+        index: usize,
+        name: &'static str,
+        check_for_correctness: bool,
+    ) -> Result<MPCParameters, Phase2Error> {
+        let mut reader = header.circuit_parameters(file, index)?;
+        let params = MPCParameters::read(&mut reader, check_for_correctness)
+            .map_err(|e| Phase2Error::MalformedParameters(name, e))?;
+        if !reader.is_empty() {
+            return Err(ContributionHeaderError::LengthMismatch(name.to_owned()).into());
+        }
 
-        debug!("Verification hash: 0x{:02x}", h.iter().format(""));
+        Ok(params)
     }
 
     #[inline]
@@ -422,11 +475,22 @@ mod tests {
 
     use crate::{
         authentication::Dummy,
-        commands::{Computation, Seed, Verification, SEED_LENGTH},
-        storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object},
+        commands::{
+            CircuitSet,
+            Computation,
+            ContributionHeaderError,
+            Initialization,
+            Phase2Error,
+            RandomSource,
+            Seed,
+            Verification,
+            SEED_LENGTH,
+        },
+        storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object, StorageObject},
         testing::prelude::*,
         Coordinator,
     };
+    use setup_utils::calculate_hash;
 
     use once_cell::sync::Lazy;
     use rand::RngCore;
@@ -483,7 +547,7 @@ mod tests {
 
             if !storage.exists(response_locator) {
                 // let expected_filesize = Object::contribution_file_size(&TEST_ENVIRONMENT_ANOMA, chunk_id, false);
-                let expected_filesize = Object::anoma_contribution_file_size(round_height, 1);
+                let expected_filesize = Object::anoma_contribution_file_size(TEST_ENVIRONMENT_ANOMA.circuit_set(), 1);
                 storage.initialize(response_locator.clone(), expected_filesize).unwrap();
             }
             if !storage.exists(contribution_file_signature_locator) {
@@ -531,4 +595,88 @@ mod tests {
             assert!(storage.exists(&next));
         }
     }
+
+    #[test]
+    #[serial]
+    fn test_transform_pok_and_correctness() {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+
+        let mut storage = test_storage(&TEST_ENVIRONMENT_ANOMA);
+        Initialization::run(&TEST_ENVIRONMENT_ANOMA, &mut storage, 0, 0).unwrap();
+        let challenge_locator = Locator::ContributionFile(ContributionLocator::new(0, 0, 0, true));
+        let challenge = storage.reader(&challenge_locator).unwrap().to_vec();
+
+        // Build a valid response
+        let mut response = calculate_hash(&challenge).to_vec();
+        let mut seed: Seed = [0; SEED_LENGTH];
+        rand::thread_rng().fill_bytes(&mut seed[..]);
+        Computation::contribute_circuits(CircuitSet::Test, &challenge, &mut response, &RandomSource::Seed(seed))
+            .unwrap();
+
+        let verified = Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &response).unwrap();
+        assert_eq!(verified.response_hash, calculate_hash(&response));
+        assert_eq!(verified.circuit_hashes.len(), 1);
+        assert_eq!(verified.header.ceremony_id, TEST_ENVIRONMENT_ANOMA.ceremony_id());
+
+        // Response verified against another circuit set, which is a fault of the coordinator
+        let error = Verification::transform_pok_and_correctness(CircuitSet::Masp, &challenge, &response).unwrap_err();
+        assert!(matches!(&error, Phase2Error::MalformedChallenge(e) if matches!(
+            e.as_ref(),
+            Phase2Error::MalformedHeader(ContributionHeaderError::CircuitMismatch { .. })
+        )));
+        assert!(!error.is_invalid_contribution());
+
+        // Response not built on top of the challenge
+        let mut wrong_hash_response = response.clone();
+        wrong_hash_response[0] ^= 1;
+        assert!(matches!(
+            Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &wrong_hash_response),
+            Err(Phase2Error::ChallengeHashMismatch)
+        ));
+
+        // Response which is the challenge itself carries no contribution
+        let mut no_contribution_response = calculate_hash(&challenge).to_vec();
+        no_contribution_response.extend_from_slice(&challenge[64..]);
+        assert!(matches!(
+            Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &no_contribution_response),
+            Err(e) if e.is_invalid_contribution()
+        ));
+
+        // Response with corrupted parameters
+        let mut corrupted_response = response.clone();
+        let last = corrupted_response.len() - 1;
+        corrupted_response[last] ^= 1;
+        assert!(matches!(
+            Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &corrupted_response),
+            Err(e) if e.is_invalid_contribution()
+        ));
+
+        // Response declaring another ceremony
+        let mut wrong_ceremony_response = response.clone();
+        wrong_ceremony_response[64 + 4 + 2 + 7] ^= 1;
+        assert!(matches!(
+            Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &wrong_ceremony_response),
+            Err(Phase2Error::MalformedHeader(
+                ContributionHeaderError::CeremonyMismatch { .. }
+            ))
+        ));
+
+        // Truncated files
+        assert!(matches!(
+            Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &response[..32]),
+            Err(Phase2Error::MalformedHeader(ContributionHeaderError::Truncated))
+        ));
+        assert!(matches!(
+            Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &response[..response.len() - 1]),
+            Err(Phase2Error::MalformedHeader(ContributionHeaderError::Truncated))
+        ));
+
+        // Data appended to the response
+        let mut extended_response = response.clone();
+        extended_response.extend_from_slice(&[0; 32]);
+        assert!(matches!(
+            Verification::transform_pok_and_correctness(CircuitSet::Test, &challenge, &extended_response),
+            Err(Phase2Error::MalformedHeader(ContributionHeaderError::TrailingData))
+        ));
+    }
 }
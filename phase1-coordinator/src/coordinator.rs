@@ -4,7 +4,7 @@
 
 use crate::{
     authentication::Signature,
-    commands::{Aggregation, Initialization},
+    commands::{Aggregation, Initialization, Phase2Error},
     coordinator_state::{
         CeremonyStorageAction,
         CoordinatorState,
@@ -19,6 +19,7 @@ use crate::{
         task::TaskInitializationError,
         ContributionFileSignature,
         ContributionInfo,
+        ContributionReceipt,
         LockedLocators,
         Round,
         Task,
@@ -40,6 +41,8 @@ use crate::{
 use setup_utils::calculate_hash;
 
 use std::{
+    collections::HashSet,
+    convert::TryFrom,
     fmt,
     net::IpAddr,
     sync::{Arc, RwLock},
@@ -168,6 +171,7 @@ pub enum CoordinatorError {
     ParticipantWasDropped,
     PendingTasksMustContainResponseTask { response_task: Task },
     Phase1Setup(setup_utils::Error),
+    Phase2(Phase2Error),
     QueueIsEmpty,
     QueueWaitTimeIncomplete,
     ResponseHashSizeInvalid,
@@ -253,6 +257,12 @@ impl From<setup_utils::Error> for CoordinatorError {
     }
 }
 
+impl From<Phase2Error> for CoordinatorError {
+    fn from(error: Phase2Error) -> Self {
+        CoordinatorError::Phase2(error)
+    }
+}
+
 impl From<std::array::TryFromSliceError> for CoordinatorError {
     fn from(error: std::array::TryFromSliceError) -> Self {
         CoordinatorError::TryFromSliceError(error)
@@ -408,6 +418,14 @@ impl Coordinator {
     pub fn set_aggregation_callback(&mut self, callback: Arc<dyn Fn(Vec<Participant>) -> () + Send + Sync>) {
         self.aggregation_callback = callback;
     }
+
+    ///
+    /// Set the shared addresses (e.g. proxies or Tor exit nodes) which are
+    /// exempted from the duplicate ip check.
+    ///
+    pub fn set_ip_ban_exemptions(&mut self, ip_ban_exemptions: HashSet<IpAddr>) {
+        self.state.set_ip_ban_exemptions(ip_ban_exemptions);
+    }
 }
 
 impl Coordinator {
@@ -630,6 +648,19 @@ impl Coordinator {
         self.state.current_round_metrics()
     }
 
+    ///
+    /// Returns the time left until a participant may time out, the next round
+    /// may start or the ceremony ends, i.e. until the next call to
+    /// `Coordinator::update` which is not triggered by a participant.
+    ///
+    #[inline]
+    pub fn time_to_next_scheduled_update(&self) -> Option<std::time::Duration> {
+        let now = self.time.now_utc();
+        self.state
+            .next_scheduled_update(self.time.as_ref())
+            .map(|deadline| std::time::Duration::try_from(deadline - now).unwrap_or_default())
+    }
+
     ///
     /// Adds the given participant to the queue if they are permitted to participate.
     ///
@@ -701,6 +732,24 @@ impl Coordinator {
         Ok(())
     }
 
+    ///
+    /// Drops the contribution of the given task after a failed verification, together with
+    /// the contributions built on top of it, and bans its author from the ceremony. The
+    /// verified contributions preceding it are kept.
+    ///
+    pub fn drop_invalid_contribution(&mut self, task: &Task) -> Result<(), CoordinatorError> {
+        // Drop the contribution and ban its author.
+        let drop = self.state.drop_invalid_contribution(task, self.time.as_ref())?;
+
+        // Update the round on disk to reflect the coordinator state change.
+        self.drop_participant_from_storage(&drop)?;
+
+        // Save the coordinator state in storage.
+        self.save_state()?;
+
+        Ok(())
+    }
+
     ///
     /// Unbans the given participant from joining the queue.
     ///
@@ -778,6 +827,38 @@ impl Coordinator {
         self.state.is_current_contributor(participant)
     }
 
+    ///
+    /// Returns `true` if the given participant is a contributor in the
+    /// current round who can't lock its chunk yet, because the contribution
+    /// preceding its own has not been uploaded or verified.
+    ///
+    #[inline]
+    pub fn is_waiting_previous_contribution(&self, participant: &Participant) -> bool {
+        let participant_info = match self.state.current_participant_info(participant) {
+            Some(participant_info) if participant_info.locked_chunks().is_empty() => participant_info,
+            _ => return false,
+        };
+        let next_task = match participant_info.assigned_tasks().front() {
+            Some(task) => task,
+            None => return false,
+        };
+        let round = match Self::load_current_round(&self.storage) {
+            Ok(round) => round,
+            _ => return false,
+        };
+        let chunk = match round.chunk(next_task.chunk_id()) {
+            Ok(chunk) => chunk,
+            _ => return false,
+        };
+
+        match chunk.current_contribution() {
+            Ok(contribution) => {
+                chunk.current_contribution_id() + 1 < next_task.contribution_id() || !contribution.is_verified()
+            }
+            _ => false,
+        }
+    }
+
     ///
     /// Returns `true` if the given participant has finished contributing
     ///
@@ -977,6 +1058,17 @@ impl Coordinator {
         }
     }
 
+    ///
+    /// Returns the task the given contributor is computing on the given chunk,
+    /// whose contribution ID is the one of the response being produced.
+    ///
+    pub fn pending_task(&self, participant: &Participant, chunk_id: u64) -> Result<Task, CoordinatorError> {
+        self.state
+            .lookup_pending_task(participant, chunk_id)?
+            .cloned()
+            .ok_or(CoordinatorError::ParticipantShouldHavePendingTasks)
+    }
+
     /// Returns previous contribution, current contribution and next contribution paths
     pub fn get_chunk_locators_for_verifier(
         &self,
@@ -1002,7 +1094,7 @@ impl Coordinator {
             return Err(CoordinatorError::ExpectedVerifier);
         }
         let round = Self::load_current_round(&self.storage)?;
-        round.initialize_verifier_response_files(&self.environment, &mut self.storage, participant, chunk_id, locators)
+        round.initialize_verifier_response_files(&mut self.storage, participant, chunk_id, locators)
     }
 
     ///
@@ -1678,14 +1770,53 @@ impl Coordinator {
         )
     }
 
+    /// Records the contribution metadata of the given contributor. It is written to storage,
+    /// together with the summary, only once the contribution is accepted.
+    pub(crate) fn set_pending_contribution_info(
+        &mut self,
+        participant: &Participant,
+        contribution_info: ContributionInfo,
+    ) -> Result<(), CoordinatorError> {
+        self.state
+            .set_pending_contribution_info(participant.clone(), contribution_info);
+        self.save_state()
+    }
+
+    /// Writes the recorded contribution metadata of the given contributor to storage and appends
+    /// it to the summary, once its contribution with the given response hash has been accepted.
+    /// Does nothing if the contributor hasn't posted any, or if it doesn't match the contribution.
+    pub(crate) fn write_pending_contribution_info(
+        &mut self,
+        participant: &Participant,
+        contribution_id: u64,
+        response_hash: &str,
+    ) -> Result<(), CoordinatorError> {
+        let contribution_info = match self.state.take_pending_contribution_info(participant) {
+            Some(contribution_info) if contribution_info.contribution_file_hash == response_hash => contribution_info,
+            Some(_) => {
+                warn!(
+                    "The contribution info of {} doesn't match its contribution",
+                    participant
+                );
+                return self.save_state();
+            }
+            None => return Ok(()),
+        };
+        self.write_contribution_info(contribution_id, contribution_info.clone())?;
+        self.update_contribution_summary(contribution_info.into())?;
+        self.save_state()
+    }
+
     /// Writes the contribution metadata to storage at the appropriate locator.
     pub(crate) fn write_contribution_info(
         &mut self,
+        contribution_id: u64,
         contribution_info: ContributionInfo,
     ) -> Result<(), CoordinatorError> {
         self.storage.insert(
             Locator::ContributionInfoFile {
                 round_height: contribution_info.ceremony_round,
+                contribution_id,
             },
             Object::ContributionInfoFile(contribution_info),
         )
@@ -1708,6 +1839,152 @@ impl Coordinator {
         )
     }
 
+    /// Returns the receipt of the contribution of the given participant in the given round,
+    /// signed with the key of the coordinator's default verifier.
+    ///
+    /// The receipt can only be issued once the contribution has been verified, which means
+    /// that the next challenge built on top of it is available.
+    pub(crate) fn contribution_receipt(
+        &self,
+        participant: &Participant,
+        round_height: u64,
+    ) -> Result<ContributionReceipt, CoordinatorError> {
+        let round = self.get_round(round_height)?;
+        if !round.is_contributor(participant) {
+            return Err(CoordinatorError::ParticipantUnauthorized);
+        }
+        let (contribution_id, contribution) = round
+            .chunk(0)?
+            .get_contributions()
+            .iter()
+            .find(|(_, contribution)| contribution.get_contributor().as_ref() == Some(participant))
+            .ok_or(CoordinatorError::ContributionMissing)?;
+        if !contribution.is_verified() {
+            return Err(CoordinatorError::ContributionMissingVerification);
+        }
+        let contribution_id = *contribution_id;
+
+        // The response of the contributor becomes the next challenge after verification, which
+        // is the challenge of the next round for the last contribution of the round
+        let next_challenge_locator = match contribution_id == round.expected_number_of_contributions() - 1 {
+            true => ContributionLocator::new(round_height + 1, 0, 0, true),
+            false => ContributionLocator::new(round_height, 0, contribution_id, true),
+        };
+        let next_challenge_locator = Locator::ContributionFile(next_challenge_locator);
+        if !self.storage.exists(&next_challenge_locator) {
+            return Err(CoordinatorError::ContributionMissingVerification);
+        }
+        let next_challenge = self.storage.reader(&next_challenge_locator)?;
+        if next_challenge.len() < 64 {
+            return Err(CoordinatorError::ContributionFileSizeMismatch);
+        }
+
+        let verifier = &self.environment.coordinator_verifiers()[0];
+        let mut receipt = ContributionReceipt::new(
+            round_height,
+            participant.address(),
+            hex::encode(calculate_hash(&next_challenge[64..])),
+            hex::encode(calculate_hash(next_challenge.as_ref())),
+            verifier.address(),
+        );
+        receipt
+            .try_sign(&self.environment.default_verifier_signing_key())
+            .map_err(anyhow::Error::from)?;
+
+        Ok(receipt)
+    }
+
+    /// Builds the next challenge from the bytes of a response file, the same way the verification
+    /// does: the hash of the response followed by its parameters.
+    pub(crate) fn next_challenge(response: &[u8]) -> Result<Vec<u8>, CoordinatorError> {
+        if response.len() < 64 {
+            return Err(CoordinatorError::ContributionFileSizeMismatch);
+        }
+        let mut challenge = calculate_hash(response).to_vec();
+        challenge.extend_from_slice(&response[64..]);
+
+        Ok(challenge)
+    }
+
+    /// Returns the round height of the contribution the given contributor will compute, together
+    /// with the locator and the hash of the latest contribution of the current round it is built
+    /// on before its verification completes.
+    ///
+    /// The next challenge only depends on the response file, so the contributor can start computing
+    /// while the verification runs. Should the verification fail, the contribution is dropped and the
+    /// actual challenge differs from this one: the contributor notices it from the challenge hash and
+    /// discards its speculative computation.
+    ///
+    /// The challenge is only provided to the participant contributing right after the latest
+    /// contribution: the next contributor of the current round, or the contributor of the next
+    /// round if it is the only one.
+    pub(crate) fn speculative_challenge(
+        &self,
+        participant: &Participant,
+    ) -> Result<(u64, ContributionLocator, String), CoordinatorError> {
+        let round = Self::load_current_round(&self.storage)?;
+        let round_height = round.round_height();
+        let chunk = round.chunk(0)?;
+        let contribution_id = chunk.current_contribution_id();
+
+        // Check that the latest contribution has been uploaded and is waiting for its verification.
+        if chunk.current_contribution()?.is_verified() {
+            return Err(CoordinatorError::ContributionAlreadyVerified);
+        }
+        if !self
+            .state
+            .get_pending_verifications()
+            .contains_key(&Task::new(0, contribution_id))
+        {
+            return Err(CoordinatorError::ContributionMissing);
+        }
+
+        // Check that the participant is the one who will compute on top of it.
+        let is_final_contribution = contribution_id == round.expected_number_of_contributions() - 1;
+        let next_round_height = match self.state.current_participant_info(participant) {
+            Some(participant_info) => match participant_info.assigned_tasks().front() {
+                Some(task) if task.contribution_id() == contribution_id + 1 => round_height,
+                _ => return Err(CoordinatorError::ParticipantUnauthorized),
+            },
+            // The order of the contributors of the next round is only known once it has started
+            None => match self.state.queue_contributor_info(participant) {
+                Some((_, Some(assigned_round), _, _))
+                    if is_final_contribution
+                        && *assigned_round == round_height + 1
+                        && self.environment.maximum_contributors_per_round() == 1 =>
+                {
+                    round_height + 1
+                }
+                _ => return Err(CoordinatorError::ParticipantUnauthorized),
+            },
+        };
+
+        // The signed response hash has been checked against the response when it was contributed.
+        let signature_locator = Locator::ContributionFileSignature(ContributionSignatureLocator::new(
+            round_height,
+            0,
+            contribution_id,
+            false,
+        ));
+        let signature: ContributionFileSignature = serde_json::from_slice(&*self.storage.reader(&signature_locator)?)?;
+
+        Ok((
+            next_round_height,
+            ContributionLocator::new(round_height, 0, contribution_id, false),
+            signature.get_response_hash().to_owned(),
+        ))
+    }
+
+    /// Builds the speculative challenge on top of the given response.
+    pub(crate) fn speculative_challenge_bytes(
+        &self,
+        response_locator: &ContributionLocator,
+    ) -> Result<Vec<u8>, CoordinatorError> {
+        let response = self.storage.reader(&Locator::ContributionFile(*response_locator))?;
+
+        Self::next_challenge(response.as_ref())
+    }
+
     /// Writes the bytes of a contribution file signature to storage at the appropriate  
     /// locator.
     pub(crate) fn write_contribution_file_signature(
@@ -2307,8 +2584,14 @@ impl Coordinator {
                     &replace_action.tasks,
                 )?;
 
-                // Remove contribution info and trimmed info
-                self.storage.clear_info_files(round.round_height());
+                // Remove contribution info and trimmed info of the dropped contributions
+                for task in &replace_action.tasks {
+                    self.storage.clear_contribution_info_file(
+                        round.round_height(),
+                        task.contribution_id(),
+                        &replace_action.dropped_contributor.address(),
+                    );
+                }
 
                 // Assign a replacement contributor from the queue to the dropped tasks for the current round.
                 round.add_replacement_contributor_unsafe(replace_action.replacement_contributor.clone())?;
@@ -2325,6 +2608,62 @@ impl Coordinator {
                     Object::RoundState(round),
                 )?;
             }
+            CeremonyStorageAction::DropContributions(drop_action) => {
+                let task = drop_action.task;
+                warn!(
+                    "Dropping contribution {} of {} and the subsequent ones",
+                    task, drop_action.dropped_contributor
+                );
+
+                // Fetch the current round from storage.
+                let mut round = Self::load_current_round(&self.storage)?;
+                let round_height = round.round_height();
+
+                // Remove the contributor and its contribution from the round, with the subsequent contributions.
+                round.remove_contributor_unsafe(&mut self.storage, &drop_action.dropped_contributor, &[], &[task])?;
+                self.storage.clear_contribution_info_file(
+                    round_height,
+                    task.contribution_id(),
+                    &drop_action.dropped_contributor.address(),
+                );
+
+                // Without contributors after the dropped one, the last verified contribution becomes the final one of
+                // the round: its next challenge is moved to the initial challenge of the next round.
+                if task.contribution_id() == round.expected_number_of_contributions() {
+                    let contribution_id = task.contribution_id() - 1;
+                    let next_challenge = Locator::ContributionFile(ContributionLocator::new(
+                        round_height,
+                        task.chunk_id(),
+                        contribution_id,
+                        true,
+                    ));
+                    let next_challenge_signature = Locator::ContributionFileSignature(
+                        ContributionSignatureLocator::new(round_height, task.chunk_id(), contribution_id, true),
+                    );
+                    let next_round_challenge =
+                        Locator::ContributionFile(ContributionLocator::new(round_height + 1, task.chunk_id(), 0, true));
+                    let next_round_challenge_signature = Locator::ContributionFileSignature(
+                        ContributionSignatureLocator::new(round_height + 1, task.chunk_id(), 0, true),
+                    );
+                    for (source, destination) in [
+                        (&next_challenge, &next_round_challenge),
+                        (&next_challenge_signature, &next_round_challenge_signature),
+                    ] {
+                        self.storage.copy(source, destination)?;
+                        self.storage.remove(source)?;
+                    }
+
+                    round.chunk_mut(task.chunk_id())?.set_verified_locators_unsafe(
+                        contribution_id,
+                        self.storage.to_path(&next_round_challenge)?,
+                        self.storage.to_path(&next_round_challenge_signature)?,
+                    )?;
+                }
+
+                // Save the updated round to storage.
+                self.storage
+                    .update(&Locator::RoundState { round_height }, Object::RoundState(round))?;
+            }
         }
 
         Ok(())
@@ -2530,7 +2869,10 @@ impl Coordinator {
     /// # Error
     /// This function assumes that the given task has been indeed assigned to the
     /// default verifier.
-    pub fn default_verify(&mut self, task: &Task) -> anyhow::Result<()> {
+    ///
+    /// An invalid contribution is reported as [`CoordinatorError::VerificationFailed`],
+    /// any other error is an internal fault of the coordinator.
+    pub fn default_verify(&mut self, task: &Task) -> Result<(), CoordinatorError> {
         let verifier = self.environment.coordinator_verifiers()[0].clone();
         let sigkey = self.environment.default_verifier_signing_key();
 
@@ -2546,7 +2888,7 @@ impl Coordinator {
         verifier: &Participant,
         verifier_signing_key: &SigningKey,
         task: &Task,
-    ) -> anyhow::Result<()> {
+    ) -> Result<(), CoordinatorError> {
         let round_height = self.current_round_height()?;
         debug!(
             "Running verification for round {} chunk {}",
@@ -2795,9 +3137,10 @@ impl Coordinator {
 mod tests {
     use crate::{
         authentication::Dummy,
-        commands::{Seed, SigningKey, SEED_LENGTH},
+        commands::{ContributionHeader, Seed, SigningKey, SEED_LENGTH},
         environment::*,
         objects::{Participant, Task},
+        storage::{ContributionLocator, Locator, StorageObject},
         testing::prelude::*,
         Coordinator,
     };
@@ -3555,6 +3898,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    // This test runs two rounds with two contributors each, so that the contribution files of the second round
+    // contain more contributions than in a ceremony with one contributor per round.
+    fn coordinator_multiple_contributors_per_round() -> anyhow::Result<()> {
+        let environment = initialize_test_environment(&Testing::default().maximum_contributors_per_round(2).into());
+
+        let mut coordinator = Coordinator::new(environment.clone(), Arc::new(Dummy))?;
+        initialize_coordinator(&mut coordinator)?;
+
+        let contributors = vec![
+            Lazy::force(&TEST_CONTRIBUTOR_ID).clone(),
+            Lazy::force(&TEST_CONTRIBUTOR_ID_2).clone(),
+        ];
+        let contributor_signing_key: SigningKey = "secret_key".to_string();
+
+        let verifier = Lazy::force(&TEST_VERIFIER_ID);
+        let verifier_signing_key: SigningKey = "secret_key".to_string();
+
+        let chunk_id = 0;
+        for round_height in 1..=2 {
+            assert_eq!(round_height, coordinator.current_round_height()?);
+            assert_eq!(3, coordinator.current_round()?.expected_number_of_contributions());
+
+            // The contributors contribute in sequence, each on top of the verified contribution of the previous one.
+            for (contribution_id, contributor) in (1..).zip(&contributors) {
+                coordinator.try_lock_chunk(chunk_id, contributor)?;
+
+                let mut seed: Seed = [0; SEED_LENGTH];
+                rand::thread_rng().fill_bytes(&mut seed[..]);
+                coordinator.run_computation(
+                    round_height,
+                    chunk_id,
+                    contribution_id,
+                    contributor,
+                    &contributor_signing_key,
+                    &seed,
+                )?;
+                coordinator.add_contribution(chunk_id, contributor)?;
+
+                let task = Task::new(chunk_id, contribution_id);
+                coordinator.run_verification(round_height, &task, verifier, &verifier_signing_key)?;
+                coordinator.verify_contribution(&task, verifier)?;
+            }
+
+            coordinator.aggregate_contributions()?;
+            coordinator.next_round(OffsetDateTime::now_utc(), contributors.clone())?;
+        }
+        assert_eq!(3, coordinator.current_round_height()?);
+
+        // The challenge of round 3 contains the four contributions of the previous rounds.
+        let challenge_locator = Locator::ContributionFile(ContributionLocator::new(3, chunk_id, 0, true));
+        let challenge = coordinator.storage.reader(&challenge_locator)?;
+        assert_eq!(
+            challenge.len() as u64,
+            environment.circuit_set().contribution_file_size(4)
+        );
+        ContributionHeader::from_file(challenge.as_ref())?;
+
+        Ok(())
+    }
+
     #[test]
     #[serial]
     #[ignore]
@@ -3,6 +3,7 @@ use crate::{
     objects::{
         participant::*,
         task::{initialize_tasks, Task},
+        ContributionInfo,
     },
     storage::{Disk, Locator, Object},
     CoordinatorError,
@@ -982,6 +983,13 @@ pub struct CoordinatorState {
     ceremony_start_time: OffsetDateTime,
     /// The list of valid tokens for each cohort.
     tokens: Vec<Vec<String>>,
+    /// The contribution info posted by the current contributors, recorded once their contribution is accepted.
+    #[serde(default)]
+    pending_contribution_info: HashMap<Participant, ContributionInfo>,
+    /// The shared addresses (e.g. proxies or Tor exit nodes) exempted from the duplicate ip check.
+    /// They are configured at startup and not persisted.
+    #[serde(skip)]
+    ip_ban_exemptions: HashSet<IpAddr>,
 }
 
 impl CoordinatorState {
@@ -1042,9 +1050,40 @@ impl CoordinatorState {
             manual_lock: false,
             ceremony_start_time: CoordinatorState::get_ceremony_start_time(),
             tokens: tokens.unwrap_or_else(|| CoordinatorState::get_tokens()),
+            pending_contribution_info: HashMap::default(),
+            ip_ban_exemptions: HashSet::new(),
         }
     }
 
+    ///
+    /// Sets the contribution info of the given contributor, until its contribution is accepted.
+    ///
+    pub(super) fn set_pending_contribution_info(&mut self, participant: Participant, info: ContributionInfo) {
+        self.pending_contribution_info.insert(participant, info);
+    }
+
+    ///
+    /// Removes and returns the contribution info of the given contributor, if any.
+    ///
+    pub(super) fn take_pending_contribution_info(&mut self, participant: &Participant) -> Option<ContributionInfo> {
+        self.pending_contribution_info.remove(participant)
+    }
+
+    ///
+    /// Sets the addresses exempted from the duplicate ip check.
+    ///
+    pub(super) fn set_ip_ban_exemptions(&mut self, ip_ban_exemptions: HashSet<IpAddr>) {
+        self.ip_ban_exemptions = ip_ban_exemptions;
+    }
+
+    ///
+    /// Returns `true` if the ip belongs to a known shared address (e.g. a proxy or a Tor exit node)
+    /// which must not be tracked for the duplicate ip check.
+    ///
+    fn is_ip_ban_exempt(&self, ip: &IpAddr) -> bool {
+        self.ip_ban_exemptions.contains(ip)
+    }
+
     /// Reset the progress of the current round, back to how it was in
     /// its initialized state, however this does maintain the drop
     /// status of participants.
@@ -1150,6 +1189,7 @@ impl CoordinatorState {
                 contributors_ips: std::mem::take(&mut self.contributors_ips),
                 queue,
                 banned: std::mem::take(&mut self.banned),
+                ip_ban_exemptions: std::mem::take(&mut self.ip_ban_exemptions),
                 ..Self::new(self.environment.clone(), Some(std::mem::take(&mut self.tokens)))
             };
 
@@ -1192,6 +1232,7 @@ impl CoordinatorState {
                 queue: std::mem::take(&mut self.queue),
                 banned: std::mem::take(&mut self.banned),
                 dropped: std::mem::take(&mut self.dropped),
+                ip_ban_exemptions: std::mem::take(&mut self.ip_ban_exemptions),
                 ..Self::new(self.environment.clone(), Some(std::mem::take(&mut self.tokens)))
             };
 
@@ -1434,6 +1475,53 @@ impl CoordinatorState {
         self.tokens.len()
     }
 
+    ///
+    /// Returns the earliest upcoming time at which an update of the coordinator
+    /// has something to do without any action of the participants: a participant
+    /// times out, the next round may start or the ceremony ends.
+    ///
+    /// Deadlines which are already past are ignored, returns `None` if there
+    /// is no upcoming deadline.
+    ///
+    pub(super) fn next_scheduled_update(&self, time: &dyn TimeSource) -> Option<OffsetDateTime> {
+        let now = time.now_utc();
+
+        let contributor_seen_timeout = self.environment.contributor_seen_timeout();
+        let participant_lock_timeout = self.environment.participant_lock_timeout();
+        let queue_seen_timeout = self.environment.queue_seen_timeout();
+
+        let seen_deadlines = self
+            .current_contributors
+            .iter()
+            .filter(|(participant, _)| !self.is_coordinator_contributor(participant))
+            .map(|(_, participant_info)| participant_info.last_seen + contributor_seen_timeout);
+        let lock_deadlines = self
+            .current_contributors
+            .iter()
+            .chain(self.current_verifiers.iter())
+            .filter(|(participant, _)| !self.is_coordinator_contributor(participant))
+            .flat_map(|(_, participant_info)| participant_info.locked_chunks.values())
+            .map(|lock| lock.lock_time + participant_lock_timeout);
+        let queue_deadlines = self
+            .queue
+            .values()
+            .map(|(_, _, last_seen, _)| *last_seen + queue_seen_timeout);
+        let next_round_after = self
+            .current_metrics
+            .as_ref()
+            .and_then(|metrics| metrics.next_round_after);
+        let ceremony_end =
+            self.ceremony_start_time + Duration::seconds((*COHORT_TIME * self.get_number_of_cohorts()) as i64);
+
+        seen_deadlines
+            .chain(lock_deadlines)
+            .chain(queue_deadlines)
+            .chain(next_round_after)
+            .chain(std::iter::once(ceremony_end))
+            .filter(|deadline| *deadline > now)
+            .min()
+    }
+
     ///
     /// Returns the list of valid tokens for a given cohort.
     ///
@@ -1569,9 +1657,10 @@ impl CoordinatorState {
         participant: &Participant,
         participant_ip: Option<&IpAddr>,
     ) -> Result<(), CoordinatorError> {
-        // Check that the pariticipant IP is not known.
+        // Check that the pariticipant IP is not known. Shared addresses are exempted, the token and
+        // the public key checks still apply to them.
         if let Some(ip) = participant_ip {
-            if *IP_BAN && self.is_duplicate_ip(ip) {
+            if *IP_BAN && !self.is_ip_ban_exempt(ip) && self.is_duplicate_ip(ip) {
                 return Err(CoordinatorError::ParticipantIpAlreadyAdded);
             }
         }
@@ -1638,8 +1727,8 @@ impl CoordinatorState {
             (reliability_score, None, time.now_utc(), time.now_utc()),
         );
 
-        // Add ip (if any) to the set of known addresses
-        if let Some(ip) = participant_ip {
+        // Add ip (if any) to the set of known addresses, unless it's shared among many contributors
+        if let Some(ip) = participant_ip.filter(|ip| !self.is_ip_ban_exempt(ip)) {
             self.contributors_ips.insert(ip, participant);
         }
 
@@ -2199,6 +2288,9 @@ impl CoordinatorState {
 
         warn!("Dropping {} from the ceremony", participant);
 
+        // Discard the contribution info of a contribution which won't be accepted.
+        self.pending_contribution_info.remove(participant);
+
         // Remove the participant from the queue and precommit, if present.
         if self.queue.contains_key(participant) || self.next.contains_key(participant) {
             // Remove the participant from the queue.
@@ -2475,6 +2567,115 @@ impl CoordinatorState {
         Ok(drop)
     }
 
+    ///
+    /// Drops the contribution of the given task, which failed its verification, together with the
+    /// contributions built on top of it, and bans its author from the ceremony.
+    ///
+    /// The contributors of a round contribute in sequence to a single chunk, so the contributions
+    /// preceding the invalid one are kept. The contributors following its author move up by one
+    /// place and restart their tasks, the next one building on top of the last verified contribution.
+    ///
+    /// If the author was the only contributor of the round, the round is rolled back to wait for
+    /// new participants.
+    ///
+    #[tracing::instrument(
+        skip(self, task, time),
+        fields(task = %task)
+    )]
+    pub(super) fn drop_invalid_contribution(
+        &mut self,
+        task: &Task,
+        time: &dyn TimeSource,
+    ) -> Result<DropParticipant, CoordinatorError> {
+        let current_round_height = self.current_round_height.ok_or(CoordinatorError::RoundHeightNotSet)?;
+        let finished_contributors = self
+            .finished_contributors
+            .get_mut(&current_round_height)
+            .ok_or(CoordinatorError::RoundCommitFailedOrCorrupted)?;
+
+        // Fetch the author of the contribution, who may have already finished the round.
+        let author = self
+            .current_contributors
+            .iter()
+            .chain(finished_contributors.iter())
+            .find(|(_, participant_info)| participant_info.completed_tasks.contains(task))
+            .map(|(participant, _)| participant.clone())
+            .ok_or(CoordinatorError::ContributorsMissing)?;
+        let mut author_info = match self.current_contributors.remove(&author) {
+            Some(participant_info) => participant_info,
+            None => finished_contributors
+                .remove(&author)
+                .ok_or_else(|| CoordinatorError::ParticipantNotFound(author.clone()))?,
+        };
+        warn!("Dropping the contribution {} of {}", task, author);
+
+        // The contributors following the author move up by one place. Their tasks are restarted, as
+        // the contributions they may have built on top of the invalid one are dropped as well.
+        let number_of_contributors = self
+            .current_metrics
+            .as_ref()
+            .ok_or(CoordinatorError::CoordinatorStateNotInitialized)?
+            .number_of_contributors
+            - 1;
+        let following_contributors: Vec<Participant> = finished_contributors
+            .iter()
+            .filter(|(_, participant_info)| participant_info.bucket_id > author_info.bucket_id)
+            .map(|(participant, _)| participant.clone())
+            .collect();
+        for participant in following_contributors {
+            if let Some(participant_info) = finished_contributors.remove(&participant) {
+                self.current_contributors.insert(participant, participant_info);
+            }
+        }
+        for participant_info in self.current_contributors.values_mut() {
+            if participant_info.bucket_id > author_info.bucket_id {
+                participant_info.bucket_id -= 1;
+                let tasks = initialize_tasks(
+                    participant_info.bucket_id,
+                    self.environment.number_of_chunks(),
+                    number_of_contributors,
+                )?;
+                participant_info.restart_tasks(tasks, time)?;
+            }
+        }
+        if let Some(metrics) = &mut self.current_metrics {
+            metrics.number_of_contributors = number_of_contributors;
+        }
+
+        // Remove the dropped contributions from the pending verifications.
+        self.pending_verification = self
+            .pending_verification
+            .drain()
+            .filter(|(pending_task, _)| {
+                pending_task.chunk_id() != task.chunk_id() || pending_task.contribution_id() < task.contribution_id()
+            })
+            .collect();
+
+        // Ban the author, whose contribution doesn't count as finished anymore.
+        author_info.finished_at = None;
+        author_info.drop(time)?;
+        self.dropped.push(author_info);
+        self.banned.insert(author.clone());
+        info!("{} was banned from the ceremony", author);
+
+        let storage_action = match number_of_contributors {
+            0 => {
+                let mut reset_action = self.reset_current_round(true, time)?;
+                reset_action.remove_participants.push(author.clone());
+                CeremonyStorageAction::ResetCurrentRound(reset_action)
+            }
+            _ => CeremonyStorageAction::DropContributions(DropContributionsStorageAction {
+                dropped_contributor: author.clone(),
+                task: *task,
+            }),
+        };
+
+        Ok(DropParticipant::DropCurrent(DropCurrentParticpantData {
+            _participant: author,
+            storage_action,
+        }))
+    }
+
     ///
     /// Unbans the given participant from joining the queue.
     ///
@@ -3097,8 +3298,10 @@ impl CoordinatorState {
              * ***********************************************************************************
              */
 
-            // Sort the contributors by their reliability (in order of highest to lowest number).
-            contributors.par_sort_by(|a, b| ((b.1).0).cmp(&(&a.1).0));
+            // Sort the contributors by their reliability (in order of highest to lowest number),
+            // then by the time they joined the queue, which sets the order of the sequential
+            // contributions when there is a single chunk.
+            contributors.par_sort_by(|a, b| ((b.1).0).cmp(&(&a.1).0).then(((a.1).3).cmp(&(b.1).3)));
 
             // Fetch the number of chunks and bucket size.
             let number_of_chunks = self.environment.number_of_chunks() as u64;
@@ -3422,6 +3625,17 @@ pub struct ReplaceContributorStorageAction {
     pub replacement_contributor: Participant,
 }
 
+/// Action to update the storage to reflect an invalid contribution
+/// being dropped in [CoordinatorState].
+#[derive(Debug)]
+pub struct DropContributionsStorageAction {
+    /// The author of the invalid contribution.
+    pub dropped_contributor: Participant,
+    /// The task of the invalid contribution, which is dropped with
+    /// the subsequent contributions of its chunk.
+    pub task: Task,
+}
+
 /// Actions taken to update the round/storage to reflect a change in
 /// [CoordinatorState].
 #[derive(Debug)]
@@ -3430,6 +3644,8 @@ pub enum CeremonyStorageAction {
     ResetCurrentRound(ResetCurrentRoundStorageAction),
     /// See [ReplaceContributorStorageAction].
     ReplaceContributor(ReplaceContributorStorageAction),
+    /// See [DropContributionsStorageAction].
+    DropContributions(DropContributionsStorageAction),
 }
 
 /// Data required by the coordinator to drop a participant from the
@@ -3696,6 +3912,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_next_scheduled_update() {
+        let environment = TEST_ENVIRONMENT.clone();
+
+        // Fetch the contributor of the coordinator.
+        let contributor = test_coordinator_contributor(&environment).unwrap();
+        let contributor_ip = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+        // Initialize a new coordinator state.
+        let mut state = CoordinatorState::new(environment.clone(), None);
+        state.initialize(5);
+
+        let time = MockTimeSource::new(OffsetDateTime::now_utc());
+        let joined = time.now_utc();
+
+        // Add the contributor of the coordinator to the queue.
+        state
+            .add_to_queue(contributor.clone(), Some(contributor_ip), 10, &time)
+            .unwrap();
+
+        // The contributor times out in the queue if it isn't seen.
+        let queue_deadline = joined + environment.queue_seen_timeout();
+        assert_eq!(Some(queue_deadline), state.next_scheduled_update(&time));
+
+        // A heartbeat postpones the deadline.
+        time.update(|t| t + Duration::seconds(10));
+        state.heartbeat(&contributor, &time).unwrap();
+        assert_eq!(
+            Some(queue_deadline + Duration::seconds(10)),
+            state.next_scheduled_update(&time)
+        );
+
+        // Past deadlines are ignored.
+        time.update(|t| t + environment.queue_seen_timeout() + Duration::seconds(1));
+        assert!(
+            state
+                .next_scheduled_update(&time)
+                .map_or(true, |deadline| deadline > time.now_utc())
+        );
+    }
+
     #[test]
     fn test_update_queue_assignment() {
         let time = SystemTimeSource::new();
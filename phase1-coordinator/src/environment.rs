@@ -1,4 +1,4 @@
-use crate::{authentication::KeyPair, objects::Participant, storage::Disk};
+use crate::{authentication::KeyPair, commands::CircuitSet, objects::Participant, storage::Disk};
 pub use phase1::{helpers::CurveKind, ContributionMode, ProvingSystem};
 use setup_utils::{CheckForCorrectness, UseCompression};
 
@@ -224,6 +224,12 @@ impl Parameters {
 pub struct Environment {
     /// The parameter settings of this coordinator.
     parameters: Settings,
+    /// The set of circuits the parameters are generated for.
+    #[serde(default)]
+    circuit_set: CircuitSet,
+    /// The identifier of the ceremony, recorded in the header of the contribution files.
+    #[serde(default)]
+    ceremony_id: u64,
     /// The compressed input setting of the coordinator.
     compressed_inputs: UseCompression,
     /// The compressed output setting of the coordinator.
@@ -291,6 +297,20 @@ impl Environment {
         self.parameters.clone()
     }
 
+    ///
+    /// Returns the set of circuits the parameters are generated for.
+    ///
+    pub const fn circuit_set(&self) -> CircuitSet {
+        self.circuit_set
+    }
+
+    ///
+    /// Returns the identifier of the ceremony.
+    ///
+    pub const fn ceremony_id(&self) -> u64 {
+        self.ceremony_id
+    }
+
     ///
     /// Returns the compressed input setting of the coordinator.
     ///
@@ -522,6 +542,16 @@ impl Testing {
         self
     }
 
+    pub fn circuit_set(mut self, circuit_set: CircuitSet) -> Self {
+        self.environment.circuit_set = circuit_set;
+        self
+    }
+
+    pub fn ceremony_id(mut self, ceremony_id: u64) -> Self {
+        self.environment.ceremony_id = ceremony_id;
+        self
+    }
+
     #[inline]
     pub fn coordinator_contributors(&self, contributors: &[Participant]) -> Self {
         // Check that all participants are contributors.
@@ -573,6 +603,8 @@ impl Testing {
                     batch_size: 16,
                 }
                 .to_settings(),
+                circuit_set: CircuitSet::Test,
+                ceremony_id: 0,
                 compressed_inputs: UseCompression::No,
                 compressed_outputs: UseCompression::Yes,
                 check_input_for_correctness: CheckForCorrectness::No,
@@ -667,6 +699,16 @@ impl Development {
         self
     }
 
+    pub fn circuit_set(mut self, circuit_set: CircuitSet) -> Self {
+        self.environment.circuit_set = circuit_set;
+        self
+    }
+
+    pub fn ceremony_id(mut self, ceremony_id: u64) -> Self {
+        self.environment.ceremony_id = ceremony_id;
+        self
+    }
+
     #[inline]
     pub fn coordinator_contributors(&self, contributors: &[Participant]) -> Self {
         // Check that all participants are contributors.
@@ -700,6 +742,8 @@ impl Development {
                     batch_size: 16,
                 }
                 .to_settings(),
+                circuit_set: CircuitSet::Masp,
+                ceremony_id: 0,
                 compressed_inputs: UseCompression::No,
                 compressed_outputs: UseCompression::Yes,
                 check_input_for_correctness: CheckForCorrectness::No,
@@ -805,6 +849,16 @@ impl Production {
         self
     }
 
+    pub fn circuit_set(mut self, circuit_set: CircuitSet) -> Self {
+        self.environment.circuit_set = circuit_set;
+        self
+    }
+
+    pub fn ceremony_id(mut self, ceremony_id: u64) -> Self {
+        self.environment.ceremony_id = ceremony_id;
+        self
+    }
+
     #[inline]
     pub fn coordinator_contributors(&self, contributors: &[Participant]) -> Self {
         // Check that all participants are contributors.
@@ -843,6 +897,8 @@ impl Production {
                     batch_size: 16,
                 }
                 .to_settings(),
+                circuit_set: CircuitSet::Masp,
+                ceremony_id: 0,
                 compressed_inputs: UseCompression::No,
                 compressed_outputs: UseCompression::Yes,
                 check_input_for_correctness: CheckForCorrectness::No,
@@ -1,4 +1,4 @@
-use std::{fmt::Display, io::Write, ops::Deref};
+use std::{collections::HashSet, fmt::Display, io::Write, net::IpAddr, ops::Deref};
 
 use crate::authentication::KeyPair;
 use bip39::{Language, Mnemonic};
@@ -23,6 +23,8 @@ pub enum IOError {
     CheckMnemonicError,
     #[error("Error in IO: {0}")]
     InputError(#[from] std::io::Error),
+    #[error("Invalid ip address: {0}")]
+    InvalidIpAddress(String),
     #[error("Error in KeyPair generation: {0}")]
     KeyPairError(#[from] ed25519_compact::Error),
     #[error("Mnemonic error: {0}")]
@@ -150,6 +152,25 @@ pub fn seed_from_string(input: &str) -> Result<[u8; 64]> {
     Ok(mnemonic.to_seed_normalized(""))
 }
 
+/// Parses a list of addresses, one per line, which are exempted from the duplicate ip check. Empty lines and lines
+/// starting with `#` are ignored.
+pub fn parse_ip_ban_exemptions(content: &str) -> Result<HashSet<IpAddr>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            line.parse::<IpAddr>()
+                .map_err(|_| IOError::InvalidIpAddress(line.to_owned()))
+        })
+        .collect()
+}
+
+/// Reads the addresses exempted from the duplicate ip check from the given file.
+pub fn read_ip_ban_exemptions(path: &str) -> Result<HashSet<IpAddr>> {
+    parse_ip_ban_exemptions(&std::fs::read_to_string(path)?)
+}
+
 /// Generates a new [`KeyPair`] from a mnemonic retrieved from the coordinator.mnemonic file in the current working directory.
 pub fn keypair_from_mnemonic() -> Result<KeyPair> {
     let mnemonic_str = std::fs::read_to_string(COORDINATOR_MNEMONIC_FILE)?;
@@ -239,7 +260,20 @@ fn check_mnemonic(mnemonic: &Mnemonic) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::seed_from_string;
+    use super::{parse_ip_ban_exemptions, seed_from_string};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn test_parse_ip_ban_exemptions() {
+        let content = "# Tor exit nodes\n127.0.0.1\n\n  ::1  \n";
+        let exemptions = parse_ip_ban_exemptions(content).unwrap();
+
+        assert_eq!(2, exemptions.len());
+        assert!(exemptions.contains(&IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        assert!(exemptions.contains(&IpAddr::V6(Ipv6Addr::LOCALHOST)));
+
+        assert!(parse_ip_ban_exemptions("127.0.0.1\nnot an ip\n").is_err());
+    }
 
     #[test]
     fn test_seed_from_string() {
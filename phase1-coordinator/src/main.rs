@@ -1,7 +1,7 @@
 use phase1_coordinator::{
     authentication::Production as ProductionSig,
     io::{self, KeyPairUser},
-    rest::{self, ResponseError, UPDATE_TIME},
+    rest::{self, ResponseError, Triggers, SWEEP_TIME},
     s3::S3Ctx,
     Coordinator,
 };
@@ -21,26 +21,36 @@ use rocket::{
 };
 
 use anyhow::Result;
-use std::{io::Write, sync::Arc};
+use std::{collections::HashSet, io::Write, sync::Arc, time::Duration};
 
 use tracing::{error, info};
 
-/// Periodically updates the [`Coordinator`]
-async fn update_coordinator(coordinator: Arc<RwLock<Coordinator>>, shutdown: Shutdown) -> Result<()> {
+/// Margin added to the deadlines of the participants, which time out once their deadline is strictly exceeded
+const DEADLINE_MARGIN: Duration = Duration::from_secs(1);
+
+/// Updates the [`Coordinator`] when requested, or as soon as a participant may time out or the next round may start.
+/// A periodic sweep catches any other change
+async fn update_coordinator(
+    coordinator: Arc<RwLock<Coordinator>>,
+    triggers: Arc<Triggers>,
+    shutdown: Shutdown,
+) -> Result<()> {
     loop {
-        tokio::time::sleep(UPDATE_TIME).await;
+        let timeout = match coordinator.read().await.time_to_next_scheduled_update() {
+            Some(time_to_next_update) => SWEEP_TIME.min(time_to_next_update + DEADLINE_MARGIN),
+            None => SWEEP_TIME,
+        };
+        triggers.update_requested(timeout).await;
 
         info!("Updating coordinator...");
         match rest::perform_coordinator_update(coordinator.clone()).await {
-            Ok(_) => info!(
-                "Update of coordinator completed, {:#?} to the next update round...",
-                UPDATE_TIME
-            ),
+            Ok(_) => info!("Update of coordinator completed"),
             Err(e) => {
                 if let ResponseError::CoordinatorError(phase1_coordinator::CoordinatorError::CeremonyIsOver) = e {
                     shutdown.clone().notify();
-                    // Don't return from this task to give the rocket task time to gracefully shut down the entire process
                     info!("Ceremony is over, server is shutting down...");
+                    // Don't return from this task to give the rocket task time to gracefully shut down the entire process
+                    std::future::pending::<()>().await;
                 } else {
                     return Err(e.into());
                 }
@@ -49,22 +59,6 @@ async fn update_coordinator(coordinator: Arc<RwLock<Coordinator>>, shutdown: Shu
     }
 }
 
-/// Periodically verifies the pending contributions
-async fn verify_contributions(coordinator: Arc<RwLock<Coordinator>>) -> Result<()> {
-    loop {
-        tokio::time::sleep(UPDATE_TIME).await;
-
-        info!("Verifying contributions...");
-        let start = std::time::Instant::now();
-        rest::perform_verify_chunks(coordinator.clone()).await?;
-        info!(
-            "Verification of contributions completed in {:#?}. {:#?} to the next verification round...",
-            start.elapsed(),
-            UPDATE_TIME
-        );
-    }
-}
-
 /// Checks and prints the env variables of interest for the ceremony
 macro_rules! print_env {
     ($($env:expr),*) => {
@@ -103,7 +97,10 @@ pub async fn main() {
         "AWS_S3_BUCKET",
         "AWS_S3_ENDPOINT",
         "NAMADA_MPC_IP_BAN",
+        "NAMADA_MPC_IP_BAN_EXEMPTIONS_PATH",
         "NAMADA_MPC_TIMEOUT_SECONDS",
+        "NAMADA_MPC_CIRCUIT_SET",
+        "NAMADA_MPC_CEREMONY_ID",
         "HEALTH_PATH",
         "NAMADA_TOKENS_PATH",
         "CEREMONY_START_TIMESTAMP",
@@ -126,6 +123,27 @@ pub async fn main() {
     #[cfg(not(debug_assertions))]
     let environment: Production = { Production::new(&keypair) };
 
+    // The circuit set can be overridden, e.g. to run a quick test ceremony with a release build
+    let environment = match std::env::var("NAMADA_MPC_CIRCUIT_SET") {
+        Ok(circuit_set) => environment.circuit_set(circuit_set.parse().expect("Invalid NAMADA_MPC_CIRCUIT_SET")),
+        Err(_) => environment,
+    };
+    info!("Circuit set: {}", environment.environment.circuit_set());
+    let environment = match std::env::var("NAMADA_MPC_CEREMONY_ID") {
+        Ok(ceremony_id) => environment.ceremony_id(ceremony_id.parse().expect("Invalid NAMADA_MPC_CEREMONY_ID")),
+        Err(_) => environment,
+    };
+    info!("Ceremony id: {}", environment.environment.ceremony_id());
+    let environment = match std::env::var("NAMADA_MPC_CONTRIBUTORS_PER_ROUND") {
+        Ok(maximum) => environment
+            .maximum_contributors_per_round(maximum.parse().expect("Invalid NAMADA_MPC_CONTRIBUTORS_PER_ROUND")),
+        Err(_) => environment,
+    };
+    info!(
+        "Maximum contributors per round: {}",
+        environment.environment.maximum_contributors_per_round()
+    );
+
     // Download token file from S3, only if local folder is missing
     if std::fs::metadata(tokens_path.as_str()).is_err() {
         download_tokens(tokens_path.as_str())
@@ -133,9 +151,17 @@ pub async fn main() {
             .expect("Error while retrieving tokens");
     }
 
+    // Load the ip ban exemptions, failing early on an unreadable or malformed file
+    let ip_ban_exemptions = match std::env::var("NAMADA_MPC_IP_BAN_EXEMPTIONS_PATH") {
+        Ok(path) => io::read_ip_ban_exemptions(&path).expect("Invalid NAMADA_MPC_IP_BAN_EXEMPTIONS_PATH"),
+        Err(_) => HashSet::new(),
+    };
+    info!("Ip ban exemptions: {}", ip_ban_exemptions.len());
+
     // Instantiate and start the coordinator
-    let coordinator =
+    let mut coordinator =
         Coordinator::new(environment.into(), Arc::new(ProductionSig)).expect("Failed to instantiate coordinator");
+    coordinator.set_ip_ban_exemptions(ip_ban_exemptions);
     let coordinator: Arc<RwLock<Coordinator>> = Arc::new(RwLock::new(coordinator));
     let up_coordinator = coordinator.clone();
     let verify_coordinator = coordinator.clone();
@@ -152,6 +178,7 @@ pub async fn main() {
         rest::join_queue,
         rest::lock_chunk,
         rest::get_challenge_url,
+        rest::get_speculative_challenge_url,
         rest::get_contribution_url,
         rest::contribute_chunk,
         rest::update_coordinator,
@@ -160,7 +187,9 @@ pub async fn main() {
         rest::verify_chunks,
         rest::get_contributor_queue_status,
         rest::post_contribution_info,
+        rest::get_contribution_receipt,
         rest::get_contributions_info,
+        rest::get_circuit_set,
         rest::get_healthcheck
     ];
 
@@ -169,19 +198,27 @@ pub async fn main() {
         rest::join_queue,
         rest::lock_chunk,
         rest::get_challenge_url,
+        rest::get_speculative_challenge_url,
         rest::get_contribution_url,
         rest::contribute_chunk,
         rest::heartbeat,
         rest::stop_coordinator,
         rest::get_contributor_queue_status,
         rest::post_contribution_info,
+        rest::get_contribution_receipt,
         rest::get_contributions_info,
+        rest::get_circuit_set,
         rest::get_healthcheck
     ];
 
+    let triggers = Arc::new(Triggers::default());
+    let up_triggers = triggers.clone();
+    let verify_triggers = triggers.clone();
+
     let build_rocket = rocket::build()
         .mount("/", routes)
         .manage(coordinator)
+        .manage(triggers)
         .register("/", catchers![
             rest::invalid_signature,
             rest::unauthorized,
@@ -194,11 +231,11 @@ pub async fn main() {
     let ignite_rocket = build_rocket.ignite().await.expect("Coordinator server didn't ignite");
     let shutdown = ignite_rocket.shutdown();
 
-    // Spawn task to update the coordinator periodically
-    let update_handle = rocket::tokio::spawn(update_coordinator(up_coordinator, shutdown));
+    // Spawn task to update the coordinator
+    let update_handle = rocket::tokio::spawn(update_coordinator(up_coordinator, up_triggers, shutdown));
 
-    // Spawn task to verify the contributions periodically
-    let verify_handle = rocket::tokio::spawn(verify_contributions(verify_coordinator));
+    // Spawn task to verify the contributions
+    let verify_handle = rocket::tokio::spawn(rest::verify_contributions(verify_coordinator, verify_triggers));
 
     // Spawn Rocket server task
     let rocket_handle = rocket::tokio::spawn(ignite_rocket.launch());
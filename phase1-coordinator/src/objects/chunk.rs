@@ -453,6 +453,23 @@ impl Chunk {
         self.contributions.remove(&contribution_id);
     }
 
+    /// Sets the verified locators of the contribution corresponding to the given contribution ID.
+    #[inline]
+    pub(crate) fn set_verified_locators_unsafe(
+        &mut self,
+        contribution_id: u64,
+        verified_locator: LocatorPath,
+        verified_signature_locator: LocatorPath,
+    ) -> Result<(), CoordinatorError> {
+        match self.contributions.get_mut(&contribution_id) {
+            Some(contribution) => {
+                contribution.set_verified_locators_unsafe(verified_locator, verified_signature_locator);
+                Ok(())
+            }
+            None => Err(CoordinatorError::ContributionMissing),
+        }
+    }
+
     /// Sets the lock holder for this chunk as the given lock holder.
     #[inline]
     pub(crate) fn set_lock_holder_unsafe(&mut self, lock_holder: Option<Participant>) {
@@ -186,6 +186,18 @@ impl Contribution {
         Ok(())
     }
 
+    /// Sets the locators of the verified contribution, after it has
+    /// been moved in storage.
+    #[inline]
+    pub(crate) fn set_verified_locators_unsafe(
+        &mut self,
+        verified_locator: LocatorPath,
+        verified_signature_locator: LocatorPath,
+    ) {
+        self.verified_locator = Some(verified_locator);
+        self.verified_signature_locator = Some(verified_signature_locator);
+    }
+
     /// Updates `verified` to `true` in this instance of `Contribution`,
     /// if the verifier ID and verified location are valid.
     ///
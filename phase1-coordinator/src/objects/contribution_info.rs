@@ -1,20 +1,19 @@
-use crate::authentication::{KeyPair, Production, Signature};
+use crate::authentication::{self, KeyPair, Production, Signature};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum ContributionInfoError {
     #[error("Keypair doesn't match the pubkey")]
     InvalidSigKey,
+    #[error("Invalid {0} signature")]
+    InvalidSignature(&'static str),
     #[error("Error while serializing ContributionInfo: {0}")]
     SerdeError(#[from] serde_json::Error),
     #[error("Error while signing ContributionInfo: {0}")]
     SignatureError(String),
-    #[error("Expected ContributionInfo to be serialized as a Map")]
-    UnexpectedSerializationFormat,
 }
 
 /// Timestamps of the contribution
@@ -102,22 +101,9 @@ pub struct ContributionInfo {
 
 impl ContributionInfo {
     /// Calculates the hash of the json string encoding all the fields of the struct
-    /// expect for the signature itself.
+    /// except for the signature itself.
     fn hash_for_signature(&self) -> Result<String, ContributionInfoError> {
-        let mut serde_contrib_info = serde_json::to_value(self.clone())?;
-
-        // Remove contributor_info_signature from json
-        let map = serde_contrib_info
-            .as_object_mut()
-            .ok_or(ContributionInfoError::UnexpectedSerializationFormat)?;
-        map.remove("contributor_info_signature");
-        let serialized_contrib_info = serde_contrib_info.to_string();
-
-        // Compute digest
-        let mut hasher = Sha256::new();
-        hasher.update(serialized_contrib_info);
-
-        Ok(format!("{:x?}", hasher.finalize()))
+        Ok(authentication::hash_for_signature(self, "contributor_info_signature")?)
     }
 
     /// Computes the signature of a json string encoding the struct.
@@ -138,9 +124,8 @@ impl ContributionInfo {
         Ok(())
     }
 
-    /// Verifies the signature.
-    #[cfg(test)]
-    fn verify_signature(&self) -> Result<bool, ContributionInfoError> {
+    /// Verifies the signature. Returns `false` if the public key or the signature are not correctly encoded.
+    pub fn verify_signature(&self) -> Result<bool, ContributionInfoError> {
         let serialized_contrib_info = self.hash_for_signature()?;
 
         Ok(Production.verify(
@@ -149,6 +134,32 @@ impl ContributionInfo {
             self.contributor_info_signature.as_str(),
         ))
     }
+
+    /// Verifies all the signatures produced by the contributor: the one of the contribution hash, the one of the
+    /// contribution file hash and the one of the struct itself.
+    pub fn verify_signatures(&self) -> Result<(), ContributionInfoError> {
+        if !Production.verify(
+            &self.public_key,
+            &self.contribution_hash,
+            &self.contribution_hash_signature,
+        ) {
+            return Err(ContributionInfoError::InvalidSignature("contribution hash"));
+        }
+
+        if !Production.verify(
+            &self.public_key,
+            &self.contribution_file_hash,
+            &self.contribution_file_signature,
+        ) {
+            return Err(ContributionInfoError::InvalidSignature("contribution file hash"));
+        }
+
+        if !self.verify_signature()? {
+            return Err(ContributionInfoError::InvalidSignature("contributor info"));
+        }
+
+        Ok(())
+    }
 }
 
 /// A summarized version of [`ContributionInfo`]
@@ -186,6 +197,14 @@ impl TrimmedContributionInfo {
         self.ceremony_round
     }
 
+    pub fn contribution_hash(&self) -> &str {
+        self.contribution_hash.as_ref()
+    }
+
+    pub fn contribution_hash_signature(&self) -> &str {
+        self.contribution_hash_signature.as_ref()
+    }
+
     #[cfg(debug_assertions)]
     pub fn is_another_machine(&self) -> bool {
         self.is_another_machine
@@ -199,9 +218,9 @@ impl TrimmedContributionInfo {
 
 #[cfg(test)]
 mod tests {
-    use crate::authentication::KeyPair;
+    use crate::authentication::{KeyPair, Production, Signature};
 
-    use super::ContributionInfo;
+    use super::{ContributionInfo, ContributionInfoError};
 
     #[test]
     fn sign_and_verify() {
@@ -226,4 +245,36 @@ mod tests {
         test_info.try_sign(&keypair).unwrap();
         assert!(test_info.verify_signature().unwrap());
     }
+
+    #[test]
+    fn verify_all_signatures() {
+        let keypair = KeyPair::new();
+        let mut test_info = ContributionInfo::default();
+        test_info.public_key = keypair.pubkey().to_owned();
+        test_info.contribution_hash = String::from("contribution hash");
+        test_info.contribution_hash_signature = Production.sign(keypair.sigkey(), "contribution hash").unwrap();
+        test_info.contribution_file_hash = String::from("contribution file hash");
+        test_info.contribution_file_signature = Production.sign(keypair.sigkey(), "contribution file hash").unwrap();
+        test_info.try_sign(&keypair).unwrap();
+        assert!(test_info.verify_signatures().is_ok());
+
+        // Signature of the file hash produced by another key
+        let other_keypair = KeyPair::new();
+        test_info.contribution_file_signature = Production
+            .sign(other_keypair.sigkey(), "contribution file hash")
+            .unwrap();
+        test_info.try_sign(&keypair).unwrap();
+        assert!(matches!(
+            test_info.verify_signatures(),
+            Err(ContributionInfoError::InvalidSignature("contribution file hash"))
+        ));
+
+        // Badly encoded signature
+        test_info.contribution_hash_signature = String::from("Not a valid signature");
+        test_info.try_sign(&keypair).unwrap();
+        assert!(matches!(
+            test_info.verify_signatures(),
+            Err(ContributionInfoError::InvalidSignature("contribution hash"))
+        ));
+    }
 }
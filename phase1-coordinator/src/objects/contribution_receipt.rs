@@ -0,0 +1,116 @@
+use crate::authentication::{self, Production, Signature};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ContributionReceiptError {
+    #[error("Error while serializing ContributionReceipt: {0}")]
+    SerdeError(#[from] serde_json::Error),
+    #[error("Error while signing ContributionReceipt: {0}")]
+    SignatureError(String),
+}
+
+/// Receipt of a verified contribution, signed by the coordinator
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContributionReceipt {
+    // Round in which the contribution took place
+    pub round_height: u64,
+    // ed25519 public key of the contributor, hex encoded
+    pub contributor_public_key: String,
+    // Hash of the contribution, as found in the contributor info
+    pub contribution_hash: String,
+    // Hash of the next challenge, built on top of the contribution
+    pub next_challenge_hash: String,
+    // Time at which the receipt was issued
+    pub timestamp: DateTime<Utc>,
+    // ed25519 public key of the coordinator, hex encoded
+    pub coordinator_public_key: String,
+    // Signature of the coordinator, computed on the json string encoding of all the other fields of this struct
+    pub coordinator_signature: String,
+}
+
+impl ContributionReceipt {
+    /// Creates a new unsigned receipt issued at [`Utc::now`].
+    pub fn new(
+        round_height: u64,
+        contributor_public_key: String,
+        contribution_hash: String,
+        next_challenge_hash: String,
+        coordinator_public_key: String,
+    ) -> Self {
+        Self {
+            round_height,
+            contributor_public_key,
+            contribution_hash,
+            next_challenge_hash,
+            timestamp: Utc::now(),
+            coordinator_public_key,
+            coordinator_signature: String::default(),
+        }
+    }
+
+    /// Calculates the hash of the json string encoding all the fields of the struct
+    /// except for the signature itself.
+    fn hash_for_signature(&self) -> Result<String, ContributionReceiptError> {
+        Ok(authentication::hash_for_signature(self, "coordinator_signature")?)
+    }
+
+    /// Computes the signature of a json string encoding the struct with the coordinator signing key.
+    pub fn try_sign(&mut self, signing_key: &str) -> Result<(), ContributionReceiptError> {
+        let digest = self.hash_for_signature()?;
+
+        self.coordinator_signature = Production
+            .sign(signing_key, digest.as_str())
+            .map_err(|e| ContributionReceiptError::SignatureError(format!("{}", e)))?;
+
+        Ok(())
+    }
+
+    /// Verifies the signature of the coordinator. Returns `false` if the public key
+    /// or the signature are not correctly encoded.
+    pub fn verify_signature(&self) -> Result<bool, ContributionReceiptError> {
+        let digest = self.hash_for_signature()?;
+
+        Ok(Production.verify(
+            self.coordinator_public_key.as_str(),
+            digest.as_str(),
+            self.coordinator_signature.as_str(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::KeyPair;
+
+    use super::ContributionReceipt;
+
+    #[test]
+    fn sign_and_verify() {
+        let keypair = KeyPair::new();
+        let mut receipt = ContributionReceipt::new(
+            3,
+            KeyPair::new().pubkey().to_owned(),
+            String::from("Not a valid hash"),
+            String::from("Not a valid hash"),
+            keypair.pubkey().to_owned(),
+        );
+
+        // Unsigned receipt
+        assert!(!receipt.verify_signature().unwrap());
+
+        receipt.try_sign(keypair.sigkey()).unwrap();
+        assert!(receipt.verify_signature().unwrap());
+
+        // Tampered receipt
+        receipt.round_height = 4;
+        assert!(!receipt.verify_signature().unwrap());
+
+        // Wrong coordinator key
+        receipt.round_height = 3;
+        receipt.coordinator_public_key = KeyPair::new().pubkey().to_owned();
+        assert!(!receipt.verify_signature().unwrap());
+    }
+}
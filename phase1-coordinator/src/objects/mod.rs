@@ -10,6 +10,9 @@ pub use contribution_file_signature::*;
 pub mod contribution_info;
 pub use contribution_info::*;
 
+pub mod contribution_receipt;
+pub use contribution_receipt::*;
+
 pub mod participant;
 pub use participant::*;
 
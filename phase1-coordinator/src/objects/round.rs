@@ -618,13 +618,11 @@ impl Round {
         // Initialize the next contribution locator.
         match participant {
             Participant::Contributor(_) => {
-                // Initialize the unverified response file.
+                // Initialize the unverified response file, which is one contribution larger than its challenge.
+                let challenge_size = storage.size(&Locator::ContributionFile(locked_locators.current_contribution))?;
                 storage.initialize(
                     Locator::ContributionFile(locked_locators.next_contribution.clone()),
-                    Object::anoma_contribution_file_size(
-                        locked_locators.next_contribution.round_height(),
-                        locked_locators.next_contribution.contribution_id(),
-                    ),
+                    environment.circuit_set().response_file_size(challenge_size),
                 )?;
 
                 // Initialize the contribution file signature.
@@ -645,7 +643,6 @@ impl Round {
     /// Initialize the files for the next challenge
     pub fn initialize_verifier_response_files(
         &self,
-        _environment: &Environment,
         storage: &mut Disk,
         participant: &Participant,
         _chunk_id: u64,
@@ -654,13 +651,11 @@ impl Round {
         if !participant.is_verifier() {
             return Err(CoordinatorError::ExpectedVerifier);
         }
-        // Initialize the next challenge file.
+        // Initialize the next challenge file, which has the size of the response.
+        let response_size = storage.size(&Locator::ContributionFile(locators.current_contribution))?;
         storage.initialize(
             Locator::ContributionFile(locators.next_contribution.clone()),
-            Object::anoma_contribution_file_size(
-                locators.next_contribution.round_height(),
-                locators.next_contribution.contribution_id(),
-            ),
+            response_size,
         )?;
 
         // Initialize the contribution file signature.
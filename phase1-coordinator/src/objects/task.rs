@@ -129,7 +129,9 @@ pub enum TaskInitializationError {
 /// function if `number_of_contributors > 0`:
 ///
 /// + `starting_bucket_id < number_of_contributors`
-/// + `number_of_chunks >= number_of_contributors`
+/// + `number_of_chunks >= number_of_contributors`, unless
+///   `number_of_chunks == 1`: the participant then has a single task on
+///   chunk 0, whose contribution ID is `starting_bucket_id + 1`
 ///
 /// # Examples
 ///
@@ -189,6 +191,14 @@ pub fn initialize_tasks(
         });
     }
 
+    // Without chunking, the contributors contribute in sequence to the
+    // single chunk, in the order of their bucket
+    if number_of_chunks == 1 {
+        let mut tasks = LinkedList::new();
+        tasks.push_back(Task::new(0, starting_bucket_id + 1));
+        return Ok(tasks);
+    }
+
     // Check whether there are not enough chunks for the number of
     // contributors
     if number_of_chunks < number_of_contributors {
@@ -294,14 +304,34 @@ mod test {
         let number_of_chunks = 1;
         let number_of_contributors = 2;
 
+        let bucket_id = 0;
+        let mut tasks = initialize_tasks(bucket_id, number_of_chunks, number_of_contributors)
+            .unwrap()
+            .into_iter();
+        assert_eq!(Some(Task::new(0, 1)), tasks.next());
+        assert!(tasks.next().is_none());
+
+        let bucket_id = 1;
+        let mut tasks = initialize_tasks(bucket_id, number_of_chunks, number_of_contributors)
+            .unwrap()
+            .into_iter();
+        assert_eq!(Some(Task::new(0, 2)), tasks.next());
+        assert!(tasks.next().is_none());
+    }
+
+    #[test]
+    fn test_initialize_tasks_2_chunks_3_contributors() {
+        let number_of_chunks = 2;
+        let number_of_contributors = 3;
+
         let bucket_id = 0;
         match initialize_tasks(bucket_id, number_of_chunks, number_of_contributors).unwrap_err() {
             TaskInitializationError::NotEnoughChunks {
                 number_of_chunks,
                 number_of_contributors,
             } => {
-                assert_eq!(1, number_of_chunks);
-                assert_eq!(2, number_of_contributors);
+                assert_eq!(2, number_of_chunks);
+                assert_eq!(3, number_of_contributors);
             }
             _ => panic!("unexpected error"),
         }
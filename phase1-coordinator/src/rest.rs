@@ -2,8 +2,9 @@
 
 use crate::{
     authentication::{Production, Signature},
-    objects::{ContributionInfo, LockedLocators, Task},
-    s3::{S3Ctx, S3Error},
+    commands::CircuitSet,
+    objects::{ContributionFileSignature, ContributionInfo, ContributionReceipt, LockedLocators, Task},
+    s3::{self, S3Ctx, S3Error},
     storage::{ContributionLocator, ContributionSignatureLocator},
     CoordinatorError,
     Participant,
@@ -20,7 +21,12 @@ use rocket::{
     request::{FromRequest, Outcome, Request},
     response::{Responder, Response},
     serde::{json::Json, Deserialize, DeserializeOwned, Serialize},
-    tokio::{fs, sync::RwLock, task},
+    tokio::{
+        fs,
+        sync::{Notify, RwLock},
+        task,
+        time,
+    },
     Shutdown,
     State,
 };
@@ -32,13 +38,20 @@ use regex::Regex;
 use std::{borrow::Cow, convert::TryFrom, io::Cursor, net::IpAddr, ops::Deref, sync::Arc, time::Duration};
 use thiserror::Error;
 
-use tracing::warn;
+use tracing::{error, info, warn};
 
 #[cfg(debug_assertions)]
 pub const UPDATE_TIME: Duration = Duration::from_secs(5);
 #[cfg(not(debug_assertions))]
 pub const UPDATE_TIME: Duration = Duration::from_secs(60);
 
+/// Period of the sweep updating the coordinator and verifying the pending contributions, in case a deadline or a
+/// trigger was missed
+#[cfg(debug_assertions)]
+pub const SWEEP_TIME: Duration = Duration::from_secs(30);
+#[cfg(not(debug_assertions))]
+pub const SWEEP_TIME: Duration = Duration::from_secs(300);
+
 pub const UNKNOWN: &str = "Unknown";
 pub const TOKEN_REGEX: &str = r"^[[:xdigit:]]{20}$";
 
@@ -109,6 +122,7 @@ impl<'r> Responder<'r, 'static> for ResponseError {
 
         let response_code = match self {
             ResponseError::CeremonyIsOver => Status::Unauthorized,
+            ResponseError::InvalidContributionInfo(_) => Status::BadRequest,
             ResponseError::InvalidHeader(_) => Status::BadRequest,
             ResponseError::InvalidSignature => Status::BadRequest,
             ResponseError::InvalidToken(_) => Status::Unauthorized,
@@ -557,11 +571,31 @@ impl<'r, T: DeserializeOwned> FromData<'r> for LazyJson<T> {
 pub enum ContributorStatus {
     Queue(u64, u64),
     Round,
+    /// In the current round, waiting for the previous contribution to be verified before locking the chunk
+    Waiting,
     Finished,
     Banned,
     Other,
 }
 
+/// Response to a successful [`lock_chunk`] request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LockedChunk {
+    /// Locators of the files reserved for the contribution
+    pub locked_locators: LockedLocators,
+    /// Maximum number of seconds the contributor can hold the lock before being dropped from the ceremony
+    pub lock_timeout: i64,
+}
+
+/// Response to a successful [`get_speculative_challenge_url`] request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SpeculativeChallenge {
+    /// Round height of the contribution to compute on top of the challenge
+    pub round_height: u64,
+    /// Url of the challenge on Amazon S3
+    pub url: String,
+}
+
 /// Request to post a [Chunk](`crate::objects::Chunk`).
 #[derive(Clone, Deserialize, Serialize)]
 pub struct PostChunkRequest {
@@ -584,6 +618,36 @@ impl PostChunkRequest {
     }
 }
 
+/// Wakes up the background tasks of the coordinator as soon as a request changes the state of the ceremony, instead of
+/// waiting for their next sweep. A trigger fired while the task is busy is kept until the task waits again.
+#[derive(Default)]
+pub struct Triggers {
+    verification: Notify,
+    update: Notify,
+}
+
+impl Triggers {
+    /// Requests the verification of the pending contributions.
+    pub fn verify(&self) {
+        self.verification.notify_one();
+    }
+
+    /// Requests an update of the [Coordinator](`crate::Coordinator`).
+    pub fn update(&self) {
+        self.update.notify_one();
+    }
+
+    /// Waits for a verification request, at most for `timeout`.
+    pub async fn verification_requested(&self, timeout: Duration) {
+        let _ = time::timeout(timeout, self.verification.notified()).await;
+    }
+
+    /// Waits for an update request, at most for `timeout`.
+    pub async fn update_requested(&self, timeout: Duration) {
+        let _ = time::timeout(timeout, self.update.notified()).await;
+    }
+}
+
 /// Checks the validity of the token for the ceremony.
 async fn token_check(coordinator: Coordinator, token: &String) -> Result<()> {
     // Check if the token's format is correct
@@ -616,6 +680,7 @@ async fn token_check(coordinator: Coordinator, token: &String) -> Result<()> {
 #[post("/contributor/join_queue", format = "json", data = "<token>")]
 pub async fn join_queue(
     coordinator: &State<Coordinator>,
+    triggers: &State<Arc<Triggers>>,
     new_participant: NewParticipant,
     token: LazyJson<String>,
 ) -> Result<()> {
@@ -626,18 +691,34 @@ pub async fn join_queue(
     task::spawn_blocking(move || {
         write_lock.add_to_queue(new_participant.participant, new_participant.ip_address, 10)
     })
-    .await?.map_err(|e| ResponseError::CoordinatorError(e))
+    .await?.map_err(|e| ResponseError::CoordinatorError(e))?;
+
+    // The new participant may start the next round
+    triggers.update();
+
+    Ok(())
 }
 
 /// Lock a [Chunk](`crate::objects::Chunk`) in the ceremony. This should be the first function called when attempting to contribute to a chunk. Once the chunk is locked, it is ready to be downloaded.
 #[get("/contributor/lock_chunk", format = "json")]
 pub async fn lock_chunk(
     coordinator: &State<Coordinator>,
+    triggers: &State<Arc<Triggers>>,
     participant: CurrentContributor,
-) -> Result<Json<LockedLocators>> {
+) -> Result<Json<LockedChunk>> {
     let mut write_lock = (*coordinator).clone().write_owned().await;
+    let lock_timeout = write_lock.environment().participant_lock_timeout().whole_seconds();
+
     match task::spawn_blocking(move || write_lock.try_lock(&participant)).await? {
-        Ok((_, locked_locators)) => Ok(Json(locked_locators)),
+        Ok((_, locked_locators)) => {
+            // Reschedule the update task for the deadline of the new lock
+            triggers.update();
+
+            Ok(Json(LockedChunk {
+                locked_locators,
+                lock_timeout,
+            }))
+        }
         Err(e) => Err(ResponseError::CoordinatorError(e)),
     }
 }
@@ -646,39 +727,102 @@ pub async fn lock_chunk(
 #[post("/contributor/challenge", format = "json", data = "<round_height>")]
 pub async fn get_challenge_url(
     coordinator: &State<Coordinator>,
-    _participant: CurrentContributor,
+    participant: CurrentContributor,
     round_height: LazyJson<u64>,
 ) -> Result<Json<String>> {
+    // Since we don't chunk the parameters, the contributors of a round contribute in sequence to chunk 0, each on top of
+    // the verified response of the previous one. For example, the 1st challenge (after the initialization) is located
+    // at round_1/chunk_0/contribution_0.verified
+    let pending_task = coordinator
+        .read()
+        .await
+        .pending_task(&participant, 0)
+        .map_err(|e| ResponseError::CoordinatorError(e))?;
+    let challenge_id = pending_task.contribution_id() - 1;
     let s3_ctx = S3Ctx::new().await?;
-    let key = format!("round_{}/chunk_0/contribution_0.verified", *round_height);
+    let key = s3::contribution_key(*round_height, challenge_id, true);
+
+    // If the initial challenge of the round is already on S3 (round rollback) immediately return the key. The following
+    // challenges must be uploaded again since a rollback replaces them with the ones of the new contributors
+    if challenge_id == 0 {
+        if let Some(url) = s3_ctx.get_challenge_url(key.clone()).await {
+            return Ok(Json(url));
+        }
+    }
+
+    let read_lock = (*coordinator).clone().read_owned().await;
+    let challenge =
+        match task::spawn_blocking(move || read_lock.get_challenge(*round_height, 0, challenge_id, true)).await? {
+            Ok(challenge) => challenge,
+            Err(e) => return Err(ResponseError::CoordinatorError(e)),
+        };
+
+    // Upload challenge to S3 and return url
+    let url = s3_ctx.upload_challenge(key, challenge).await?;
+
+    Ok(Json(url))
+}
 
-    // If challenge is already on S3 (round rollback) immediately return the key
+/// Key of the speculative challenge built on top of the response with the given hash, uploaded next to it. The hash
+/// tells apart the responses of a contribution id reused after an invalid contribution has been dropped.
+fn speculative_challenge_key(response_locator: &ContributionLocator, response_hash: &str) -> String {
+    format!(
+        "{}.{}.speculative",
+        s3::contribution_key(
+            response_locator.round_height(),
+            response_locator.contribution_id(),
+            false
+        ),
+        response_hash
+    )
+}
+
+/// Get the url of the challenge the contributor will compute on, built on top of the latest contribution before its
+/// verification completes. This lets the contributor start computing while waiting for the chunk lock.
+#[get("/contributor/speculative_challenge", format = "json")]
+pub async fn get_speculative_challenge_url(
+    coordinator: &State<Coordinator>,
+    participant: Participant,
+) -> Result<Json<SpeculativeChallenge>> {
+    let read_lock = (*coordinator).clone().read_owned().await;
+    let (round_height, response_locator, response_hash) =
+        match task::spawn_blocking(move || read_lock.speculative_challenge(&participant)).await? {
+            Ok(speculative_challenge) => speculative_challenge,
+            Err(e) => return Err(ResponseError::CoordinatorError(e)),
+        };
+
+    // The challenge is built and uploaded once, on the first request
+    let key = speculative_challenge_key(&response_locator, &response_hash);
+    let s3_ctx = S3Ctx::new().await?;
     if let Some(url) = s3_ctx.get_challenge_url(key.clone()).await {
-        return Ok(Json(url));
+        return Ok(Json(SpeculativeChallenge { round_height, url }));
     }
 
-    // Since we don't chunk the parameters, we have one chunk and one allowed contributor per round. Thus the challenge will always be located at round_{i}/chunk_0/contribution_0.verified
-    // For example, the 1st challenge (after the initialization) is located at round_1/chunk_0/contribution_0.verified
     let read_lock = (*coordinator).clone().read_owned().await;
-    let challenge = match task::spawn_blocking(move || read_lock.get_challenge(*round_height, 0, 0, true)).await? {
+    let challenge = match task::spawn_blocking(move || read_lock.speculative_challenge_bytes(&response_locator)).await?
+    {
         Ok(challenge) => challenge,
         Err(e) => return Err(ResponseError::CoordinatorError(e)),
     };
-
-    // Upload challenge to S3 and return url
     let url = s3_ctx.upload_challenge(key, challenge).await?;
 
-    Ok(Json(url))
+    Ok(Json(SpeculativeChallenge { round_height, url }))
 }
 
 /// Request the urls where to upload a [Chunk](`crate::objects::Chunk`) contribution and the ContributionFileSignature.
 #[post("/upload/chunk", format = "json", data = "<round_height>")]
 pub async fn get_contribution_url(
-    _participant: CurrentContributor,
+    coordinator: &State<Coordinator>,
+    participant: CurrentContributor,
     round_height: LazyJson<u64>,
 ) -> Result<Json<(String, String)>> {
-    let contrib_key = format!("round_{}/chunk_0/contribution_1.unverified", *round_height);
-    let contrib_sig_key = format!("round_{}/chunk_0/contribution_1.unverified.signature", *round_height);
+    let pending_task = coordinator
+        .read()
+        .await
+        .pending_task(&participant, 0)
+        .map_err(|e| ResponseError::CoordinatorError(e))?;
+    let contrib_key = s3::contribution_key(*round_height, pending_task.contribution_id(), false);
+    let contrib_sig_key = format!("{}.signature", contrib_key);
 
     // Prepare urls for the upload
     let s3_ctx = S3Ctx::new().await?;
@@ -687,7 +831,7 @@ pub async fn get_contribution_url(
     Ok(Json(urls))
 }
 
-/// Notify the [Coordinator](`crate::Coordinator`) of a finished and uploaded [Contribution](`crate::objects::Contribution`). This will unlock the given [Chunk](`crate::objects::Chunk`).
+/// Notify the [Coordinator](`crate::Coordinator`) of a finished and uploaded [Contribution](`crate::objects::Contribution`). This will unlock the given [Chunk](`crate::objects::Chunk`) and trigger the verification of the contribution.
 #[post(
     "/contributor/contribute_chunk",
     format = "json",
@@ -695,24 +839,46 @@ pub async fn get_contribution_url(
 )]
 pub async fn contribute_chunk(
     coordinator: &State<Coordinator>,
+    triggers: &State<Arc<Triggers>>,
     participant: CurrentContributor,
     contribute_chunk_request: LazyJson<PostChunkRequest>,
 ) -> Result<()> {
     // Download contribution and its signature from S3 to local disk from the provided Urls
     let s3_ctx = S3Ctx::new().await?;
-    let (contribution, contribution_sig) = s3_ctx.get_contribution(contribute_chunk_request.round_height).await?;
+    let round_height = contribute_chunk_request.round_height;
+    let contribution_id = contribute_chunk_request.contribution_locator.contribution_id();
+    let pending_task = coordinator
+        .read()
+        .await
+        .pending_task(&participant, 0)
+        .map_err(|e| ResponseError::CoordinatorError(e))?;
+    if pending_task.contribution_id() != contribution_id {
+        return Err(ResponseError::CoordinatorError(
+            CoordinatorError::ContributionIdMismatch,
+        ));
+    }
+    let (contribution, contribution_sig) = s3_ctx.get_contribution(round_height, contribution_id).await?;
     let mut write_lock = (*coordinator).clone().write_owned().await;
 
     task::spawn_blocking(move || {
         write_lock.write_contribution(contribute_chunk_request.contribution_locator, contribution)?;
+        let contribution_file_signature: ContributionFileSignature = serde_json::from_slice(&contribution_sig)?;
+        let response_hash = contribution_file_signature.get_response_hash().to_owned();
         write_lock.write_contribution_file_signature(
             contribute_chunk_request.contribution_signature_locator,
-            serde_json::from_slice(&contribution_sig)?,
+            contribution_file_signature,
         )?;
-        write_lock.try_contribute(&participant, 0) // Only 1 chunk per round, chunk_id is always 0
+        write_lock.try_contribute(&participant, 0)?; // Only 1 chunk per round, chunk_id is always 0
+
+        // The response hash has been checked against the contribution file
+        write_lock.write_pending_contribution_info(&participant, contribution_id, &response_hash)
     })
     .await?
-    .map_or_else(|e| Err(ResponseError::CoordinatorError(e)), |_| Ok(()))
+    .map_err(|e| ResponseError::CoordinatorError(e))?;
+
+    triggers.verify();
+
+    Ok(())
 }
 
 /// Performs the update of the [Coordinator](`crate::Coordinator`)
@@ -762,43 +928,67 @@ pub async fn perform_verify_chunks(coordinator: Coordinator) -> Result<()> {
         let mut write_lock = coordinator.clone().write_owned().await;
         // NOTE: we are going to rely on the single default verifier built in the coordinator itself,
         //  no external verifiers
-        let verify_response = match task::spawn_blocking(move || write_lock.default_verify(&task)).await {
-            Ok(inner) => inner.map_err(|e| e.to_string()),
-            Err(e) => Err(e.to_string()),
-        };
-
-        if let Err(e) = verify_response {
-            warn!("Error while verifying a contribution: {}. Restarting the round...", e);
-            // FIXME: the verify_masp function may panic but the program doesn't shut down because we are executing it on a separate thread. It would be better though to make that function return a Result instead of panicking. Revert of round should be moved inside default_verify
-
-            // Get the participant who produced the contribution
-            let mut write_lock = coordinator.clone().write_owned().await;
-            return task::spawn_blocking(move || {
-                let finished_contributor = write_lock
-                    .state()
-                    .current_round_finished_contributors()
-                    .unwrap()
-                    .first()
-                    .unwrap()
-                    .clone();
-
-                // Reset the round to prevent a coordinator stall (the corrupted contribution is not automatically dropped)
-                write_lock
-                    .reset_round()
-                    .map_err(|e| ResponseError::CoordinatorError(e))?;
-
-                // Ban the participant who produced the invalid contribution. Must be banned after the reset beacuse one can't ban a finished contributor
-                write_lock
-                    .ban_participant(&finished_contributor)
-                    .map_err(|e| ResponseError::CoordinatorError(e))
-            })
-            .await?;
+        match task::spawn_blocking(move || write_lock.default_verify(&task)).await? {
+            Ok(()) => continue,
+            Err(CoordinatorError::VerificationFailed) => warn!("Invalid contribution. Dropping it..."),
+            Err(e) => {
+                // Internal fault of the coordinator: the contribution stays pending and its verification will be
+                // attempted again, the contributor is not held responsible
+                error!("Error while verifying a contribution: {}", e);
+                return Err(ResponseError::CoordinatorError(e));
+            }
         }
+
+        // Drop the invalid contribution and ban the participant who produced it, to prevent a coordinator stall. The
+        // verified contributions of the round are kept: the next contributor gets the last verified one as challenge
+        // and discards the computation it speculatively built on top of the invalid one
+        let mut write_lock = coordinator.clone().write_owned().await;
+        return task::spawn_blocking(move || {
+            write_lock
+                .drop_invalid_contribution(&task)
+                .map_err(|e| ResponseError::CoordinatorError(e))
+        })
+        .await?;
     }
 
     Ok(())
 }
 
+/// Verifies the pending contributions as soon as they are uploaded. A periodic sweep catches any missed contribution
+pub async fn verify_contributions(coordinator: Coordinator, triggers: Arc<Triggers>) -> Result<()> {
+    let mut timeout = SWEEP_TIME;
+
+    loop {
+        triggers.verification_requested(timeout).await;
+
+        if coordinator.read().await.get_pending_verifications().is_empty() {
+            timeout = SWEEP_TIME;
+            continue;
+        }
+
+        info!("Verifying contributions...");
+        let start = std::time::Instant::now();
+        match perform_verify_chunks(coordinator.clone()).await {
+            Ok(_) => {
+                info!("Verification of contributions completed in {:#?}", start.elapsed());
+                timeout = SWEEP_TIME;
+            }
+            // The contributors are banned only for invalid contributions, the verification of a contribution which
+            // failed because of an internal fault is retried shortly after
+            Err(e) => {
+                error!(
+                    "Verification of contributions failed: {}. {:#?} to the next verification round...",
+                    e, UPDATE_TIME
+                );
+                timeout = UPDATE_TIME;
+            }
+        }
+
+        // The verification may have completed the round or reset it
+        triggers.update();
+    }
+}
+
 /// Verify all the pending contributions. This endpoint is accessible only by the coordinator itself.
 #[cfg(debug_assertions)]
 #[get("/verify")]
@@ -815,12 +1005,21 @@ pub async fn get_contributor_queue_status(
     let contributor = participant.clone();
 
     let read_lock = (*coordinator).clone().read_owned().await;
-    // Check that the contributor is authorized to lock a chunk in the current round.
-    if task::spawn_blocking(move || read_lock.is_current_contributor(&contributor))
-        .await
-        .unwrap()
-    {
-        return Json(ContributorStatus::Round);
+    // Check that the contributor is authorized to lock a chunk in the current round. The next contributors of the
+    // round wait for the verification of the contribution preceding theirs
+    let round_status = task::spawn_blocking(move || {
+        if !read_lock.is_current_contributor(&contributor) {
+            None
+        } else if read_lock.is_waiting_previous_contribution(&contributor) {
+            Some(ContributorStatus::Waiting)
+        } else {
+            Some(ContributorStatus::Round)
+        }
+    })
+    .await
+    .unwrap();
+    if let Some(status) = round_status {
+        return Json(status);
     }
 
     let read_lock = coordinator.read().await;
@@ -878,16 +1077,59 @@ pub async fn post_contribution_info(
         )));
     }
 
-    // Write contribution info and summary to file
+    request
+        .verify_signatures()
+        .map_err(|e| ResponseError::InvalidContributionInfo(e.to_string()))?;
+
+    // Check the file hash against the signature uploaded to S3 with the contribution. The signed response hash is
+    // checked against the actual contribution file when the chunk is contributed
+    let contribution_id = coordinator
+        .read()
+        .await
+        .pending_task(&participant, 0)
+        .map_err(|e| ResponseError::CoordinatorError(e))?
+        .contribution_id();
+    let s3_ctx = S3Ctx::new().await?;
+    let signature = s3_ctx
+        .get_contribution_signature(request.ceremony_round, contribution_id)
+        .await?;
+    let signature: ContributionFileSignature =
+        serde_json::from_slice(&signature).map_err(|e| ResponseError::SerdeError(e.to_string()))?;
+    let state = serde_json::to_string(signature.get_state()).map_err(|e| ResponseError::SerdeError(e.to_string()))?;
+    if !Production.verify(&participant.address(), &state, signature.get_signature()) {
+        return Err(ResponseError::InvalidContributionInfo(
+            "Signature of the uploaded contribution file is invalid".to_string(),
+        ));
+    }
+    if signature.get_response_hash() != request.contribution_file_hash {
+        return Err(ResponseError::InvalidContributionInfo(format!(
+            "Contribution file hash in info {} doesn't match the hash of the uploaded file {}",
+            request.contribution_file_hash,
+            signature.get_response_hash()
+        )));
+    }
+
+    // The contribution info and summary are written to file once contribute_chunk has checked the contribution against
+    // its signature
     let mut write_lock = (*coordinator).clone().write_owned().await;
 
-    task::spawn_blocking(move || {
-        write_lock.write_contribution_info(request.clone())?;
+    task::spawn_blocking(move || write_lock.set_pending_contribution_info(&participant, request.0))
+        .await?
+        .map_err(|e| ResponseError::CoordinatorError(e))
+}
 
-        write_lock.update_contribution_summary(request.0.into())
-    })
-    .await?
-    .map_err(|e| ResponseError::CoordinatorError(e))
+/// Get the receipt of the contribution of the participant in the given round, signed by the [Coordinator](`crate::Coordinator`). The receipt is available only after the contribution has been verified.
+#[post("/contributor/receipt", format = "json", data = "<round_height>")]
+pub async fn get_contribution_receipt(
+    coordinator: &State<Coordinator>,
+    participant: Participant,
+    round_height: LazyJson<u64>,
+) -> Result<Json<ContributionReceipt>> {
+    let read_lock = (*coordinator).clone().read_owned().await;
+
+    task::spawn_blocking(move || read_lock.contribution_receipt(&participant, *round_height))
+        .await?
+        .map_or_else(|e| Err(ResponseError::CoordinatorError(e)), |receipt| Ok(Json(receipt)))
 }
 
 /// Retrieve the contributions' info. This endpoint is accessible by anyone and does not require a signed request.
@@ -901,6 +1143,12 @@ pub async fn get_contributions_info(coordinator: &State<Coordinator>) -> Result<
     Ok(summary)
 }
 
+/// Retrieve the set of circuits of the ceremony. This endpoint is accessible by anyone and does not require a signed request.
+#[get("/circuit_set")]
+pub async fn get_circuit_set(coordinator: &State<Coordinator>) -> Json<CircuitSet> {
+    Json(coordinator.read().await.environment().circuit_set())
+}
+
 /// Retrieve healthcheck info. This endpoint is accessible by anyone and does not require a signed request.
 #[get("/healthcheck", format = "json")]
 pub async fn get_healthcheck() -> Result<String> {
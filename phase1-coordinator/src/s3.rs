@@ -50,6 +50,16 @@ pub enum S3Error {
 
 type Result<T> = std::result::Result<T, S3Error>;
 
+/// Returns the key of a contribution file on S3, which mirrors its path in the coordinator storage.
+pub(crate) fn contribution_key(round_height: u64, contribution_id: u64, is_verified: bool) -> String {
+    let extension = if is_verified { "verified" } else { "unverified" };
+
+    format!(
+        "round_{}/chunk_0/contribution_{}.{}",
+        round_height, contribution_id, extension
+    )
+}
+
 pub struct S3Ctx {
     client: S3Client,
     bucket: &'static String,
@@ -157,21 +167,33 @@ impl S3Ctx {
     }
 
     /// Retrieve a contribution and its signature from S3.
-    pub(crate) async fn get_contribution(&self, round_height: u64) -> Result<(Vec<u8>, Vec<u8>)> {
+    pub(crate) async fn get_contribution(&self, round_height: u64, contribution_id: u64) -> Result<(Vec<u8>, Vec<u8>)> {
+        let key = contribution_key(round_height, contribution_id, false);
         let get_contrib = GetObjectRequest {
             bucket: self.bucket.clone(),
-            key: format!("round_{}/chunk_0/contribution_1.unverified", round_height),
+            key: key.clone(),
             ..Default::default()
         };
         let get_sig = GetObjectRequest {
             bucket: self.bucket.clone(),
-            key: format!("round_{}/chunk_0/contribution_1.unverified.signature", round_height),
+            key: format!("{}.signature", key),
             ..Default::default()
         };
 
         rocket::tokio::try_join!(self.get_object(get_contrib), self.get_object(get_sig))
     }
 
+    /// Retrieve the signature of a contribution from S3, without the contribution itself.
+    pub(crate) async fn get_contribution_signature(&self, round_height: u64, contribution_id: u64) -> Result<Vec<u8>> {
+        let get_sig = GetObjectRequest {
+            bucket: self.bucket.clone(),
+            key: format!("{}.signature", contribution_key(round_height, contribution_id, false)),
+            ..Default::default()
+        };
+
+        self.get_object(get_sig).await
+    }
+
     /// Retrieve the compressed token folder.
     pub async fn get_tokens(&self) -> Result<Vec<u8>> {
         let key = match std::env::var("AWS_S3_PROD") {
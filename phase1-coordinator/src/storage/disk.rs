@@ -185,11 +185,10 @@ impl Disk {
                 Ok(Object::RoundFile(file_bytes))
             }
             Locator::ContributionFile(contribution_locator) => {
-                // Check that the contribution size is correct.
-                let expected_size = Object::anoma_contribution_file_size(
-                    contribution_locator.round_height(),
-                    contribution_locator.contribution_id(),
-                );
+                // The size of a contribution depends on the number of contributions of the previous rounds, so only
+                // check that it's not smaller than the initial challenge. The content is validated through the
+                // contribution header during the verification.
+                let minimum_size = self.environment.circuit_set().base_file_size();
                 let found_size = file_bytes.len() as u64;
                 debug!(
                     "Round {} chunk {} filesize is {}",
@@ -197,17 +196,15 @@ impl Disk {
                     contribution_locator.chunk_id(),
                     found_size
                 );
-                if found_size == 0 || expected_size != found_size {
+                if found_size < minimum_size {
                     error!(
-                        "Contribution file size should be {} but found {}",
-                        expected_size, found_size
+                        "Contribution file size should be at least {} but found {}",
+                        minimum_size, found_size
                     );
                     return Err(CoordinatorError::ContributionFileSizeMismatch.into());
                 }
 
-                let mut contribution_file: Vec<u8> = Vec::with_capacity(expected_size as usize);
-                contribution_file.write_all(&file_bytes)?;
-                Ok(Object::ContributionFile(contribution_file))
+                Ok(Object::ContributionFile(file_bytes))
             }
             Locator::ContributionFileSignature(contribution_locator) => {
                 // Check that the contribution file signature size is correct.
@@ -231,7 +228,7 @@ impl Disk {
                 let contribution_file_signature: ContributionFileSignature = serde_json::from_slice(&file_bytes)?;
                 Ok(Object::ContributionFileSignature(contribution_file_signature))
             }
-            Locator::ContributionInfoFile { round_height: _ } => {
+            Locator::ContributionInfoFile { .. } => {
                 let contribution_info: ContributionInfo = serde_json::from_slice(&file_bytes)?;
                 Ok(Object::ContributionInfoFile(contribution_info))
             }
@@ -384,19 +381,73 @@ impl Disk {
         self.clear_info_files(round_height);
     }
 
-    /// Delete contribution file and trim contribution summary file if these files exist
+    /// Delete contribution files and trim contribution summary file if these files exist
     pub fn clear_info_files(&mut self, round_height: u64) {
-        if let Err(e) = self.remove(&Locator::ContributionInfoFile { round_height }) {
-            tracing::warn!("Could not delete contribution file: {}", e);
+        // Contribution 0 is the verified challenge of the round, the contributors start from 1
+        let maximum_contributors = self.environment.maximum_contributors_per_round() as u64;
+        for contribution_id in 1..=maximum_contributors {
+            let locator = Locator::ContributionInfoFile {
+                round_height,
+                contribution_id,
+            };
+            if !self.exists(&locator) {
+                continue;
+            }
+
+            if let Err(e) = self.remove(&locator) {
+                tracing::warn!("Could not delete contribution file: {}", e);
+            }
         }
 
         match self.get(&Locator::ContributionsInfoSummary) {
             Ok(o) => {
                 if let Object::ContributionsInfoSummary(mut s) = o {
                     // NOTE: the vec is ordered for ascending round heights
-                    if let Some(contrib) = s.last() {
-                        if contrib.ceremony_round() == round_height {
-                            s.pop();
+                    let length = s.len();
+                    while s
+                        .last()
+                        .map_or(false, |contrib| contrib.ceremony_round() == round_height)
+                    {
+                        s.pop();
+                    }
+
+                    if s.len() != length {
+                        if let Err(e) =
+                            self.update(&Locator::ContributionsInfoSummary, Object::ContributionsInfoSummary(s))
+                        {
+                            tracing::warn!("Could not trim contribution summary file: {}", e);
+                        }
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Could not retrieve contribution summary file: {}", e),
+        }
+    }
+
+    /// Delete the contribution file of the given contribution and remove it from the contribution summary if these
+    /// files exist
+    pub fn clear_contribution_info_file(&mut self, round_height: u64, contribution_id: u64, public_key: &str) {
+        let locator = Locator::ContributionInfoFile {
+            round_height,
+            contribution_id,
+        };
+        if self.exists(&locator) {
+            if let Err(e) = self.remove(&locator) {
+                tracing::warn!("Could not delete contribution file: {}", e);
+            }
+        }
+
+        match self.get(&Locator::ContributionsInfoSummary) {
+            Ok(o) => {
+                if let Object::ContributionsInfoSummary(mut s) = o {
+                    let length = s.len();
+                    s.retain(|contrib| contrib.ceremony_round() != round_height || contrib.public_key() != public_key);
+
+                    if s.len() != length {
+                        if let Err(e) =
+                            self.update(&Locator::ContributionsInfoSummary, Object::ContributionsInfoSummary(s))
+                        {
+                            tracing::warn!("Could not trim contribution summary file: {}", e);
                         }
                     }
                 }
@@ -557,11 +608,8 @@ impl StorageObject for Disk {
                 }
             }
             Locator::ContributionFile(contribution_locator) => {
-                // Check that the contribution size is correct.
-                let expected_size = Object::anoma_contribution_file_size(
-                    contribution_locator.round_height(),
-                    contribution_locator.contribution_id(),
-                );
+                // Check that the contribution is not smaller than the initial challenge, as in `get`.
+                let minimum_size = self.environment.circuit_set().base_file_size();
                 let found_size = data.len() as u64;
                 debug!(
                     "Round {} chunk {} filesize is {}",
@@ -569,10 +617,10 @@ impl StorageObject for Disk {
                     contribution_locator.chunk_id(),
                     found_size
                 );
-                if found_size != expected_size {
+                if found_size < minimum_size {
                     error!(
-                        "Contribution file size should be {} but found {}",
-                        expected_size, found_size
+                        "Contribution file size should be at least {} but found {}",
+                        minimum_size, found_size
                     );
                     return Err(CoordinatorError::ContributionFileSizeMismatch.into());
                 }
@@ -612,18 +660,15 @@ impl StorageObject for Disk {
                     return Err(CoordinatorError::RoundFileSizeMismatch.into());
                 }
             }
-            Locator::ContributionFile(contribution_locator) => {
-                // Check that the contribution size is correct.
-                let expected_size = Object::anoma_contribution_file_size(
-                    contribution_locator.round_height(),
-                    contribution_locator.contribution_id(),
-                );
+            Locator::ContributionFile(_) => {
+                // The contribution files are sized on initialization, from the size of the file they're built on.
+                let minimum_size = self.environment.circuit_set().base_file_size();
                 let found_size = memmap.len() as u64;
                 debug!("File size of {} is {}", self.to_path(locator)?, found_size);
-                if found_size != expected_size {
+                if found_size < minimum_size {
                     error!(
-                        "Contribution file size should be {} but found {}",
-                        expected_size, found_size
+                        "Contribution file size should be at least {} but found {}",
+                        minimum_size, found_size
                     );
                     return Err(CoordinatorError::ContributionFileSizeMismatch.into());
                 }
@@ -697,9 +742,12 @@ impl StorageLocator for DiskResolver {
                     ),
                 }
             }
-            Locator::ContributionInfoFile { round_height } => format!(
-                "{}/contributors/namada_contributor_info_round_{}.json",
-                self.base, round_height
+            Locator::ContributionInfoFile {
+                round_height,
+                contribution_id,
+            } => format!(
+                "{}/contributors/namada_contributor_info_round_{}_contribution_{}.json",
+                self.base, round_height, contribution_id
             ),
             Locator::ContributionsInfoSummary => format!("{}/contributors.json", self.base),
         };
@@ -5,27 +5,33 @@
 //  NOTE: these tests require the phase1radix files to be placed in the phase1-coordinator folder
 
 use std::{
+    future::Future,
     io::Write,
     net::{IpAddr, SocketAddr},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use blake2::Digest;
 use phase1_coordinator::{
     authentication::{KeyPair, Production, Signature},
-    commands::{Computation, RandomSource},
+    commands::{CircuitSet, Computation, RandomSource},
     environment::Testing,
-    objects::{ContributionInfo, LockedLocators, TrimmedContributionInfo},
+    objects::{ContributionInfo, ContributionReceipt, LockedLocators, TrimmedContributionInfo},
     rest::{
         self,
         ContributorStatus,
+        LockedChunk,
         PostChunkRequest,
+        SpeculativeChallenge,
+        Triggers,
         BODY_DIGEST_HEADER,
         CONTENT_LENGTH_HEADER,
         PUBKEY_HEADER,
         SIGNATURE_HEADER,
+        SWEEP_TIME,
     },
-    storage::{ContributionLocator, ContributionSignatureLocator, Object},
+    storage::{ContributionLocator, ContributionSignatureLocator},
     testing::coordinator,
     ContributionFileSignature,
     ContributionState,
@@ -38,7 +44,7 @@ use rocket::{
     http::{ContentType, Header, Status},
     local::blocking::{Client, LocalRequest},
     routes,
-    tokio::sync::RwLock,
+    tokio::{runtime::Runtime, sync::RwLock},
     Build,
     Rocket,
 };
@@ -63,8 +69,18 @@ struct TestCtx {
 
 /// Build the rocket server for testing with the proper configuration.
 fn build_context() -> TestCtx {
+    build_context_with_contributors(1)
+}
+
+/// Build the rocket server for testing, with the given number of contributors in the first round. The first
+/// contributor holds the lock on the chunk.
+fn build_context_with_contributors(contributors_per_round: usize) -> TestCtx {
     // Reset storage to prevent state conflicts between tests and initialize test environment
-    let environment = coordinator::initialize_test_environment(&Testing::default().into());
+    let environment = coordinator::initialize_test_environment(
+        &Testing::default()
+            .maximum_contributors_per_round(contributors_per_round)
+            .into(),
+    );
 
     // Create token file
     let tmp_dir = tempfile::tempdir().unwrap();
@@ -82,15 +98,18 @@ fn build_context() -> TestCtx {
     let keypair1 = KeyPair::new();
     let keypair2 = KeyPair::new();
     let keypair3 = KeyPair::new();
+    let keypair4 = KeyPair::new();
 
     let contributor1 = Participant::new_contributor(keypair1.pubkey());
     let contributor2 = Participant::new_contributor(keypair2.pubkey());
     let unknown_contributor = Participant::new_contributor(keypair3.pubkey());
+    let contributor3 = Participant::new_contributor(keypair4.pubkey());
 
     let coordinator_ip = IpAddr::V4("0.0.0.0".parse().unwrap());
     let contributor1_ip = IpAddr::V4("0.0.0.1".parse().unwrap());
     let contributor2_ip = IpAddr::V4("0.0.0.2".parse().unwrap());
     let unknown_contributor_ip = IpAddr::V4("0.0.0.3".parse().unwrap());
+    let contributor3_ip = IpAddr::V4("0.0.0.4".parse().unwrap());
 
     coordinator.initialize().unwrap();
     let coordinator_keypair = KeyPair::custom_new(
@@ -108,6 +127,16 @@ fn build_context() -> TestCtx {
     coordinator
         .add_to_queue(contributor1.clone(), Some(contributor1_ip.clone()), 10)
         .unwrap();
+    if contributors_per_round > 1 {
+        coordinator
+            .add_to_queue(contributor2.clone(), Some(contributor2_ip.clone()), 10)
+            .unwrap();
+    }
+    if contributors_per_round > 2 {
+        coordinator
+            .add_to_queue(contributor3.clone(), Some(contributor3_ip.clone()), 10)
+            .unwrap();
+    }
     coordinator.update().unwrap();
 
     let (_, locked_locators) = coordinator.try_lock(&contributor1).unwrap();
@@ -126,11 +155,15 @@ fn build_context() -> TestCtx {
             rest::get_contributor_queue_status,
             rest::post_contribution_info,
             rest::get_contributions_info,
+            rest::get_circuit_set,
             rest::get_healthcheck,
             rest::get_contribution_url,
-            rest::get_challenge_url
+            rest::get_challenge_url,
+            rest::get_speculative_challenge_url,
+            rest::get_contribution_receipt
         ])
         .manage(coordinator)
+        .manage(Arc::new(Triggers::default()))
         .register("/", catchers![
             rest::invalid_signature,
             rest::unauthorized,
@@ -154,6 +187,12 @@ fn build_context() -> TestCtx {
         keypair: keypair2,
         locked_locators: None,
     };
+    let test_participant3 = TestParticipant {
+        _inner: contributor3,
+        address: contributor3_ip,
+        keypair: keypair4,
+        locked_locators: None,
+    };
     let unknown_participant = TestParticipant {
         _inner: unknown_contributor,
         address: unknown_contributor_ip,
@@ -163,7 +202,7 @@ fn build_context() -> TestCtx {
 
     TestCtx {
         rocket,
-        contributors: vec![test_participant1, test_participant2],
+        contributors: vec![test_participant1, test_participant2, test_participant3],
         unknown_participant,
         coordinator: coord_verifier,
     }
@@ -246,6 +285,19 @@ fn test_get_healthcheck() {
     }
 }
 
+#[test]
+fn test_get_circuit_set() {
+    let ctx = build_context();
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    let req = client.get("/circuit_set");
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let circuit_set: CircuitSet = response.into_json().unwrap();
+    assert_eq!(circuit_set, CircuitSet::Test);
+}
+
 #[test]
 fn test_get_contributor_queue_status() {
     let ctx = build_context();
@@ -585,15 +637,12 @@ fn test_contribution() {
     let mut contribution: Vec<u8> = Vec::new();
     contribution.write_all(challenge_hash.as_slice()).unwrap();
     let entropy = RandomSource::Entropy(String::from("entropy"));
-    Computation::contribute_test_masp(&challenge, &mut contribution, &entropy);
-
-    // Initial contribution size is 2332 but the Coordinator expect ANOMA_BASE_FILE_SIZE. Extend to this size with trailing 0s
-    let contrib_size = Object::anoma_contribution_file_size(ROUND_HEIGHT, 1);
-    contribution.resize(contrib_size as usize, 0);
+    Computation::contribute_circuits(CircuitSet::Test, &challenge, &mut contribution, &entropy).unwrap();
 
     let contribution_file_signature_locator = ContributionSignatureLocator::new(ROUND_HEIGHT, 0, 1, false);
 
     let response_hash = calculate_hash(contribution.as_ref());
+    let contribution_hash = calculate_hash(&contribution[64..]);
 
     let contribution_state = ContributionState::new(challenge_hash.to_vec(), response_hash.to_vec(), None).unwrap();
 
@@ -626,14 +675,43 @@ fn test_contribution() {
         .unwrap()
         .current_contribution()
         .round_height();
-    contrib_info.try_sign(&ctx.contributors[0].keypair).unwrap();
+    contrib_info.contribution_hash = hex::encode(contribution_hash);
+    contrib_info.contribution_hash_signature = Production.sign(sigkey, &contrib_info.contribution_hash).unwrap();
+    contrib_info.contribution_file_hash = hex::encode(response_hash);
+    contrib_info.contribution_file_signature = Production.sign(sigkey, &contrib_info.contribution_file_hash).unwrap();
+
+    // Wrong, file hash not matching the uploaded contribution
+    let mut wrong_info = contrib_info.clone();
+    wrong_info.contribution_file_hash = hex::encode(challenge_hash);
+    wrong_info.contribution_file_signature = Production.sign(sigkey, &wrong_info.contribution_file_hash).unwrap();
+    wrong_info.try_sign(&ctx.contributors[0].keypair).unwrap();
+    req = client.post("/contributor/contribution_info");
+    req = set_request::<ContributionInfo>(req, &ctx.contributors[0].keypair, Some(&wrong_info));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert!(response.body().is_some());
 
+    // Wrong, unsigned contribution info
+    req = client.post("/contributor/contribution_info");
+    req = set_request::<ContributionInfo>(req, &ctx.contributors[0].keypair, Some(&contrib_info));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert!(response.body().is_some());
+
+    contrib_info.try_sign(&ctx.contributors[0].keypair).unwrap();
     req = client.post("/contributor/contribution_info");
     req = set_request::<ContributionInfo>(req, &ctx.contributors[0].keypair, Some(&contrib_info));
     let response = req.dispatch();
     assert_eq!(response.status(), Status::Ok);
     assert!(response.body().is_none());
 
+    // The contribution info is not published before the contribution is accepted
+    req = client.get("/contribution_info");
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let summary: Vec<TrimmedContributionInfo> = response.into_json().unwrap();
+    assert!(summary.is_empty());
+
     // Contribute
     let post_chunk = PostChunkRequest::new(ROUND_HEIGHT, contribution_locator, contribution_file_signature_locator);
 
@@ -676,3 +754,414 @@ fn test_contribution() {
     assert_eq!(response.status(), Status::Unauthorized);
     assert!(response.body().is_some());
 }
+
+/// Runs the contribution of the participant holding the given locators through the REST API, then verifies it.
+/// Returns the challenge of the contribution.
+fn contribute_and_verify(
+    client: &Client,
+    ctx: &TestCtx,
+    participant: &TestParticipant,
+    locked_locators: &LockedLocators,
+) -> Vec<u8> {
+    let challenge = contribute(client, participant, locked_locators);
+
+    // Verify chunk
+    let mut req = client.get("/verify");
+    req = set_request::<()>(req, &ctx.coordinator.keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    challenge
+}
+
+/// Runs the contribution of the participant holding the given locators through the REST API, leaving it pending
+/// verification. Returns the challenge of the contribution.
+fn contribute(client: &Client, participant: &TestParticipant, locked_locators: &LockedLocators) -> Vec<u8> {
+    contribute_with_validity(client, participant, locked_locators, true)
+}
+
+/// Same as [`contribute`], tampering with the parameters of the contribution if it must be invalid.
+fn contribute_with_validity(
+    client: &Client,
+    participant: &TestParticipant,
+    locked_locators: &LockedLocators,
+    valid: bool,
+) -> Vec<u8> {
+    use setup_utils::calculate_hash;
+
+    let reqwest_client = reqwest::blocking::Client::new();
+    let contribution_locator = locked_locators.next_contribution();
+    let round_height = contribution_locator.round_height();
+
+    // Get challenge
+    let mut req = client.post("/contributor/challenge");
+    req = set_request::<u64>(req, &participant.keypair, Some(&round_height));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let challenge_url: String = response.into_json().unwrap();
+    let challenge = reqwest_client
+        .get(challenge_url)
+        .send()
+        .unwrap()
+        .bytes()
+        .unwrap()
+        .to_vec();
+
+    // Compute the contribution
+    let challenge_hash = calculate_hash(challenge.as_ref());
+    let mut contribution = challenge_hash.to_vec();
+    let entropy = RandomSource::Entropy(participant.keypair.pubkey().to_owned());
+    Computation::contribute_circuits(CircuitSet::Test, &challenge, &mut contribution, &entropy).unwrap();
+    if !valid {
+        *contribution.last_mut().unwrap() ^= 1;
+    }
+    let response_hash = calculate_hash(contribution.as_ref());
+
+    let sigkey = participant.keypair.sigkey();
+    let contribution_state = ContributionState::new(challenge_hash.to_vec(), response_hash.to_vec(), None).unwrap();
+    let signature = Production
+        .sign(sigkey, &contribution_state.signature_message().unwrap())
+        .unwrap();
+    let contribution_file_signature = ContributionFileSignature::new(signature, contribution_state).unwrap();
+
+    // Upload contribution and signature
+    req = client.post("/upload/chunk");
+    req = set_request::<u64>(req, &participant.keypair, Some(&round_height));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let (chunk_url, sig_url): (String, String) = response.into_json().unwrap();
+
+    let mut contrib_info = ContributionInfo::default();
+    contrib_info.public_key = participant.keypair.pubkey().to_owned();
+    contrib_info.ceremony_round = round_height;
+    contrib_info.contribution_hash = hex::encode(calculate_hash(&contribution[64..]));
+    contrib_info.contribution_hash_signature = Production.sign(sigkey, &contrib_info.contribution_hash).unwrap();
+    contrib_info.contribution_file_hash = hex::encode(response_hash);
+    contrib_info.contribution_file_signature = Production.sign(sigkey, &contrib_info.contribution_file_hash).unwrap();
+    contrib_info.try_sign(&participant.keypair).unwrap();
+
+    let response = reqwest_client.put(chunk_url).body(contribution).send().unwrap();
+    assert!(response.status().is_success());
+    let response = reqwest_client
+        .put(sig_url)
+        .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+        .body(serde_json::to_vec(&contribution_file_signature).unwrap())
+        .send()
+        .unwrap();
+    assert!(response.status().is_success());
+
+    // Post contribution info
+    req = client.post("/contributor/contribution_info");
+    req = set_request::<ContributionInfo>(req, &participant.keypair, Some(&contrib_info));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    // Contribute
+    let post_chunk = PostChunkRequest::new(
+        round_height,
+        contribution_locator,
+        locked_locators.next_contribution_file_signature(),
+    );
+    req = client.post("/contributor/contribute_chunk");
+    req = set_request::<PostChunkRequest>(req, &participant.keypair, Some(&post_chunk));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    challenge
+}
+
+/// Test two contributors contributing in sequence in the same round.
+#[test]
+fn test_sequential_contributions() {
+    use setup_utils::calculate_hash;
+
+    let ctx = build_context_with_contributors(2);
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    // The first contributor builds on top of the initial challenge of the round
+    let locked_locators = ctx.contributors[0].locked_locators.as_ref().unwrap();
+    assert_eq!(locked_locators.next_contribution().contribution_id(), 1);
+    contribute_and_verify(&client, &ctx, &ctx.contributors[0], locked_locators);
+
+    // The receipt of the first contributor is available before the end of the round
+    let mut req = client.post("/contributor/receipt");
+    req = set_request::<u64>(req, &ctx.contributors[0].keypair, Some(&ROUND_HEIGHT));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let receipt: ContributionReceipt = response.into_json().unwrap();
+    assert!(receipt.verify_signature().unwrap());
+
+    // The second contributor builds on top of the verified contribution of the first one, in the same round
+    req = client.get("/contributor/lock_chunk");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let locked_chunk: LockedChunk = response.into_json().unwrap();
+    let locked_locators = locked_chunk.locked_locators;
+    assert_eq!(locked_locators.next_contribution().round_height(), ROUND_HEIGHT);
+    assert_eq!(locked_locators.next_contribution().contribution_id(), 2);
+
+    let challenge = contribute_and_verify(&client, &ctx, &ctx.contributors[1], &locked_locators);
+    assert_eq!(hex::encode(calculate_hash(&challenge)), receipt.next_challenge_hash);
+
+    // Both contributions are recorded in the summary
+    req = client.get("/contribution_info");
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let summary: Vec<TrimmedContributionInfo> = response.into_json().unwrap();
+    assert_eq!(summary.len(), 2);
+    assert_eq!(summary[0].public_key(), ctx.contributors[0].keypair.pubkey());
+    assert_eq!(summary[1].public_key(), ctx.contributors[1].keypair.pubkey());
+    assert!(summary.iter().all(|c| c.ceremony_round() == ROUND_HEIGHT));
+
+    // The receipt of the second contributor is built on the challenge of the next round
+    req = client.post("/contributor/receipt");
+    req = set_request::<u64>(req, &ctx.contributors[1].keypair, Some(&ROUND_HEIGHT));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let last_receipt: ContributionReceipt = response.into_json().unwrap();
+    assert_ne!(last_receipt.next_challenge_hash, receipt.next_challenge_hash);
+}
+
+/// Test the next contributor of the round computing on top of the previous contribution while it is verified.
+#[test]
+fn test_speculative_challenge() {
+    let ctx = build_context_with_contributors(2);
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+    let reqwest_client = reqwest::blocking::Client::new();
+
+    // The second contributor waits for the contribution of the first one, which has not been uploaded yet
+    let mut req = client.get("/contributor/queue_status");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    match response.into_json::<ContributorStatus>().unwrap() {
+        ContributorStatus::Waiting => (),
+        _ => panic!("Wrong ContributorStatus"),
+    }
+
+    req = client.get("/contributor/speculative_challenge");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    // Once uploaded, the contribution of the first contributor can be built upon before its verification
+    let locked_locators = ctx.contributors[0].locked_locators.as_ref().unwrap();
+    contribute(&client, &ctx.contributors[0], locked_locators);
+
+    req = client.get("/contributor/speculative_challenge");
+    req = set_request::<()>(req, &ctx.unknown_participant.keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    req = client.get("/contributor/speculative_challenge");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let speculative_challenge: SpeculativeChallenge = response.into_json().unwrap();
+    assert_eq!(speculative_challenge.round_height, ROUND_HEIGHT);
+    let speculative_challenge = reqwest_client
+        .get(speculative_challenge.url)
+        .send()
+        .unwrap()
+        .bytes()
+        .unwrap()
+        .to_vec();
+
+    // The challenge is built once, further requests return the same one
+    req = client.get("/contributor/speculative_challenge");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let speculative_challenge_url = response.into_json::<SpeculativeChallenge>().unwrap().url;
+    let same_challenge = reqwest_client
+        .get(speculative_challenge_url)
+        .send()
+        .unwrap()
+        .bytes()
+        .unwrap()
+        .to_vec();
+    assert_eq!(same_challenge, speculative_challenge);
+
+    // After the verification, the challenge of the second contributor is the speculative one
+    req = client.get("/verify");
+    req = set_request::<()>(req, &ctx.coordinator.keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    req = client.get("/contributor/queue_status");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    match response.into_json::<ContributorStatus>().unwrap() {
+        ContributorStatus::Round => (),
+        _ => panic!("Wrong ContributorStatus"),
+    }
+
+    req = client.get("/contributor/lock_chunk");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    req = client.post("/contributor/challenge");
+    req = set_request::<u64>(req, &ctx.contributors[1].keypair, Some(&ROUND_HEIGHT));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let challenge_url: String = response.into_json().unwrap();
+    let challenge = reqwest_client
+        .get(challenge_url)
+        .send()
+        .unwrap()
+        .bytes()
+        .unwrap()
+        .to_vec();
+    assert_eq!(challenge, speculative_challenge);
+}
+
+/// Test an invalid contribution in the middle of a round: only the invalid contribution is dropped, and the next
+/// contributor builds on top of the last verified one.
+#[test]
+fn test_invalid_contribution() {
+    use setup_utils::calculate_hash;
+
+    let ctx = build_context_with_contributors(3);
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    let locked_locators = ctx.contributors[0].locked_locators.as_ref().unwrap();
+    contribute_and_verify(&client, &ctx, &ctx.contributors[0], locked_locators);
+    let mut req = client.post("/contributor/receipt");
+    req = set_request::<u64>(req, &ctx.contributors[0].keypair, Some(&ROUND_HEIGHT));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let receipt: ContributionReceipt = response.into_json().unwrap();
+
+    // The second contribution fails its verification
+    req = client.get("/contributor/lock_chunk");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let locked_locators = response.into_json::<LockedChunk>().unwrap().locked_locators;
+    assert_eq!(locked_locators.next_contribution().contribution_id(), 2);
+    contribute_with_validity(&client, &ctx.contributors[1], &locked_locators, false);
+
+    req = client.get("/verify");
+    req = set_request::<()>(req, &ctx.coordinator.keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    req = client.get("/contributor/queue_status");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    match response.into_json::<ContributorStatus>().unwrap() {
+        ContributorStatus::Banned => (),
+        _ => panic!("Wrong ContributorStatus"),
+    }
+
+    // The first contribution is kept, with its receipt and its info
+    req = client.post("/contributor/receipt");
+    req = set_request::<u64>(req, &ctx.contributors[0].keypair, Some(&ROUND_HEIGHT));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let kept_receipt: ContributionReceipt = response.into_json().unwrap();
+    assert_eq!(kept_receipt.next_challenge_hash, receipt.next_challenge_hash);
+
+    req = client.get("/contribution_info");
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let summary: Vec<TrimmedContributionInfo> = response.into_json().unwrap();
+    assert_eq!(summary.len(), 1);
+    assert_eq!(summary[0].public_key(), ctx.contributors[0].keypair.pubkey());
+
+    // The third contributor takes the place of the second one, on top of the first contribution
+    req = client.get("/contributor/queue_status");
+    req = set_request::<()>(req, &ctx.contributors[2].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    match response.into_json::<ContributorStatus>().unwrap() {
+        ContributorStatus::Round => (),
+        _ => panic!("Wrong ContributorStatus"),
+    }
+
+    req = client.get("/contributor/lock_chunk");
+    req = set_request::<()>(req, &ctx.contributors[2].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let locked_locators = response.into_json::<LockedChunk>().unwrap().locked_locators;
+    assert_eq!(locked_locators.next_contribution().round_height(), ROUND_HEIGHT);
+    assert_eq!(locked_locators.next_contribution().contribution_id(), 2);
+
+    let challenge = contribute_and_verify(&client, &ctx, &ctx.contributors[2], &locked_locators);
+    assert_eq!(hex::encode(calculate_hash(&challenge)), receipt.next_challenge_hash);
+
+    // The round ends with the two valid contributions
+    req = client.get("/contribution_info");
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let summary: Vec<TrimmedContributionInfo> = response.into_json().unwrap();
+    assert_eq!(summary.len(), 2);
+    assert_eq!(summary[0].public_key(), ctx.contributors[0].keypair.pubkey());
+    assert_eq!(summary[1].public_key(), ctx.contributors[2].keypair.pubkey());
+
+    req = client.post("/contributor/receipt");
+    req = set_request::<u64>(req, &ctx.contributors[2].keypair, Some(&ROUND_HEIGHT));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+}
+
+/// Waits for a trigger of the coordinator, checking that it was fired well before the periodic sweep.
+fn assert_triggered(runtime: &Runtime, requested: impl Future<Output = ()>) {
+    let start = Instant::now();
+    runtime.block_on(requested);
+    assert!(start.elapsed() < SWEEP_TIME / 2);
+}
+
+/// Test the requests waking up the background tasks of the coordinator, instead of waiting for the periodic sweep.
+#[test]
+fn test_triggers() {
+    let ctx = build_context_with_contributors(2);
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+    let coordinator = client.rocket().state::<Arc<RwLock<Coordinator>>>().unwrap().clone();
+    let triggers = client.rocket().state::<Arc<Triggers>>().unwrap().clone();
+    let runtime = Runtime::new().unwrap();
+
+    // A new participant in the queue wakes up the update
+    let socket_address = SocketAddr::new(ctx.unknown_participant.address, 8080);
+    let mut req = client.post("/contributor/join_queue").remote(socket_address);
+    req = set_request::<String>(
+        req,
+        &ctx.unknown_participant.keypair,
+        Some(&format!("7fe7c70eda056784fcf4")),
+    );
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_triggered(&runtime, triggers.update_requested(SWEEP_TIME));
+
+    // An uploaded contribution is verified right away, then the update is woken up
+    runtime.spawn(rest::verify_contributions(coordinator, triggers.clone()));
+    let start = Instant::now();
+    let locked_locators = ctx.contributors[0].locked_locators.as_ref().unwrap();
+    contribute(&client, &ctx.contributors[0], locked_locators);
+
+    loop {
+        req = client.get("/contributor/queue_status");
+        req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+        let response = req.dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        match response.into_json::<ContributorStatus>().unwrap() {
+            ContributorStatus::Round => break,
+            ContributorStatus::Waiting => (),
+            _ => panic!("Wrong ContributorStatus"),
+        }
+        assert!(start.elapsed() < SWEEP_TIME / 2);
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    assert_triggered(&runtime, triggers.update_requested(SWEEP_TIME));
+
+    // A new lock wakes up the update to reschedule it for the deadline of the lock
+    req = client.get("/contributor/lock_chunk");
+    req = set_request::<()>(req, &ctx.contributors[1].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_triggered(&runtime, triggers.update_requested(SWEEP_TIME));
+}
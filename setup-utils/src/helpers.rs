@@ -121,6 +121,24 @@ pub fn user_system_randomness() -> Vec<u8> {
     arr.to_vec()
 }
 
+/// Number of SHA-256 iterations applied to the beacon by [`beacon_randomness`].
+#[cfg(not(feature = "wasm"))]
+pub const BEACON_HASH_ITERATIONS: u64 = 1 << 10;
+
+/// Applies `iterations` SHA-256 iterations over the beacon hash, as [`beacon_randomness`] does, without printing the
+/// interstitial states.
+#[cfg(not(feature = "wasm"))]
+pub fn iterate_beacon_hash(mut beacon_hash: [u8; 32], iterations: u64) -> [u8; 32] {
+    for _ in 0..iterations {
+        let mut h = Sha256::new();
+        h.update(&beacon_hash);
+        let result = h.finalize();
+        beacon_hash.copy_from_slice(&result);
+    }
+
+    beacon_hash
+}
+
 #[allow(clippy::modulo_one)]
 #[cfg(not(feature = "wasm"))]
 pub fn beacon_randomness(mut beacon_hash: [u8; 32]) -> [u8; 32] {
@@ -283,6 +301,17 @@ mod tests {
         );
     }
 
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn test_iterate_beacon_hash() {
+        let beacon = [7; 32];
+        assert_eq!(iterate_beacon_hash(beacon, 0), beacon);
+        assert_eq!(
+            iterate_beacon_hash(beacon, BEACON_HASH_ITERATIONS),
+            beacon_randomness(beacon)
+        );
+    }
+
     #[test]
     fn test_same_ratio() {
         let rng = &mut thread_rng();